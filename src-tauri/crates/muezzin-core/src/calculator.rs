@@ -0,0 +1,187 @@
+use salah::prelude::*;
+use chrono::{DateTime, Local, Datelike, NaiveDate, NaiveTime};
+use chrono_tz::Tz;
+
+use crate::models::{PrayerTimes, CalculationSettings, CustomTimes, JumuahTime};
+use crate::error::{Error, Result};
+use crate::qibla;
+
+pub struct PrayerCalculator {
+    coordinates: Option<Coordinates>,
+    config: Option<Configuration>,
+    timezone: Tz,
+    custom_times: Option<CustomTimes>,
+    jumuah_time: Option<JumuahTime>,
+}
+
+impl Default for PrayerCalculator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PrayerCalculator {
+    pub fn new() -> Self {
+        Self {
+            coordinates: None,
+            config: None,
+            timezone: chrono_tz::UTC,
+            custom_times: None,
+            jumuah_time: None,
+        }
+    }
+
+    pub fn update_settings(
+        &mut self,
+        lat: f64,
+        lon: f64,
+        calc_settings: &CalculationSettings,
+        timezone: Tz,
+        custom_times: Option<CustomTimes>,
+        jumuah_time: Option<JumuahTime>,
+    ) -> Result<()> {
+        self.coordinates = Some(Coordinates::new(lat, lon));
+        self.timezone = timezone;
+        self.custom_times = custom_times;
+        self.jumuah_time = jumuah_time;
+        
+        // Map calculation method
+        let method = match calc_settings.calc_method.as_str() {
+            "MWL" => Method::MuslimWorldLeague,
+            "Egyptian" => Method::Egyptian,
+            "Karachi" => Method::Karachi,
+            "UAQ" => Method::UmmAlQura,
+            "Dubai" => Method::Dubai,
+            "Qatar" => Method::Qatar,
+            "Kuwait" => Method::Kuwait,
+            "MC" => Method::MoonsightingCommittee,
+            "Singapore" => Method::Singapore,
+            "Turkey" => Method::Turkey,
+            "Tehran" => Method::Tehran,
+            "ISNA" => Method::NorthAmerica,
+            _ => Method::MuslimWorldLeague,
+        };
+
+        let mut config = Configuration::with(method);
+        
+        // Set madhab
+        config.madhab = match calc_settings.madhab.as_str() {
+            "Hanafi" => Madhab::Hanafi,
+            _ => Madhab::Shafi,
+        };
+
+        // Apply adjustments if any
+        if let Some(ref adj) = calc_settings.adjustments {
+            config.fajr_offset = adj.fajr;
+            config.dhuhr_offset = adj.dhuhr;
+            config.asr_offset = adj.asr;
+            config.maghrib_offset = adj.maghrib;
+            config.isha_offset = adj.isha;
+        }
+
+        self.config = Some(config);
+        Ok(())
+    }
+
+    pub fn calculate_prayer_times(&self, date: DateTime<Local>) -> Result<PrayerTimes> {
+        let coords = self.coordinates.ok_or(Error::NotInitialized)?;
+        let config = self.config.as_ref().ok_or(Error::NotInitialized)?;
+
+        let prayers = salah::PrayerSchedule::new()
+            .on(date.date_naive())
+            .for_location(coords)
+            .with_configuration(config.clone())
+            .calculate()
+            .map_err(|e| Error::Prayer(e.to_string()))?;
+
+        let mut result = PrayerTimes {
+            fajr: prayers.time(Prayer::Fajr).with_timezone(&Local),
+            sunrise: prayers.time(Prayer::Sunrise).with_timezone(&Local),
+            dhuhr: prayers.time(Prayer::Dhuhr).with_timezone(&Local),
+            asr: prayers.time(Prayer::Asr).with_timezone(&Local),
+            maghrib: prayers.time(Prayer::Maghrib).with_timezone(&Local),
+            isha: prayers.time(Prayer::Isha).with_timezone(&Local),
+        };
+
+        // Apply custom times if enabled and it's today
+        if let Some(ref custom) = self.custom_times {
+            if custom.enabled && date.date_naive() == Local::now().date_naive() {
+                if let Some(ref time_str) = custom.fajr {
+                    if let Ok(time) = NaiveTime::parse_from_str(time_str, "%H:%M") {
+                        result.fajr = self.at(date.date_naive(), time).unwrap_or(result.fajr);
+                    }
+                }
+                if let Some(ref time_str) = custom.dhuhr {
+                    if let Ok(time) = NaiveTime::parse_from_str(time_str, "%H:%M") {
+                        result.dhuhr = self.at(date.date_naive(), time).unwrap_or(result.dhuhr);
+                    }
+                }
+                if let Some(ref time_str) = custom.asr {
+                    if let Ok(time) = NaiveTime::parse_from_str(time_str, "%H:%M") {
+                        result.asr = self.at(date.date_naive(), time).unwrap_or(result.asr);
+                    }
+                }
+                if let Some(ref time_str) = custom.maghrib {
+                    if let Ok(time) = NaiveTime::parse_from_str(time_str, "%H:%M") {
+                        result.maghrib = self.at(date.date_naive(), time).unwrap_or(result.maghrib);
+                    }
+                }
+                if let Some(ref time_str) = custom.isha {
+                    if let Ok(time) = NaiveTime::parse_from_str(time_str, "%H:%M") {
+                        result.isha = self.at(date.date_naive(), time).unwrap_or(result.isha);
+                    }
+                }
+            }
+        }
+
+        // Apply Jumuah time if it's Friday
+        if let Some(ref jumuah) = self.jumuah_time {
+            if jumuah.enabled && date.weekday() == chrono::Weekday::Fri {
+                if let Ok(time) = NaiveTime::parse_from_str(&jumuah.time, "%H:%M") {
+                    result.dhuhr = self.at(date.date_naive(), time).unwrap_or(result.dhuhr);
+                }
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// `time` on `date` in the location's timezone
+    fn at(&self, date: NaiveDate, time: NaiveTime) -> Option<DateTime<Local>> {
+        date.and_time(time)
+            .and_local_timezone(self.timezone)
+            .earliest()
+            .map(|t| t.with_timezone(&Local))
+    }
+
+    pub fn get_next_prayer(&self) -> Result<(String, DateTime<Local>)> {
+        self.get_prayer_after(Local::now())
+    }
+
+    /// First prayer (sunrise excluded) strictly after `time`
+    pub fn get_prayer_after(&self, time: DateTime<Local>) -> Result<(String, DateTime<Local>)> {
+        let times = self.calculate_prayer_times(time)?;
+
+        if time < times.fajr {
+            Ok(("Fajr".to_string(), times.fajr))
+        } else if time < times.dhuhr {
+            Ok(("Dhuhr".to_string(), times.dhuhr))
+        } else if time < times.asr {
+            Ok(("Asr".to_string(), times.asr))
+        } else if time < times.maghrib {
+            Ok(("Maghrib".to_string(), times.maghrib))
+        } else if time < times.isha {
+            Ok(("Isha".to_string(), times.isha))
+        } else {
+            // Next is Fajr tomorrow
+            let tomorrow = time + chrono::Duration::days(1);
+            let tomorrow_times = self.calculate_prayer_times(tomorrow)?;
+            Ok(("Fajr".to_string(), tomorrow_times.fajr))
+        }
+    }
+
+    pub fn get_qibla_direction(&self) -> Result<f64> {
+        let coords = self.coordinates.ok_or(Error::NotInitialized)?;
+        Ok(qibla::bearing(coords.latitude, coords.longitude))
+    }
+}
@@ -0,0 +1,16 @@
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    /// No location or calculation method has been set yet
+    #[error("Not initialized")]
+    NotInitialized,
+
+    #[error("Prayer calculation error: {0}")]
+    Prayer(String),
+
+    #[error("{0}")]
+    InvalidInput(String),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
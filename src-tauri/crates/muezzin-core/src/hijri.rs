@@ -0,0 +1,92 @@
+//! Hijri dates from the tabular (arithmetical) Islamic calendar.
+//!
+//! The tabular calendar can be a day off the sighted or Umm al-Qura one, so
+//! callers pass an adjustment in days, like the Electron app's `hijriAdj`.
+
+use chrono::{Datelike, Duration, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+/// Julian day number of 0001-01-01 minus one, so that
+/// `num_days_from_ce() + JDN_OFFSET` is the Julian day number
+const JDN_OFFSET: i32 = 1_721_425;
+
+pub const MONTH_NAMES: [&str; 12] = [
+    "Muharram",
+    "Safar",
+    "Rabi al-Awwal",
+    "Rabi al-Thani",
+    "Jumada al-Awwal",
+    "Jumada al-Thani",
+    "Rajab",
+    "Shaban",
+    "Ramadan",
+    "Shawwal",
+    "Dhu al-Qadah",
+    "Dhu al-Hijjah",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HijriDate {
+    pub year: i32,
+    pub month: u32,
+    pub day: u32,
+}
+
+impl HijriDate {
+    pub fn from_gregorian(date: NaiveDate, adjustment: i32) -> Self {
+        let date = date + Duration::days(adjustment as i64);
+        let jdn = date.num_days_from_ce() + JDN_OFFSET;
+
+        // Civil epoch (16 July 622), 30-year cycle with 11 leap years
+        let l = jdn - 1_948_440 + 10_632;
+        let n = (l - 1) / 10_631;
+        let l = l - 10_631 * n + 354;
+        let j = ((10_985 - l) / 5_316) * ((50 * l) / 17_719) + (l / 5_670) * ((43 * l) / 15_238);
+        let l = l - ((30 - j) / 15) * ((17_719 * j) / 50) - (j / 16) * ((15_238 * j) / 43) + 29;
+        let month = (24 * l) / 709;
+        let day = l - (709 * month) / 24;
+        let year = 30 * n + j - 30;
+
+        Self { year, month: month as u32, day: day as u32 }
+    }
+
+    pub fn month_name(&self) -> &'static str {
+        MONTH_NAMES[(self.month as usize).clamp(1, 12) - 1]
+    }
+}
+
+impl std::fmt::Display for HijriDate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {} {} AH", self.day, self.month_name(), self.year)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hijri(y: i32, m: u32, d: u32) -> HijriDate {
+        HijriDate::from_gregorian(NaiveDate::from_ymd_opt(y, m, d).unwrap(), 0)
+    }
+
+    #[test]
+    fn known_dates() {
+        assert_eq!(hijri(2000, 1, 1), HijriDate { year: 1420, month: 9, day: 24 });
+        assert_eq!(hijri(2025, 3, 1), HijriDate { year: 1446, month: 9, day: 1 });
+        assert_eq!(hijri(2025, 3, 31), HijriDate { year: 1446, month: 10, day: 1 });
+    }
+
+    #[test]
+    fn adjustment_shifts_the_day() {
+        let date = NaiveDate::from_ymd_opt(2025, 3, 1).unwrap();
+        assert_eq!(HijriDate::from_gregorian(date, -1), HijriDate { year: 1446, month: 8, day: 29 });
+        assert_eq!(HijriDate::from_gregorian(date, 1).day, 2);
+    }
+
+    #[test]
+    fn displays_month_name() {
+        assert_eq!(hijri(2025, 3, 1).to_string(), "1 Ramadan 1446 AH");
+        assert_eq!(hijri(2024, 7, 8).month_name(), "Muharram");
+    }
+}
@@ -0,0 +1,26 @@
+//! Prayer time, Qibla and Hijri calculations behind Muezzin, with no UI or
+//! Tauri dependency. The app, `muezzin-cli` and `muezzin-daemon` all build on
+//! it.
+//!
+//! ```no_run
+//! use muezzin_core::{models::CalculationSettings, timetable, PrayerCalculator};
+//!
+//! let mut calculator = PrayerCalculator::new();
+//! calculator.update_settings(21.4225, 39.8262, &CalculationSettings::default(), chrono_tz::Asia::Riyadh, None, None)?;
+//! for day in timetable::month(&calculator, 2025, 3, 0)? {
+//!     println!("{} ({}): Fajr {}", day.date, day.hijri, day.times.fajr.format("%H:%M"));
+//! }
+//! println!("Qibla: {:.1}°", muezzin_core::qibla::bearing(51.5074, -0.1278));
+//! # Ok::<(), muezzin_core::Error>(())
+//! ```
+
+pub mod calculator;
+pub mod error;
+pub mod hijri;
+pub mod models;
+pub mod qibla;
+pub mod timetable;
+
+pub use calculator::PrayerCalculator;
+pub use error::{Error, Result};
+pub use hijri::HijriDate;
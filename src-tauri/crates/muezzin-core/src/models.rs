@@ -0,0 +1,64 @@
+//! Calculation inputs and results, serialized the same way the app stores
+//! and sends them.
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrayerTimes {
+    pub fajr: DateTime<Local>,
+    pub sunrise: DateTime<Local>,
+    pub dhuhr: DateTime<Local>,
+    pub asr: DateTime<Local>,
+    pub maghrib: DateTime<Local>,
+    pub isha: DateTime<Local>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CalculationSettings {
+    pub calc_method: String,
+    pub madhab: String,
+    pub hlr: String,
+    pub pcr: String,
+    pub shafaq: String,
+    pub adjustments: Option<Adjustments>,
+}
+
+impl Default for CalculationSettings {
+    fn default() -> Self {
+        Self {
+            calc_method: "MWL".to_string(),
+            madhab: "Shafi".to_string(),
+            hlr: "TA".to_string(),
+            pcr: "CC".to_string(),
+            shafaq: "shafaqG".to_string(),
+            adjustments: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Adjustments {
+    pub fajr: i32,
+    pub dhuhr: i32,
+    pub asr: i32,
+    pub maghrib: i32,
+    pub isha: i32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CustomTimes {
+    pub enabled: bool,
+    pub fajr: Option<String>,
+    pub dhuhr: Option<String>,
+    pub asr: Option<String>,
+    pub maghrib: Option<String>,
+    pub isha: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JumuahTime {
+    pub enabled: bool,
+    pub time: String,
+}
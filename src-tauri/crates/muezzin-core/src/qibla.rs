@@ -0,0 +1,40 @@
+//! Direction of the Kaaba along the great circle.
+
+/// Coordinates of the Kaaba in Mecca
+pub const KAABA: (f64, f64) = (21.4225241, 39.8261818);
+
+/// Initial great-circle bearing from the given point to the Kaaba, in degrees
+/// clockwise from true north
+pub fn bearing(latitude: f64, longitude: f64) -> f64 {
+    let (kaaba_lat, kaaba_lon) = (KAABA.0.to_radians(), KAABA.1.to_radians());
+    let lat = latitude.to_radians();
+    let delta_lon = kaaba_lon - longitude.to_radians();
+
+    let y = delta_lon.sin();
+    let x = lat.cos() * kaaba_lat.tan() - lat.sin() * delta_lon.cos();
+    y.atan2(x).to_degrees().rem_euclid(360.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 0.1, "{} is not close to {}", actual, expected);
+    }
+
+    #[test]
+    fn known_bearings() {
+        assert_close(bearing(51.5074, -0.1278), 118.99);
+        assert_close(bearing(40.7128, -74.0060), 58.48);
+        assert_close(bearing(-33.8688, 151.2093), 277.50);
+    }
+
+    #[test]
+    fn bearing_is_within_a_full_turn() {
+        for (lat, lon) in [(0.0, 0.0), (64.1, -21.9), (-54.8, -68.3), (35.7, 139.7)] {
+            let b = bearing(lat, lon);
+            assert!((0.0..360.0).contains(&b), "{}", b);
+        }
+    }
+}
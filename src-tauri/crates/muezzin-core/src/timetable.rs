@@ -0,0 +1,118 @@
+//! Prayer times over a run of days, e.g. a month for a printed timetable.
+
+use chrono::{DateTime, Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+use crate::calculator::PrayerCalculator;
+use crate::error::{Error, Result};
+use crate::hijri::HijriDate;
+use crate::models::PrayerTimes;
+
+/// Longest range a timetable can cover
+pub const MAX_DAYS: i64 = 366;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimetableDay {
+    pub date: NaiveDate,
+    pub hijri: HijriDate,
+    pub times: PrayerTimes,
+}
+
+/// Noon avoids landing on the wrong day around DST changes
+fn at_noon(date: NaiveDate) -> Result<DateTime<Local>> {
+    date.and_hms_opt(12, 0, 0)
+        .and_then(|t| t.and_local_timezone(Local).earliest())
+        .ok_or_else(|| Error::InvalidInput(format!("Invalid date: {}", date)))
+}
+
+pub fn day(calculator: &PrayerCalculator, date: NaiveDate, hijri_adjustment: i32) -> Result<TimetableDay> {
+    Ok(TimetableDay {
+        date,
+        hijri: HijriDate::from_gregorian(date, hijri_adjustment),
+        times: calculator.calculate_prayer_times(at_noon(date)?)?,
+    })
+}
+
+/// Every day from `from` to `to`, both included
+pub fn range(calculator: &PrayerCalculator, from: NaiveDate, to: NaiveDate, hijri_adjustment: i32) -> Result<Vec<TimetableDay>> {
+    if to < from {
+        return Err(Error::InvalidInput(format!("{} is before {}", to, from)));
+    }
+    if (to - from).num_days() >= MAX_DAYS {
+        return Err(Error::InvalidInput(format!("A timetable can span at most {} days", MAX_DAYS)));
+    }
+
+    from.iter_days()
+        .take_while(|d| *d <= to)
+        .map(|date| day(calculator, date, hijri_adjustment))
+        .collect()
+}
+
+pub fn month(calculator: &PrayerCalculator, year: i32, month: u32, hijri_adjustment: i32) -> Result<Vec<TimetableDay>> {
+    let invalid = || Error::InvalidInput(format!("Invalid month: {}-{}", year, month));
+    let first = NaiveDate::from_ymd_opt(year, month, 1).ok_or_else(invalid)?;
+    let next = if month == 12 {
+        NaiveDate::from_ymd_opt(year + 1, 1, 1)
+    } else {
+        NaiveDate::from_ymd_opt(year, month + 1, 1)
+    }
+    .ok_or_else(invalid)?;
+
+    range(calculator, first, next.pred_opt().ok_or_else(invalid)?, hijri_adjustment)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::CalculationSettings;
+
+    fn calculator() -> PrayerCalculator {
+        let mut calculator = PrayerCalculator::new();
+        calculator
+            .update_settings(21.4225, 39.8262, &CalculationSettings::default(), chrono_tz::Asia::Riyadh, None, None)
+            .unwrap();
+        calculator
+    }
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn month_covers_every_day() {
+        let days = month(&calculator(), 2024, 2, 0).unwrap();
+        assert_eq!(days.len(), 29);
+        assert_eq!(days[0].date, date(2024, 2, 1));
+        assert_eq!(days[28].date, date(2024, 2, 29));
+        assert_eq!(month(&calculator(), 2025, 12, 0).unwrap().len(), 31);
+    }
+
+    #[test]
+    fn days_carry_their_hijri_date() {
+        let days = range(&calculator(), date(2025, 2, 28), date(2025, 3, 1), 0).unwrap();
+        assert_eq!(days[0].hijri, HijriDate { year: 1446, month: 8, day: 29 });
+        assert_eq!(days[1].hijri, HijriDate { year: 1446, month: 9, day: 1 });
+    }
+
+    #[test]
+    fn prayers_are_in_order() {
+        for day in range(&calculator(), date(2025, 6, 1), date(2025, 6, 7), 0).unwrap() {
+            let t = &day.times;
+            assert!(t.fajr < t.sunrise && t.sunrise < t.dhuhr && t.dhuhr < t.asr && t.asr < t.maghrib && t.maghrib < t.isha);
+        }
+    }
+
+    #[test]
+    fn rejects_bad_ranges() {
+        assert!(matches!(range(&calculator(), date(2025, 3, 2), date(2025, 3, 1), 0), Err(Error::InvalidInput(_))));
+        assert!(matches!(range(&calculator(), date(2025, 1, 1), date(2026, 1, 2), 0), Err(Error::InvalidInput(_))));
+        assert!(matches!(month(&calculator(), 2025, 13, 0), Err(Error::InvalidInput(_))));
+    }
+
+    #[test]
+    fn uninitialized_calculator_fails() {
+        let result = day(&PrayerCalculator::new(), date(2025, 3, 1), 0);
+        assert!(matches!(result, Err(Error::NotInitialized)));
+    }
+}
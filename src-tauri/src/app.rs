@@ -0,0 +1,232 @@
+//! The window: Tauri's plugins, the shared [`AppState`], the tray and the
+//! commands the frontend invokes.
+
+use tauri::Manager;
+use tauri_plugin_autostart::MacosLauncher;
+use std::sync::Arc;
+use parking_lot::RwLock;
+
+use crate::{audio, i18n, kiosk, prayer_times, tray, commands};
+use crate::storage::AppStorage;
+use muezzin_core::PrayerCalculator;
+use crate::audio::AudioPlayer;
+use crate::events::EventBus;
+use crate::hooks::Hooks;
+use crate::kiosk::Kiosk;
+use crate::media_pause::MediaPause;
+use crate::http_api::HttpApi;
+use crate::mqtt::Mqtt;
+use crate::multi_room::MultiRoom;
+use crate::quran::QuranText;
+use crate::quran_audio::QuranAudioService;
+use crate::quran_packs::QuranPacks;
+use crate::tracker::PrayerTracker;
+
+pub struct AppState {
+    pub(crate) storage: Arc<AppStorage>,
+    pub(crate) calculator: Arc<RwLock<PrayerCalculator>>,
+    pub(crate) audio_player: Arc<RwLock<AudioPlayer>>,
+    pub(crate) quran: Arc<QuranText>,
+    pub(crate) quran_audio: Arc<QuranAudioService>,
+    pub(crate) quran_packs: Arc<QuranPacks>,
+    pub(crate) tracker: Arc<PrayerTracker>,
+    pub(crate) events: Arc<EventBus>,
+    pub(crate) http_api: Arc<HttpApi>,
+    pub(crate) mqtt: Arc<Mqtt>,
+    pub(crate) hooks: Arc<Hooks>,
+    pub(crate) media: Arc<MediaPause>,
+    pub(crate) multi_room: Arc<MultiRoom>,
+    pub(crate) kiosk: Arc<Kiosk>,
+}
+
+/// Runs the app, from within the Tokio runtime
+pub fn run() {
+    tauri::Builder::default()
+        .plugin(tauri_plugin_store::Builder::default().build())
+        .plugin(tauri_plugin_notification::init())
+        .plugin(tauri_plugin_dialog::init())
+        .plugin(tauri_plugin_shell::init())
+        .plugin(tauri_plugin_autostart::init(
+            MacosLauncher::LaunchAgent,
+            Some(vec!["--minimized"]),
+        ))
+        .setup(|app| {
+            let storage = Arc::new(AppStorage::new(app.handle()).expect("Failed to initialize storage"));
+            let calculator = Arc::new(RwLock::new(PrayerCalculator::new()));
+            let audio_player = Arc::new(RwLock::new(AudioPlayer::new().expect("Failed to initialize audio")));
+            let quran = Arc::new(QuranText::load().expect("Failed to load Quran text"));
+            let quran_audio = Arc::new(QuranAudioService::new(app.handle(), &quran).expect("Failed to initialize Quran audio"));
+            let quran_packs = Arc::new(QuranPacks::new(app.handle(), &quran).expect("Failed to initialize Quran packs"));
+            let tracker = Arc::new(PrayerTracker::new(app.handle()).expect("Failed to open prayer tracker"));
+            let events = Arc::new(EventBus::new());
+            let http_api = Arc::new(HttpApi::new());
+            let mqtt = Arc::new(Mqtt::new());
+            let multi_room = Arc::new(MultiRoom::new());
+
+            let settings = storage.get_settings().ok();
+            if let Some(ref settings) = settings {
+                i18n::set_language(&settings.language);
+            }
+
+            app.manage(AppState {
+                storage,
+                calculator,
+                audio_player: audio_player.clone(),
+                quran,
+                quran_audio,
+                quran_packs,
+                tracker,
+                events: events.clone(),
+                http_api: http_api.clone(),
+                mqtt: mqtt.clone(),
+                hooks: Arc::new(Hooks::new()),
+                media: Arc::new(MediaPause::new()),
+                multi_room: multi_room.clone(),
+                kiosk: Arc::new(Kiosk::new()),
+            });
+
+            // Start the local HTTP API, MQTT client and multi-room link if they were left on
+            if let Some(settings) = settings {
+                if settings.http_api.enabled {
+                    if let Err(e) = http_api.apply(app.handle(), &settings.http_api) {
+                        eprintln!("Error starting HTTP API: {}", e);
+                    }
+                }
+                mqtt.apply(Arc::new(app.handle().clone()), &events, &settings.mqtt);
+                multi_room.apply(Arc::new(app.handle().clone()), &events, &settings.multi_room);
+
+                // Straight onto the mosque display
+                if settings.kiosk.enabled {
+                    if let Err(e) = kiosk::set_active(app.handle(), true) {
+                        eprintln!("Error starting kiosk mode: {}", e);
+                    }
+                }
+            }
+
+            // Setup system tray
+            tray::setup_tray(app.handle()).expect("Failed to setup tray");
+
+            // Forward playback progress to the media player
+            let progress_player = audio_player.clone();
+            let progress_handle = app.handle().clone();
+            tokio::spawn(async move {
+                audio::start_progress_emitter(progress_player, progress_handle).await;
+            });
+
+            // Initialize prayer time checker
+            let handle = app.handle().clone();
+            tokio::spawn(async move {
+                prayer_times::start_prayer_checker(handle).await;
+            });
+
+            Ok(())
+        })
+        .invoke_handler(tauri::generate_handler![
+            commands::get_prayer_times,
+            commands::get_prayer_times_for_date,
+            commands::get_next_prayer,
+            commands::update_location,
+            commands::update_settings,
+            commands::update_custom_times,
+            commands::update_jumuah_time,
+            commands::get_settings,
+            commands::get_location_profiles,
+            commands::save_location_profile,
+            commands::delete_location_profile,
+            commands::switch_location_profile,
+            commands::play_adhan,
+            commands::stop_adhan,
+            commands::pause_adhan,
+            commands::resume_adhan,
+            commands::seek_audio,
+            commands::set_volume,
+            commands::is_audio_playing,
+            commands::get_audio_progress,
+            commands::get_quran_chapters,
+            commands::get_surah,
+            commands::get_verses,
+            commands::search_quran,
+            commands::get_translation_catalog,
+            commands::get_installed_packs,
+            commands::download_quran_pack,
+            commands::import_quran_pack,
+            commands::remove_quran_pack,
+            commands::get_parallel_verses,
+            commands::get_quran_progress,
+            commands::toggle_bookmark,
+            commands::set_verse_note,
+            commands::set_last_read,
+            commands::create_khatm_plan,
+            commands::record_khatm_pages,
+            commands::cancel_khatm_plan,
+            commands::get_khatm_status,
+            commands::export_quran_progress,
+            commands::import_quran_progress,
+            commands::get_recitations,
+            commands::play_quran,
+            commands::stop_quran,
+            commands::get_quran_playback_state,
+            commands::download_surah_audio,
+            commands::clear_quran_audio_cache,
+            commands::mute_adhan,
+            commands::unmute_adhan,
+            commands::get_mute_status,
+            commands::get_qibla_direction,
+            commands::get_http_api_status,
+            commands::get_mqtt_status,
+            commands::get_multi_room_status,
+            commands::get_hook_log,
+            commands::get_kiosk_display,
+            commands::enter_kiosk,
+            commands::exit_kiosk,
+            commands::set_kiosk_pin,
+            commands::test_hook,
+            commands::regenerate_http_api_token,
+            commands::get_backend_language,
+            commands::notification_action,
+            commands::record_prayer,
+            commands::clear_prayer_record,
+            commands::get_prayer_log,
+            commands::get_prayer_streak,
+            commands::get_prayer_stats,
+            commands::get_qada_balance,
+            commands::set_qada_backlog,
+            commands::record_qada,
+            commands::undo_qada,
+            commands::export_prayer_log,
+            commands::export_settings_bundle,
+            commands::preview_settings_bundle,
+            commands::import_settings_bundle,
+            commands::find_electron_config,
+            commands::import_electron_settings,
+            commands::get_storage_report,
+            commands::reset_storage_value,
+            commands::check_for_updates,
+            commands::initialize_first_time,
+        ])
+        .run(tauri::generate_context!())
+        .expect("error while running tauri application");
+}
+
+#[cfg(test)]
+mod tests {
+    /// A command left out of `generate_handler!` still compiles, the
+    /// frontend only finds out when invoking it fails
+    #[test]
+    fn every_command_is_registered() {
+        let app = include_str!("app.rs");
+        let mut lines = include_str!("commands.rs").lines();
+        let mut commands = Vec::new();
+        while let Some(line) = lines.next() {
+            if line.trim() == "#[tauri::command]" {
+                let signature = lines.next().unwrap_or_default();
+                let name = signature.split("fn ").nth(1).and_then(|rest| rest.split(['(', '<']).next());
+                commands.push(name.unwrap_or(signature));
+            }
+        }
+
+        let missing: Vec<&str> = commands.iter().copied().filter(|name| !app.contains(&format!("commands::{},", name))).collect();
+        assert!(commands.len() > 50);
+        assert!(missing.is_empty(), "Not in generate_handler!: {:?}", missing);
+    }
+}
@@ -1,29 +1,59 @@
-use rodio::{Decoder, OutputStream, Sink, OutputStreamHandle};
+use rodio::{Decoder, OutputStream, Sink, OutputStreamHandle, Source};
 use std::fs::File;
 use std::io::BufReader;
 use std::path::{Path, PathBuf};
-
+use std::time::Duration;
+use parking_lot::RwLock;
+#[cfg(feature = "gui")]
+use std::sync::Arc;
+#[cfg(feature = "gui")]
+use tauri::{AppHandle, Emitter};
+
+use crate::checker::AudioSink;
+use crate::models::AudioProgress;
 use crate::error::{AppError, Result};
 
 pub struct AudioPlayer {
-    _stream: OutputStream,
     stream_handle: OutputStreamHandle,
     sink: Option<Sink>,
+    duration: Option<Duration>,
 }
 
 impl AudioPlayer {
     pub fn new() -> Result<Self> {
-        let (stream, stream_handle) = OutputStream::try_default()
-            .map_err(|e| AppError::Rodio(e.to_string()))?;
+        // The output stream isn't `Send`, so it is kept open on a thread of
+        // its own and the player, shared across threads, holds only its handle
+        let (sender, receiver) = std::sync::mpsc::channel();
+        std::thread::spawn(move || match OutputStream::try_default() {
+            Ok((_stream, stream_handle)) => {
+                let _ = sender.send(Ok(stream_handle));
+                loop {
+                    std::thread::park();
+                }
+            }
+            Err(e) => {
+                let _ = sender.send(Err(e.to_string()));
+            }
+        });
+        let stream_handle = receiver
+            .recv()
+            .map_err(|e| AppError::Rodio(e.to_string()))?
+            .map_err(AppError::Rodio)?;
 
         Ok(Self {
-            _stream: stream,
             stream_handle,
             sink: None,
+            duration: None,
         })
     }
 
     pub fn play_adhan(&mut self, path: &str) -> Result<()> {
+        let audio_path = resolve_resource_path(path);
+        println!("Playing audio from: {:?}", audio_path);
+        self.play_file(&audio_path)
+    }
+
+    pub fn play_file(&mut self, audio_path: &Path) -> Result<()> {
         // Stop any currently playing audio
         self.stop();
 
@@ -31,22 +61,13 @@ impl AudioPlayer {
         let sink = Sink::try_new(&self.stream_handle)
             .map_err(|e| AppError::Rodio(e.to_string()))?;
 
-        // Resolve path - try both absolute and relative to resources
-        let audio_path = if Path::new(path).exists() {
-            PathBuf::from(path)
-        } else {
-            // Try relative to app directory
-            PathBuf::from(format!("../{}", path))
-        };
-
-        println!("Playing audio from: {:?}", audio_path);
-
-        let file = File::open(&audio_path)
-            .map_err(|e| AppError::Custom(format!("Failed to open audio file '{}': {}", path, e)))?;
+        let file = File::open(audio_path)
+            .map_err(|e| AppError::NotFound(format!("Failed to open audio file '{}': {}", audio_path.display(), e)))?;
         
         let source = Decoder::new(BufReader::new(file))
             .map_err(|e| AppError::Rodio(format!("Failed to decode audio: {}", e)))?;
-        
+
+        self.duration = source.total_duration();
         sink.append(source);
         sink.play();
 
@@ -59,6 +80,7 @@ impl AudioPlayer {
         if let Some(sink) = self.sink.take() {
             sink.stop();
         }
+        self.duration = None;
     }
 
     pub fn set_volume(&mut self, volume: f32) {
@@ -82,4 +104,87 @@ impl AudioPlayer {
             sink.play();
         }
     }
+
+    pub fn is_paused(&self) -> bool {
+        self.sink.as_ref().map(|s| s.is_paused()).unwrap_or(false)
+    }
+
+    pub fn seek(&mut self, position: Duration) -> Result<()> {
+        let sink = self.sink.as_ref().ok_or_else(|| AppError::Rodio("Nothing is playing".to_string()))?;
+
+        // Clamp to the track length so seeking past the end doesn't error out
+        let position = match self.duration {
+            Some(duration) => position.min(duration),
+            None => position,
+        };
+
+        sink.try_seek(position)
+            .map_err(|e| AppError::Rodio(format!("Failed to seek: {}", e)))
+    }
+
+    pub fn position(&self) -> Duration {
+        self.sink.as_ref().map(|s| s.get_pos()).unwrap_or_default()
+    }
+
+    pub fn duration(&self) -> Option<Duration> {
+        self.duration
+    }
+
+    pub fn progress(&self) -> AudioProgress {
+        let position = self.position().as_secs_f64();
+        let duration = self.duration.map(|d| d.as_secs_f64());
+        let percent = match duration {
+            Some(d) if d > 0.0 => (position / d * 100.0).min(100.0),
+            _ => 0.0,
+        };
+
+        AudioProgress {
+            playing: self.is_playing() && !self.is_paused(),
+            paused: self.is_paused(),
+            position,
+            duration,
+            percent,
+        }
+    }
+}
+
+impl AudioSink for RwLock<AudioPlayer> {
+    fn play_adhan(&self, path: &str) -> Result<()> {
+        self.write().play_adhan(path)
+    }
+
+    fn is_playing(&self) -> bool {
+        self.read().is_playing()
+    }
+}
+
+/// Resolves a bundled resource path - try both absolute and relative to the app directory
+pub fn resolve_resource_path(path: &str) -> PathBuf {
+    if Path::new(path).exists() {
+        PathBuf::from(path)
+    } else {
+        PathBuf::from(format!("../{}", path))
+    }
+}
+
+/// Emits `audio-progress` once per second while something is playing, plus a
+/// final zeroed event when playback ends, mirroring the old `progress-request`.
+#[cfg(feature = "gui")]
+pub async fn start_progress_emitter(audio_player: Arc<RwLock<AudioPlayer>>, app_handle: AppHandle) {
+    let mut interval = tokio::time::interval(Duration::from_secs(1));
+    let mut was_playing = false;
+
+    loop {
+        interval.tick().await;
+
+        let progress = audio_player.read().progress();
+        let active = progress.playing || progress.paused;
+
+        if active {
+            let _ = app_handle.emit("audio-progress", &progress);
+        } else if was_playing {
+            let _ = app_handle.emit("audio-progress", AudioProgress::default());
+        }
+        was_playing = active;
+    }
 }
@@ -0,0 +1,451 @@
+//! `muezzin-cli`: prayer times in the terminal, without the app.
+//!
+//! Location and calculation method come from flags, falling back to what the
+//! app has stored in `<data dir>/io.github.cormacz.muezzin/settings.json`.
+//!
+//! ```text
+//! muezzin-cli today
+//! muezzin-cli --lat 48.8566 --lon 2.3522 --tz Europe/Paris --method MWL next
+//! muezzin-cli --format csv range --from 2025-03-01 --to 2025-03-30
+//! muezzin-cli qibla
+//! muezzin-cli hijri --adjust -1
+//! ```
+
+use chrono::{DateTime, Local, NaiveDate};
+use chrono_tz::Tz;
+use clap::{Parser, Subcommand, ValueEnum};
+use serde::Serialize;
+use std::path::PathBuf;
+
+use muezzin_core::timetable::{self, TimetableDay};
+use muezzin_core::{qibla, HijriDate, PrayerCalculator};
+
+use muezzin_lib::error::{AppError, Result};
+use muezzin_lib::settings_file::{self, StoredSettings};
+use muezzin_lib::validation;
+
+#[derive(Parser)]
+#[command(name = "muezzin-cli", version, about = "Prayer times, Qibla and Hijri date from the command line")]
+struct Cli {
+    #[arg(long, global = true, allow_hyphen_values = true)]
+    lat: Option<f64>,
+    #[arg(long, global = true, allow_hyphen_values = true)]
+    lon: Option<f64>,
+    /// IANA timezone, e.g. Europe/Paris
+    #[arg(long, global = true)]
+    tz: Option<String>,
+    /// Calculation method, e.g. MWL, ISNA, Egyptian, UAQ
+    #[arg(long, global = true)]
+    method: Option<String>,
+    /// Shafi or Hanafi
+    #[arg(long, global = true)]
+    madhab: Option<String>,
+    #[arg(long, global = true, value_enum, default_value_t = Format::Table)]
+    format: Format,
+    /// Settings file to read instead of the app's
+    #[arg(long, global = true)]
+    settings: Option<PathBuf>,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    Table,
+    Json,
+    Csv,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Today's prayer times, or another day's
+    Today {
+        #[arg(long)]
+        date: Option<NaiveDate>,
+    },
+    /// The next prayer and how long until it
+    Next,
+    /// Prayer times for every day between two dates, inclusive
+    Range {
+        #[arg(long)]
+        from: NaiveDate,
+        #[arg(long)]
+        to: NaiveDate,
+    },
+    /// Qibla bearing from true north
+    Qibla,
+    /// Hijri date
+    Hijri {
+        #[arg(long)]
+        date: Option<NaiveDate>,
+        /// Days to add to the tabular calendar
+        #[arg(long, default_value_t = 0, allow_hyphen_values = true)]
+        adjust: i32,
+    },
+}
+
+/// Reads the app's store unless pointed at another file. A missing store
+/// is only an error when the path was given explicitly.
+fn load_stored(path: Option<PathBuf>) -> Result<StoredSettings> {
+    match path {
+        Some(path) => StoredSettings::load(&path),
+        None => match settings_file::default_path() {
+            Some(path) if path.exists() => StoredSettings::load(&path),
+            _ => Ok(StoredSettings::default()),
+        },
+    }
+}
+
+fn build_calculator(cli: &Cli, stored: StoredSettings) -> Result<(PrayerCalculator, f64, f64, Tz)> {
+    let (lat, lon, tz) = match (cli.lat, cli.lon, &cli.tz, stored.location) {
+        (Some(lat), Some(lon), Some(tz), _) => (lat, lon, tz.clone()),
+        (lat, lon, tz, Some((s_lat, s_lon, s_tz))) => {
+            (lat.unwrap_or(s_lat), lon.unwrap_or(s_lon), tz.clone().unwrap_or(s_tz))
+        }
+        _ => {
+            return Err(AppError::InvalidInput(
+                "No stored location found, pass --lat, --lon and --tz".to_string(),
+            ))
+        }
+    };
+    if !(-90.0..=90.0).contains(&lat) || !(-180.0..=180.0).contains(&lon) {
+        return Err(AppError::InvalidInput(format!("Invalid coordinates: {}, {}", lat, lon)));
+    }
+    let tz: Tz = tz.parse().map_err(|e| AppError::Timezone(format!("Invalid timezone: {}", e)))?;
+
+    let mut calculation = stored.settings.calculation;
+    if let Some(ref method) = cli.method {
+        calculation.calc_method = method.clone();
+    }
+    if let Some(ref madhab) = cli.madhab {
+        calculation.madhab = madhab.clone();
+    }
+    if !validation::CALC_METHODS.contains(&calculation.calc_method.as_str()) {
+        return Err(AppError::InvalidInput(format!(
+            "Unknown calculation method '{}', expected one of {}",
+            calculation.calc_method,
+            validation::CALC_METHODS.join(", ")
+        )));
+    }
+    if !validation::MADHABS.contains(&calculation.madhab.as_str()) {
+        return Err(AppError::InvalidInput(format!("Unknown madhab '{}', expected Shafi or Hanafi", calculation.madhab)));
+    }
+
+    let mut calculator = PrayerCalculator::new();
+    calculator.update_settings(lat, lon, &calculation, tz, stored.custom_times, stored.jumuah_time)?;
+    Ok((calculator, lat, lon, tz))
+}
+
+#[derive(Debug, Serialize)]
+struct DayRow {
+    date: String,
+    fajr: String,
+    sunrise: String,
+    dhuhr: String,
+    asr: String,
+    maghrib: String,
+    isha: String,
+}
+
+impl DayRow {
+    fn new(day: &TimetableDay, tz: Tz) -> Self {
+        let hm = |t: DateTime<Local>| t.with_timezone(&tz).format("%H:%M").to_string();
+        let times = &day.times;
+        Self {
+            date: day.date.format("%Y-%m-%d").to_string(),
+            fajr: hm(times.fajr),
+            sunrise: hm(times.sunrise),
+            dhuhr: hm(times.dhuhr),
+            asr: hm(times.asr),
+            maghrib: hm(times.maghrib),
+            isha: hm(times.isha),
+        }
+    }
+
+    fn cells(&self) -> Vec<String> {
+        vec![
+            self.date.clone(),
+            self.fajr.clone(),
+            self.sunrise.clone(),
+            self.dhuhr.clone(),
+            self.asr.clone(),
+            self.maghrib.clone(),
+            self.isha.clone(),
+        ]
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct NextPrayer {
+    prayer: String,
+    time: String,
+    minutes: i64,
+}
+
+#[derive(Debug, Serialize)]
+struct Qibla {
+    latitude: f64,
+    longitude: f64,
+    bearing: f64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct HijriOutput {
+    gregorian: String,
+    year: i32,
+    month: u32,
+    month_name: String,
+    day: u32,
+}
+
+fn table(headers: &[&str], rows: &[Vec<String>]) -> String {
+    let widths: Vec<usize> = headers
+        .iter()
+        .enumerate()
+        .map(|(i, h)| rows.iter().map(|r| r[i].chars().count()).chain([h.len()]).max().unwrap_or(0))
+        .collect();
+    let line = |cells: Vec<&str>| {
+        cells
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+            .collect::<Vec<_>>()
+            .join("  ")
+            .trim_end()
+            .to_string()
+    };
+
+    let mut out = vec![line(headers.to_vec())];
+    out.extend(rows.iter().map(|r| line(r.iter().map(String::as_str).collect())));
+    out.join("\n")
+}
+
+fn csv(headers: &[&str], rows: &[Vec<String>]) -> String {
+    let mut out = vec![headers.join(",")];
+    out.extend(rows.iter().map(|r| r.join(",")));
+    out.join("\n")
+}
+
+fn render<T: Serialize>(format: Format, value: &T, headers: &[&str], rows: &[Vec<String>], text: impl FnOnce() -> String) -> Result<String> {
+    Ok(match format {
+        Format::Table => text(),
+        Format::Json => serde_json::to_string_pretty(value)?,
+        Format::Csv => csv(headers, rows),
+    })
+}
+
+const DAY_HEADERS: [&str; 7] = ["Date", "Fajr", "Sunrise", "Dhuhr", "Asr", "Maghrib", "Isha"];
+
+fn render_days(format: Format, days: &[DayRow]) -> Result<String> {
+    let rows: Vec<Vec<String>> = days.iter().map(DayRow::cells).collect();
+    render(format, &days, &DAY_HEADERS, &rows, || table(&DAY_HEADERS, &rows))
+}
+
+fn format_minutes(minutes: i64) -> String {
+    if minutes >= 60 {
+        format!("{}h {}m", minutes / 60, minutes % 60)
+    } else {
+        format!("{}m", minutes)
+    }
+}
+
+fn render_next(format: Format, next: &NextPrayer, time_of_day: &str) -> Result<String> {
+    let rows = vec![vec![next.prayer.clone(), next.time.clone(), next.minutes.to_string()]];
+    render(format, next, &["prayer", "time", "minutes"], &rows, || {
+        format!("{} at {} (in {})", next.prayer, time_of_day, format_minutes(next.minutes))
+    })
+}
+
+fn render_qibla(format: Format, qibla: &Qibla) -> Result<String> {
+    let rows = vec![vec![qibla.latitude.to_string(), qibla.longitude.to_string(), qibla.bearing.to_string()]];
+    render(format, qibla, &["latitude", "longitude", "bearing"], &rows, || {
+        format!("Qibla: {}° from true north", qibla.bearing)
+    })
+}
+
+fn render_hijri(format: Format, hijri: &HijriOutput) -> Result<String> {
+    let rows = vec![vec![
+        hijri.gregorian.clone(),
+        hijri.year.to_string(),
+        hijri.month.to_string(),
+        hijri.month_name.clone(),
+        hijri.day.to_string(),
+    ]];
+    render(format, hijri, &["gregorian", "year", "month", "month_name", "day"], &rows, || {
+        format!("{} {} {} AH", hijri.day, hijri.month_name, hijri.year)
+    })
+}
+
+fn run(cli: Cli) -> Result<String> {
+    // The Hijri date doesn't depend on the location
+    if let Command::Hijri { date, adjust } = cli.command {
+        let date = date.unwrap_or_else(|| Local::now().date_naive());
+        let hijri = HijriDate::from_gregorian(date, adjust);
+        return render_hijri(cli.format, &HijriOutput {
+            gregorian: date.format("%Y-%m-%d").to_string(),
+            year: hijri.year,
+            month: hijri.month,
+            month_name: hijri.month_name().to_string(),
+            day: hijri.day,
+        });
+    }
+
+    let stored = load_stored(cli.settings.clone())?;
+    let (calculator, lat, lon, tz) = build_calculator(&cli, stored)?;
+
+    match cli.command {
+        Command::Today { date } => {
+            let date = date.unwrap_or_else(|| Local::now().date_naive());
+            let day = timetable::day(&calculator, date, 0)?;
+            render_days(cli.format, &[DayRow::new(&day, tz)])
+        }
+        Command::Range { from, to } => {
+            let days: Vec<DayRow> = timetable::range(&calculator, from, to, 0)?
+                .iter()
+                .map(|day| DayRow::new(day, tz))
+                .collect();
+            render_days(cli.format, &days)
+        }
+        Command::Next => {
+            let now = Local::now();
+            let (prayer, time) = calculator.get_prayer_after(now)?;
+            let local = time.with_timezone(&tz);
+            // Round up so the countdown never reads 0 before the adhan
+            let minutes = ((time - now).num_seconds() + 59) / 60;
+            render_next(
+                cli.format,
+                &NextPrayer { prayer, time: local.to_rfc3339(), minutes },
+                &local.format("%H:%M").to_string(),
+            )
+        }
+        Command::Qibla => {
+            let bearing = (qibla::bearing(lat, lon) * 10.0).round() / 10.0;
+            render_qibla(cli.format, &Qibla { latitude: lat, longitude: lon, bearing })
+        }
+        Command::Hijri { .. } => unreachable!("handled above"),
+    }
+}
+
+fn main() {
+    match run(Cli::parse()) {
+        Ok(output) => println!("{}", output),
+        Err(e) => {
+            eprintln!("muezzin-cli: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn days() -> Vec<DayRow> {
+        let row = |date: &str, times: [&str; 6]| DayRow {
+            date: date.to_string(),
+            fajr: times[0].to_string(),
+            sunrise: times[1].to_string(),
+            dhuhr: times[2].to_string(),
+            asr: times[3].to_string(),
+            maghrib: times[4].to_string(),
+            isha: times[5].to_string(),
+        };
+        vec![
+            row("2025-03-01", ["05:52", "07:24", "13:02", "15:58", "18:41", "20:06"]),
+            row("2025-03-02", ["05:50", "07:22", "13:02", "15:59", "18:42", "20:07"]),
+        ]
+    }
+
+    fn snapshot(name: &str, actual: &str) {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/snapshots/cli").join(name);
+        let expected = fs::read_to_string(&path).unwrap_or_else(|e| panic!("{}: {}", path.display(), e));
+        assert_eq!(actual, expected.trim_end_matches('\n'), "snapshot {} differs", name);
+    }
+
+    /// Runs the CLI against a store file holding `store`, never the app's own
+    fn run_with(name: &str, store: &str, args: &[&str]) -> Result<String> {
+        let path = std::env::temp_dir().join(format!("muezzin-cli-{}-{}.json", std::process::id(), name));
+        fs::write(&path, store).unwrap();
+        let settings = ["--settings", path.to_str().unwrap()];
+        let cli = Cli::try_parse_from(["muezzin-cli"].iter().chain(&settings).chain(args)).unwrap();
+        let output = run(cli);
+        fs::remove_file(&path).unwrap();
+        output
+    }
+
+    // Raleigh, NC with ISNA and Hanafi, the reference case of the Adhan
+    // libraries the calculation comes from
+    const RALEIGH: [&str; 10] =
+        ["--lat", "35.7750", "--lon", "-78.6336", "--tz", "America/New_York", "--method", "ISNA", "--madhab", "Hanafi"];
+
+    #[test]
+    fn today_from_flags() {
+        let args: Vec<&str> = RALEIGH.iter().copied().chain(["today", "--date", "2015-07-12"]).collect();
+        snapshot("today.raleigh.table.txt", &run_with("today", "{}", &args).unwrap());
+    }
+
+    #[test]
+    fn range_from_the_stored_settings() {
+        let store = r#"{
+            "latitude": 35.775,
+            "longitude": -78.6336,
+            "timezone": "America/New_York",
+            "settings": { "calculation": { "calc_method": "ISNA", "madhab": "Hanafi" } }
+        }"#;
+        let args = ["--format", "csv", "range", "--from", "2015-07-16", "--to", "2015-07-18"];
+        snapshot("range.raleigh.csv", &run_with("range", store, &args).unwrap());
+    }
+
+    #[test]
+    fn unknown_method_is_rejected() {
+        let args: Vec<&str> = RALEIGH[..6].iter().copied().chain(["--method", "UOIF", "today"]).collect();
+        assert!(matches!(run_with("method", "{}", &args), Err(AppError::InvalidInput(_))));
+    }
+
+    #[test]
+    fn days_as_table() {
+        snapshot("days.table.txt", &render_days(Format::Table, &days()).unwrap());
+    }
+
+    #[test]
+    fn days_as_json() {
+        snapshot("days.json", &render_days(Format::Json, &days()).unwrap());
+    }
+
+    #[test]
+    fn days_as_csv() {
+        snapshot("days.csv", &render_days(Format::Csv, &days()).unwrap());
+    }
+
+    #[test]
+    fn next_prayer_outputs() {
+        let next = NextPrayer { prayer: "Asr".to_string(), time: "2025-03-01T15:58:00+01:00".to_string(), minutes: 72 };
+        snapshot("next.table.txt", &render_next(Format::Table, &next, "15:58").unwrap());
+        snapshot("next.json", &render_next(Format::Json, &next, "15:58").unwrap());
+    }
+
+    #[test]
+    fn qibla_outputs() {
+        let qibla = Qibla { latitude: 48.8566, longitude: 2.3522, bearing: 119.1 };
+        snapshot("qibla.table.txt", &render_qibla(Format::Table, &qibla).unwrap());
+        snapshot("qibla.csv", &render_qibla(Format::Csv, &qibla).unwrap());
+    }
+
+    #[test]
+    fn hijri_outputs() {
+        let date = NaiveDate::from_ymd_opt(2025, 3, 1).unwrap();
+        let hijri = HijriDate::from_gregorian(date, 0);
+        let output = HijriOutput {
+            gregorian: "2025-03-01".to_string(),
+            year: hijri.year,
+            month: hijri.month,
+            month_name: hijri.month_name().to_string(),
+            day: hijri.day,
+        };
+        snapshot("hijri.table.txt", &render_hijri(Format::Table, &output).unwrap());
+        snapshot("hijri.json", &render_hijri(Format::Json, &output).unwrap());
+        snapshot("hijri.csv", &render_hijri(Format::Csv, &output).unwrap());
+    }
+}
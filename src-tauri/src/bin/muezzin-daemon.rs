@@ -0,0 +1,371 @@
+//! `muezzin-daemon`: plays the adhan on an always-on box, without a window.
+//!
+//! It reads the app's settings file (or `--settings`), runs the same prayer
+//! checker, hooks and multi-room link as the app and logs instead of showing
+//! notifications. Logs go to stdout with syslog level prefixes, which journald
+//! understands, or to `--log-file`. SIGHUP reloads the settings file and
+//! SIGTERM stops it.
+//!
+//! ```text
+//! muezzin-daemon --resource-dir /usr/share/muezzin
+//! kill -HUP $(pidof muezzin-daemon)
+//! ```
+
+use chrono::Local;
+use clap::Parser;
+use parking_lot::{Mutex, RwLock};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use muezzin_core::PrayerCalculator;
+
+use muezzin_lib::audio::AudioPlayer;
+use muezzin_lib::checker::{self, AudioSink, CheckerEvent, Notifier, Scheduler, SettingsSource, SystemClock};
+use muezzin_lib::error::{AppError, Result};
+use muezzin_lib::events::{EventBus, PrayerEvent};
+use muezzin_lib::hooks::Hooks;
+use muezzin_lib::models::{AppSettings, MultiRoomState, MultiRoomStatus};
+use muezzin_lib::multi_room::{MultiRoom, MultiRoomHost, Schedule};
+use muezzin_lib::settings_file::{self, StoredSettings};
+
+#[derive(Parser)]
+#[command(name = "muezzin-daemon", version, about = "Plays the adhan without a window")]
+struct Args {
+    /// Settings file to read instead of the app's
+    #[arg(long)]
+    settings: Option<PathBuf>,
+    /// Append logs to this file instead of writing them to stdout
+    #[arg(long)]
+    log_file: Option<PathBuf>,
+    /// Directory that relative adhan paths such as `assets/adhan.mp3` are resolved against
+    #[arg(long)]
+    resource_dir: Option<PathBuf>,
+    /// Log adhans instead of playing them, for boxes without a sound card
+    #[arg(long)]
+    no_audio: bool,
+}
+
+#[derive(Clone, Copy)]
+enum Level {
+    Error,
+    Info,
+}
+
+impl Level {
+    /// sd-daemon prefix, see `man sd-daemon`
+    fn syslog_prefix(self) -> &'static str {
+        match self {
+            Level::Error => "<3>",
+            Level::Info => "<6>",
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Level::Error => "ERROR",
+            Level::Info => "INFO",
+        }
+    }
+}
+
+/// Journald-style lines on stdout, or timestamped lines in a file
+struct Log {
+    file: Option<Mutex<File>>,
+}
+
+impl Log {
+    fn open(path: Option<&Path>) -> Result<Self> {
+        let file = match path {
+            Some(path) => Some(Mutex::new(OpenOptions::new().create(true).append(true).open(path)?)),
+            None => None,
+        };
+        Ok(Self { file })
+    }
+
+    fn write(&self, level: Level, message: &str) {
+        match self.file {
+            Some(ref file) => {
+                let line = format!("{} {:<5} {}\n", Local::now().format("%Y-%m-%dT%H:%M:%S%:z"), level.label(), message);
+                let _ = file.lock().write_all(line.as_bytes());
+            }
+            None => println!("{}{}", level.syslog_prefix(), message),
+        }
+    }
+
+    fn info(&self, message: &str) {
+        self.write(Level::Info, message);
+    }
+
+    fn error(&self, message: &str) {
+        self.write(Level::Error, message);
+    }
+}
+
+/// Stands in for the speakers with `--no-audio`
+struct LoggedAudio {
+    log: Arc<Log>,
+}
+
+impl AudioSink for LoggedAudio {
+    fn play_adhan(&self, path: &str) -> Result<()> {
+        self.log.info(&format!("Not playing {} (--no-audio)", path));
+        Ok(())
+    }
+
+    fn is_playing(&self) -> bool {
+        false
+    }
+}
+
+struct DaemonHost {
+    settings: RwLock<AppSettings>,
+    /// Our own schedule from the settings file, also what a leader shares
+    schedule: RwLock<Schedule>,
+    calculator: Arc<RwLock<PrayerCalculator>>,
+    audio: Arc<dyn AudioSink>,
+    events: EventBus,
+    multi_room: MultiRoom,
+    hooks: Hooks,
+    log: Arc<Log>,
+}
+
+impl DaemonHost {
+    fn apply_multi_room(self: &Arc<Self>) {
+        let settings = self.settings.read().multi_room.clone();
+        self.multi_room.apply(self.clone(), &self.events, &settings);
+    }
+}
+
+impl SettingsSource for DaemonHost {
+    fn settings(&self) -> Result<AppSettings> {
+        let mut settings = self.settings.read().clone();
+        // A connected multi-room leader starts our adhan instead
+        if self.multi_room.leader_plays_adhan() {
+            settings.adhan_check = false;
+        }
+        Ok(settings)
+    }
+}
+
+impl Notifier for DaemonHost {
+    fn notify(&self, event: CheckerEvent) {
+        self.hooks.fire(&self.settings.read().hooks, &event);
+
+        match event {
+            CheckerEvent::DateChanged => self.log.info("New day, recalculating prayer times"),
+            CheckerEvent::Minute { .. } => {}
+            CheckerEvent::Prayer { prayer, time, with_sound, .. } => {
+                let adhan = if with_sound { "playing adhan" } else { "adhan off or muted" };
+                self.log.info(&format!("{} at {} ({})", prayer, time.format("%H:%M"), adhan));
+                // Multi-room followers play along
+                self.events.publish(PrayerEvent::Adhan { prayer, time, with_sound });
+            }
+            CheckerEvent::Reminder { prayer, minutes, .. } => {
+                self.log.info(&format!("{} in {} minutes", prayer, minutes));
+            }
+            CheckerEvent::AdhanEnded { prayer } => self.log.info(&format!("{} adhan finished", prayer)),
+            CheckerEvent::Iqamah { prayer, time } => {
+                self.log.info(&format!("{} Iqamah at {}", prayer, time.format("%H:%M")));
+            }
+        }
+    }
+
+    fn log_error(&self, message: &str) {
+        self.log.error(message);
+    }
+}
+
+impl MultiRoomHost for DaemonHost {
+    fn schedule(&self) -> Result<Schedule> {
+        Ok(self.schedule.read().clone())
+    }
+
+    fn apply_schedule(&self, schedule: &Schedule) -> Result<()> {
+        *self.calculator.write() = schedule.calculator()?;
+        log_next_prayer(&self.log, &self.calculator.read());
+        Ok(())
+    }
+
+    fn restore_schedule(&self) -> Result<()> {
+        *self.calculator.write() = self.schedule.read().calculator()?;
+        log_next_prayer(&self.log, &self.calculator.read());
+        Ok(())
+    }
+
+    fn play_adhan(&self, prayer: &str) -> Result<()> {
+        let settings = self.settings.read().clone();
+        if checker::adhan_wanted(&settings, prayer, Local::now()) {
+            self.log.info(&format!("{} adhan on the leader, playing along", prayer));
+            self.audio.play_adhan(checker::adhan_path(&settings, prayer))?;
+        }
+        Ok(())
+    }
+
+    fn status_changed(&self, status: &MultiRoomStatus) {
+        let leader = status.leader.as_deref().unwrap_or_default();
+        let address = status.address.as_deref().unwrap_or_default();
+        match status.state {
+            MultiRoomState::Off => {
+                if let Some(ref error) = status.error {
+                    self.log.error(&format!("Multi-room stopped: {}", error));
+                }
+            }
+            MultiRoomState::Leading => {
+                self.log.info(&format!("Leading as {} on {} ({} following)", leader, address, status.followers));
+            }
+            MultiRoomState::Searching => match status.error {
+                Some(ref error) => self.log.info(&format!("Looking for a multi-room leader: {}", error)),
+                None => self.log.info("Looking for a multi-room leader"),
+            },
+            MultiRoomState::Connected => self.log.info(&format!("Following {} at {}", leader, address)),
+            MultiRoomState::Unreachable => self.log.error(&format!(
+                "Leader {} unreachable: {}",
+                leader,
+                status.error.as_deref().unwrap_or_default()
+            )),
+        }
+    }
+}
+
+/// Loads settings, our schedule and a calculator for it from the settings file
+fn load(args: &Args) -> Result<(AppSettings, Schedule, PrayerCalculator)> {
+    let path = args
+        .settings
+        .clone()
+        .or_else(settings_file::default_path)
+        .ok_or_else(|| AppError::NotFound("No settings file found, pass --settings".to_string()))?;
+    let stored = StoredSettings::load(&path)?;
+    let (latitude, longitude, timezone) = stored.location.clone().ok_or_else(|| {
+        AppError::NotFound("No location has been saved yet".to_string())
+    })?;
+    let schedule = Schedule {
+        latitude,
+        longitude,
+        timezone,
+        calculation: stored.settings.calculation.clone(),
+        custom_times: stored.custom_times.clone(),
+        jumuah_time: stored.jumuah_time.clone(),
+    };
+    let calculator = schedule.calculator()?;
+
+    let mut settings = stored.settings;
+    if let Some(ref dir) = args.resource_dir {
+        settings.adhan_path = resolve_resource(dir, &settings.adhan_path);
+        settings.adhan_fajr_path = settings.adhan_fajr_path.as_deref().map(|p| resolve_resource(dir, p));
+    }
+    Ok((settings, schedule, calculator))
+}
+
+/// Resolves a relative adhan path against `--resource-dir`
+fn resolve_resource(dir: &Path, path: &str) -> String {
+    if Path::new(path).is_absolute() {
+        path.to_string()
+    } else {
+        dir.join(path).to_string_lossy().into_owned()
+    }
+}
+
+fn log_next_prayer(log: &Log, calculator: &PrayerCalculator) {
+    match calculator.get_next_prayer() {
+        Ok((prayer, time)) => log.info(&format!("Next prayer: {} at {}", prayer, time.format("%Y-%m-%d %H:%M"))),
+        Err(e) => log.error(&format!("Error getting next prayer: {}", e)),
+    }
+}
+
+#[cfg(unix)]
+async fn wait_for_signals(args: &Args, host: &Arc<DaemonHost>) -> Result<()> {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut terminate = signal(SignalKind::terminate())?;
+    let mut interrupt = signal(SignalKind::interrupt())?;
+    let mut hangup = signal(SignalKind::hangup())?;
+
+    loop {
+        tokio::select! {
+            _ = terminate.recv() => return Ok(()),
+            _ = interrupt.recv() => return Ok(()),
+            _ = hangup.recv() => match load(args) {
+                Ok((settings, schedule, calculator)) => {
+                    *host.settings.write() = settings;
+                    *host.schedule.write() = schedule;
+                    host.apply_multi_room();
+                    // A schedule follower keeps its leader's times
+                    *host.calculator.write() = host
+                        .multi_room
+                        .leader_schedule()
+                        .and_then(|leader| leader.calculator().ok())
+                        .unwrap_or(calculator);
+                    host.log.info("Reloaded settings");
+                    log_next_prayer(&host.log, &host.calculator.read());
+                }
+                Err(e) => host.log.error(&format!("Error reloading settings, keeping the previous ones: {}", e)),
+            },
+        }
+    }
+}
+
+#[cfg(not(unix))]
+async fn wait_for_signals(_args: &Args, _host: &Arc<DaemonHost>) -> Result<()> {
+    tokio::signal::ctrl_c().await?;
+    Ok(())
+}
+
+async fn run(args: Args, log: Arc<Log>) -> Result<()> {
+    let (settings, schedule, calculator) = load(&args)?;
+    let audio_player = if args.no_audio { None } else { Some(Arc::new(RwLock::new(AudioPlayer::new()?))) };
+    let audio: Arc<dyn AudioSink> = match audio_player {
+        Some(ref player) => player.clone(),
+        None => Arc::new(LoggedAudio { log: log.clone() }),
+    };
+    log_next_prayer(&log, &calculator);
+
+    let calculator = Arc::new(RwLock::new(calculator));
+    let host = Arc::new(DaemonHost {
+        settings: RwLock::new(settings),
+        schedule: RwLock::new(schedule),
+        calculator: calculator.clone(),
+        audio: audio.clone(),
+        events: EventBus::new(),
+        multi_room: MultiRoom::new(),
+        hooks: Hooks::new(),
+        log: log.clone(),
+    });
+    host.apply_multi_room();
+
+    let scheduler = Scheduler::new(
+        Arc::new(SystemClock),
+        calculator,
+        host.clone(),
+        host.clone(),
+        audio,
+    );
+    let checker = tokio::spawn(checker::run(scheduler));
+
+    let result = wait_for_signals(&args, &host).await;
+    log.info("Stopping");
+    checker.abort();
+    if let Some(player) = audio_player {
+        player.write().stop();
+    }
+    result
+}
+
+#[tokio::main]
+async fn main() {
+    let args = Args::parse();
+    let log = match Log::open(args.log_file.as_deref()) {
+        Ok(log) => Arc::new(log),
+        Err(e) => {
+            eprintln!("muezzin-daemon: cannot open log file: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    log.info("Starting");
+    if let Err(e) = run(args, log.clone()).await {
+        log.error(&e.to_string());
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,582 @@
+//! Decides when to play the adhan and raise reminders. Time, settings,
+//! notifications and audio all come in through traits, so the app, the
+//! headless daemon and the tests each plug in their own.
+
+use chrono::{DateTime, Datelike, Local, NaiveDate, Timelike};
+use parking_lot::RwLock;
+use std::sync::Arc;
+use tokio::time::Duration;
+
+use muezzin_core::PrayerCalculator;
+use crate::error::Result;
+use crate::models::{AlertMode, AppSettings, PrayerTimes};
+use crate::mute;
+
+#[derive(Debug, Clone)]
+pub enum CheckerEvent {
+    /// Local midnight has passed, so today's times changed
+    DateChanged,
+    /// Sent every minute with the prayer coming up
+    Minute {
+        now: DateTime<Local>,
+        next_prayer: String,
+        next_time: DateTime<Local>,
+        today: Option<PrayerTimes>,
+    },
+    /// A prayer time has been reached
+    Prayer {
+        prayer: String,
+        time: DateTime<Local>,
+        next: Option<(String, DateTime<Local>)>,
+        /// Whether the adhan was started
+        with_sound: bool,
+        /// Whether the alert mode and settings ask for a notification
+        notify: bool,
+    },
+    /// A configured reminder before a prayer is due
+    Reminder {
+        prayer: String,
+        time: DateTime<Local>,
+        minutes: u32,
+    },
+    /// The adhan started at `Prayer` has finished or was stopped
+    AdhanEnded {
+        prayer: String,
+    },
+    /// The configured Iqamah offset after a prayer has passed
+    Iqamah {
+        prayer: String,
+        time: DateTime<Local>,
+    },
+}
+
+pub trait Clock: Send + Sync {
+    fn now(&self) -> DateTime<Local>;
+}
+
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Local> {
+        Local::now()
+    }
+}
+
+pub trait SettingsSource: Send + Sync {
+    fn settings(&self) -> Result<AppSettings>;
+}
+
+/// Receives every event, whether or not it should reach the user
+pub trait Notifier: Send + Sync {
+    fn notify(&self, event: CheckerEvent);
+
+    fn log_error(&self, message: &str) {
+        eprintln!("{}", message);
+    }
+}
+
+pub trait AudioSink: Send + Sync {
+    fn play_adhan(&self, path: &str) -> Result<()>;
+    /// Whether anything is still playing, paused counts as playing
+    fn is_playing(&self) -> bool;
+}
+
+pub struct Scheduler {
+    clock: Arc<dyn Clock>,
+    calculator: Arc<RwLock<PrayerCalculator>>,
+    settings: Arc<dyn SettingsSource>,
+    notifier: Arc<dyn Notifier>,
+    audio: Arc<dyn AudioSink>,
+    last_check_minute: Option<u32>,
+    last_date: NaiveDate,
+    /// Next prayer as found on the previous minute's check
+    pending: Option<(String, DateTime<Local>)>,
+    /// Prayer whose adhan is playing
+    playing: Option<String>,
+    /// Next Iqamah to announce
+    iqamah: Option<(String, DateTime<Local>)>,
+    /// Last settings error reported, so a corrupt store is logged once
+    settings_error: Option<String>,
+}
+
+impl Scheduler {
+    pub fn new(
+        clock: Arc<dyn Clock>,
+        calculator: Arc<RwLock<PrayerCalculator>>,
+        settings: Arc<dyn SettingsSource>,
+        notifier: Arc<dyn Notifier>,
+        audio: Arc<dyn AudioSink>,
+    ) -> Self {
+        let last_date = clock.now().date_naive();
+        Self {
+            clock,
+            calculator,
+            settings,
+            notifier,
+            audio,
+            last_check_minute: None,
+            last_date,
+            pending: None,
+            playing: None,
+            iqamah: None,
+            settings_error: None,
+        }
+    }
+
+    /// One step, meant to be called every second
+    pub fn tick(&mut self) {
+        if self.playing.is_some() && !self.audio.is_playing() {
+            let prayer = self.playing.take().unwrap();
+            self.notifier.notify(CheckerEvent::AdhanEnded { prayer });
+        }
+        if self.iqamah.as_ref().is_some_and(|(_, time)| self.clock.now() >= *time) {
+            let (prayer, time) = self.iqamah.take().unwrap();
+            self.notifier.notify(CheckerEvent::Iqamah { prayer, time });
+        }
+
+        let now = self.clock.now();
+        let current_minute = now.hour() * 60 + now.minute();
+        let current_date = now.date_naive();
+
+        // Recalculate prayers at midnight
+        if current_date != self.last_date {
+            self.last_date = current_date;
+            self.notifier.notify(CheckerEvent::DateChanged);
+        }
+
+        // Only check once per minute
+        if self.last_check_minute == Some(current_minute) {
+            return;
+        }
+        self.last_check_minute = Some(current_minute);
+
+        // Unreadable settings mustn't silence the adhan for good, so carry
+        // on with the defaults until they can be read again
+        let settings = match self.settings.settings() {
+            Ok(s) => {
+                self.settings_error = None;
+                s
+            }
+            Err(e) => {
+                let error = e.to_string();
+                if self.settings_error.as_ref() != Some(&error) {
+                    self.notifier.log_error(&format!("Error loading settings, using the defaults: {}", error));
+                    self.settings_error = Some(error);
+                }
+                AppSettings::default()
+            }
+        };
+
+        let calc = self.calculator.read();
+        let (prayer_name, prayer_time) = match calc.get_prayer_after(now) {
+            Ok(p) => p,
+            Err(e) => {
+                self.notifier.log_error(&format!("Error getting next prayer: {}", e));
+                return;
+            }
+        };
+        let today_times = calc.calculate_prayer_times(now).ok();
+        drop(calc);
+
+        self.notifier.notify(CheckerEvent::Minute {
+            now,
+            next_prayer: prayer_name.clone(),
+            next_time: prayer_time,
+            today: today_times,
+        });
+
+        // The lookup only sees prayers strictly after `now`, so the prayer
+        // reached this minute is the one found on the previous check. One
+        // missed by more than a minute (the machine slept) is skipped.
+        let reached = self
+            .pending
+            .replace((prayer_name.clone(), prayer_time))
+            .filter(|(_, time)| now >= *time && now - *time < chrono::Duration::minutes(1));
+
+        if !settings.adhan_check && !settings.notif_check {
+            return;
+        }
+
+        if let Some((prayer, time)) = reached {
+            self.prayer_time(&settings, &prayer, time, now);
+        }
+
+        self.check_reminder(&settings, &prayer_name, prayer_time, now);
+    }
+
+    fn prayer_time(&mut self, settings: &AppSettings, prayer: &str, time: DateTime<Local>, now: DateTime<Local>) {
+        let mode = settings.alert_modes.for_prayer(prayer);
+        let with_sound = adhan_wanted(settings, prayer, now);
+
+        if with_sound {
+            match self.audio.play_adhan(adhan_path(settings, prayer)) {
+                Ok(()) => self.playing = Some(prayer.to_string()),
+                Err(e) => self.notifier.log_error(&format!("Error playing adhan: {}", e)),
+            }
+        }
+
+        let friday = time.weekday() == chrono::Weekday::Fri;
+        self.iqamah = settings
+            .iqamah
+            .as_ref()
+            .and_then(|i| i.offset_for(prayer, friday))
+            .map(|offset| (prayer.to_string(), time + chrono::Duration::minutes(offset as i64)));
+
+        let next = self.calculator.read().get_prayer_after(time).ok();
+        self.notifier.notify(CheckerEvent::Prayer {
+            prayer: prayer.to_string(),
+            time,
+            next,
+            with_sound,
+            notify: settings.notif_check && mode != AlertMode::Silent,
+        });
+    }
+
+    fn check_reminder(&self, settings: &AppSettings, prayer: &str, time: DateTime<Local>, now: DateTime<Local>) {
+        let Some(ref reminder) = settings.reminder_times else {
+            return;
+        };
+        if !reminder.enabled {
+            return;
+        }
+
+        let minutes_until = ((time.timestamp() - now.timestamp()) / 60) as u32;
+        let reminder_minutes = match prayer {
+            "Fajr" => reminder.fajr,
+            "Dhuhr" if now.weekday() == chrono::Weekday::Fri => reminder.jumuah,
+            "Dhuhr" => reminder.dhuhr,
+            "Asr" => reminder.asr,
+            "Maghrib" => reminder.maghrib,
+            "Isha" => reminder.isha,
+            _ => 0,
+        };
+
+        if reminder_minutes > 0
+            && minutes_until == reminder_minutes
+            && settings.notif_check
+            && settings.alert_modes.for_prayer(prayer) != AlertMode::Silent
+        {
+            self.notifier.notify(CheckerEvent::Reminder {
+                prayer: prayer.to_string(),
+                time,
+                minutes: reminder_minutes,
+            });
+        }
+    }
+}
+
+/// Whether the adhan should sound for `prayer`: on, not muted and not a
+/// notification-only or silent prayer
+pub fn adhan_wanted(settings: &AppSettings, prayer: &str, now: DateTime<Local>) -> bool {
+    settings.adhan_check
+        && settings.alert_modes.for_prayer(prayer) == AlertMode::NotificationAndSound
+        && !mute::is_adhan_muted(settings, now)
+}
+
+/// The adhan recording for `prayer`, Fajr having its own when set
+pub fn adhan_path<'a>(settings: &'a AppSettings, prayer: &str) -> &'a str {
+    match settings.adhan_fajr_path {
+        Some(ref path) if prayer == "Fajr" => path,
+        _ => &settings.adhan_path,
+    }
+}
+
+/// Ticks the scheduler every second, forever
+pub async fn run(mut scheduler: Scheduler) {
+    let mut check_interval = tokio::time::interval(Duration::from_secs(1));
+    loop {
+        check_interval.tick().await;
+        scheduler.tick();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{CalculationSettings, IqamahTimes, ReminderTimes};
+    use chrono::{Duration, TimeZone};
+    use parking_lot::Mutex;
+
+    struct FakeClock {
+        now: Mutex<DateTime<Local>>,
+    }
+
+    impl FakeClock {
+        fn advance(&self, by: Duration) {
+            *self.now.lock() += by;
+        }
+    }
+
+    impl Clock for FakeClock {
+        fn now(&self) -> DateTime<Local> {
+            *self.now.lock()
+        }
+    }
+
+    struct FixedSettings(AppSettings);
+
+    impl SettingsSource for FixedSettings {
+        fn settings(&self) -> Result<AppSettings> {
+            Ok(self.0.clone())
+        }
+    }
+
+    struct CorruptSettings;
+
+    impl SettingsSource for CorruptSettings {
+        fn settings(&self) -> Result<AppSettings> {
+            Err(crate::error::AppError::CorruptValue {
+                key: "settings".to_string(),
+                reason: "expected a boolean".to_string(),
+            })
+        }
+    }
+
+    /// Notifier and audio sink in one, so events keep their relative order
+    struct Recorder {
+        clock: Arc<FakeClock>,
+        events: Mutex<Vec<(DateTime<Local>, String)>>,
+        playing_until: Mutex<Option<DateTime<Local>>>,
+    }
+
+    impl Recorder {
+        fn push(&self, event: String) {
+            self.events.lock().push((self.clock.now(), event));
+        }
+    }
+
+    impl Notifier for Recorder {
+        fn notify(&self, event: CheckerEvent) {
+            match event {
+                CheckerEvent::Reminder { prayer, minutes, .. } => self.push(format!("reminder {} {}", prayer, minutes)),
+                CheckerEvent::Prayer { prayer, with_sound, notify, .. } => {
+                    self.push(format!("prayer {} sound={} notify={}", prayer, with_sound, notify))
+                }
+                CheckerEvent::AdhanEnded { prayer } => self.push(format!("adhan ended {}", prayer)),
+                CheckerEvent::Iqamah { prayer, .. } => self.push(format!("iqamah {}", prayer)),
+                CheckerEvent::DateChanged | CheckerEvent::Minute { .. } => {}
+            }
+        }
+
+        fn log_error(&self, message: &str) {
+            self.push(format!("error {}", message));
+        }
+    }
+
+    impl AudioSink for Recorder {
+        fn play_adhan(&self, path: &str) -> Result<()> {
+            self.push(format!("adhan {}", path));
+            *self.playing_until.lock() = Some(self.clock.now() + Duration::seconds(ADHAN_SECONDS));
+            Ok(())
+        }
+
+        fn is_playing(&self) -> bool {
+            self.playing_until.lock().is_some_and(|until| self.clock.now() < until)
+        }
+    }
+
+    const DAY: i64 = 24 * 60 * 60;
+    /// How long the fake adhan plays
+    const ADHAN_SECONDS: i64 = 180;
+
+    /// A Monday, so Dhuhr isn't Jumuah
+    fn start() -> DateTime<Local> {
+        Local.with_ymd_and_hms(2025, 3, 3, 0, 0, 0).earliest().unwrap()
+    }
+
+    /// Places the location on the test machine's meridian, so all five
+    /// prayers fall inside its local day whatever its timezone
+    fn calculator() -> Arc<RwLock<PrayerCalculator>> {
+        let longitude = start().offset().local_minus_utc() as f64 / 240.0;
+        let mut calculator = PrayerCalculator::new();
+        calculator
+            .update_settings(21.4225, longitude, &CalculationSettings::default(), chrono_tz::UTC, None, None)
+            .unwrap();
+        Arc::new(RwLock::new(calculator))
+    }
+
+    fn base_settings() -> AppSettings {
+        AppSettings {
+            adhan_path: "adhan.mp3".to_string(),
+            adhan_fajr_path: Some("fajr.mp3".to_string()),
+            reminder_times: Some(ReminderTimes {
+                enabled: true,
+                fajr: 10,
+                dhuhr: 15,
+                asr: 0,
+                maghrib: 5,
+                isha: 20,
+                jumuah: 30,
+            }),
+            ..AppSettings::default()
+        }
+    }
+
+    /// What the recorder saw, and the prayers of the day in order
+    type Recorded = Vec<(DateTime<Local>, String)>;
+    type Prayers = Vec<(String, DateTime<Local>)>;
+
+    /// Runs the scheduler one second at a time through a whole local day,
+    /// returning what it recorded and the prayers it should have seen
+    fn run_day(settings: AppSettings) -> (Recorded, Prayers) {
+        run_day_with(Arc::new(FixedSettings(settings)))
+    }
+
+    fn run_day_with(settings: Arc<dyn SettingsSource>) -> (Recorded, Prayers) {
+        let start = start();
+        let clock = Arc::new(FakeClock { now: Mutex::new(start) });
+        let recorder = Arc::new(Recorder { clock: clock.clone(), events: Mutex::new(Vec::new()), playing_until: Mutex::new(None) });
+        let calculator = calculator();
+
+        let mut prayers = Vec::new();
+        let mut cursor = start;
+        loop {
+            let (prayer, time) = calculator.read().get_prayer_after(cursor).unwrap();
+            if time >= start + Duration::seconds(DAY) {
+                break;
+            }
+            prayers.push((prayer, time));
+            cursor = time;
+        }
+
+        let mut scheduler = Scheduler::new(
+            clock.clone(),
+            calculator,
+            settings,
+            recorder.clone(),
+            recorder.clone(),
+        );
+        for _ in 0..DAY {
+            scheduler.tick();
+            clock.advance(Duration::seconds(1));
+        }
+
+        let events = recorder.events.lock().clone();
+        (events, prayers)
+    }
+
+    #[test]
+    fn whole_day_plays_each_adhan_after_its_reminder() {
+        let (events, prayers) = run_day(base_settings());
+
+        let names: Vec<&str> = prayers.iter().map(|(p, _)| p.as_str()).collect();
+        assert_eq!(names, ["Fajr", "Dhuhr", "Asr", "Maghrib", "Isha"]);
+
+        let mut expected = Vec::new();
+        for (prayer, time) in &prayers {
+            let (reminder, path) = match prayer.as_str() {
+                "Fajr" => (10, "fajr.mp3"),
+                "Dhuhr" => (15, "adhan.mp3"),
+                "Asr" => (0, "adhan.mp3"),
+                "Maghrib" => (5, "adhan.mp3"),
+                _ => (20, "adhan.mp3"),
+            };
+            if reminder > 0 {
+                expected.push((*time - Duration::minutes(reminder), format!("reminder {} {}", prayer, reminder)));
+            }
+            expected.push((*time, format!("adhan {}", path)));
+            expected.push((*time, format!("prayer {} sound=true notify=true", prayer)));
+            expected.push((*time + Duration::seconds(ADHAN_SECONDS), format!("adhan ended {}", prayer)));
+        }
+        assert_eq!(events, expected);
+    }
+
+    #[test]
+    fn silent_prayers_and_mutes_skip_sound_and_notifications() {
+        let mut settings = base_settings();
+        settings.alert_modes.maghrib = AlertMode::Silent;
+        settings.alert_modes.isha = AlertMode::Notification;
+        // Muted until just after Fajr, which gets a notification but no adhan
+        let (_, fajr) = calculator().read().get_prayer_after(start()).unwrap();
+        settings.muted_until = Some(fajr + Duration::minutes(1));
+
+        let (events, _) = run_day(settings);
+        let events: Vec<&str> = events.iter().map(|(_, e)| e.as_str()).collect();
+        assert_eq!(events, [
+            "reminder Fajr 10",
+            "prayer Fajr sound=false notify=true",
+            "reminder Dhuhr 15",
+            "adhan adhan.mp3",
+            "prayer Dhuhr sound=true notify=true",
+            "adhan ended Dhuhr",
+            "adhan adhan.mp3",
+            "prayer Asr sound=true notify=true",
+            "adhan ended Asr",
+            "prayer Maghrib sound=false notify=false",
+            "reminder Isha 20",
+            "prayer Isha sound=false notify=true",
+        ]);
+    }
+
+    #[test]
+    fn nothing_fires_with_adhan_and_notifications_off() {
+        let mut settings = base_settings();
+        settings.adhan_check = false;
+        settings.notif_check = false;
+
+        let (events, _) = run_day(settings);
+        assert!(events.is_empty(), "{:?}", events);
+    }
+
+    #[test]
+    fn iqamah_follows_each_prayer_by_its_offset() {
+        let mut settings = base_settings();
+        settings.adhan_check = false;
+        settings.reminder_times = None;
+        settings.iqamah = Some(IqamahTimes { enabled: true, fajr: 20, dhuhr: 10, asr: 10, maghrib: 5, isha: 0, jumuah: 30 });
+
+        let (events, prayers) = run_day(settings);
+        let mut expected = Vec::new();
+        for (prayer, time) in &prayers {
+            let offset = match prayer.as_str() {
+                "Fajr" => 20,
+                "Maghrib" => 5,
+                "Isha" => 0,
+                _ => 10,
+            };
+            expected.push((*time, format!("prayer {} sound=false notify=true", prayer)));
+            expected.push((*time + Duration::minutes(offset), format!("iqamah {}", prayer)));
+        }
+        // A zero offset is announced on the tick after the prayer
+        let isha = expected.len() - 1;
+        expected[isha].0 += Duration::seconds(1);
+        assert_eq!(events, expected);
+    }
+
+    #[test]
+    fn prayer_slept_through_is_not_played_late() {
+        let (_, fajr) = calculator().read().get_prayer_after(start()).unwrap();
+        let clock = Arc::new(FakeClock { now: Mutex::new(fajr - Duration::minutes(2)) });
+        let recorder = Arc::new(Recorder { clock: clock.clone(), events: Mutex::new(Vec::new()), playing_until: Mutex::new(None) });
+        let mut scheduler = Scheduler::new(
+            clock.clone(),
+            calculator(),
+            Arc::new(FixedSettings(base_settings())),
+            recorder.clone(),
+            recorder.clone(),
+        );
+
+        scheduler.tick();
+        clock.advance(Duration::minutes(5));
+        scheduler.tick();
+        assert!(recorder.events.lock().is_empty(), "{:?}", recorder.events.lock());
+    }
+
+    #[test]
+    fn corrupt_settings_fall_back_to_the_defaults() {
+        let (events, prayers) = run_day_with(Arc::new(CorruptSettings));
+
+        let default_path = AppSettings::default().adhan_path;
+        let mut expected = vec![(
+            start(),
+            "error Error loading settings, using the defaults: Stored value 'settings' is corrupt: expected a boolean"
+                .to_string(),
+        )];
+        for (prayer, time) in &prayers {
+            expected.push((*time, format!("adhan {}", default_path)));
+            expected.push((*time, format!("prayer {} sound=true notify=true", prayer)));
+            expected.push((*time + Duration::seconds(ADHAN_SECONDS), format!("adhan ended {}", prayer)));
+        }
+        assert_eq!(events, expected);
+    }
+}
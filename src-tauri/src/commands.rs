@@ -1,12 +1,13 @@
-use tauri::State;
-use chrono::{DateTime, Local};
-use crate::{AppState, models::*, error::Result, geolocation};
+use tauri::{AppHandle, Emitter, State};
+use chrono::{DateTime, Local, NaiveDate};
+use std::sync::Arc;
+use crate::{AppState, models::*, error::Result, checker, electron_import, geolocation, hooks, http_api, i18n, kiosk, mute, notifications, tray, quran, quran_audio, quran_packs, quran_progress, settings_bundle, tracker, validation};
 
 #[tauri::command]
 pub async fn get_prayer_times(state: State<'_, AppState>) -> Result<PrayerTimes> {
     let calculator = state.calculator.read();
     let now = Local::now();
-    calculator.calculate_prayer_times(now)
+    Ok(calculator.calculate_prayer_times(now)?)
 }
 
 #[tauri::command]
@@ -16,9 +17,103 @@ pub async fn get_prayer_times_for_date(
 ) -> Result<PrayerTimes> {
     let calculator = state.calculator.read();
     let date = DateTime::parse_from_rfc3339(&date)
-        .map_err(|e| crate::error::AppError::Custom(format!("Invalid date format: {}", e)))?
+        .map_err(|e| crate::error::AppError::InvalidInput(format!("Invalid date format: {}", e)))?
         .with_timezone(&Local);
-    calculator.calculate_prayer_times(date)
+    Ok(calculator.calculate_prayer_times(date)?)
+}
+
+/// Reloads the calculator from whatever is currently persisted
+pub(crate) fn reload_calculator(state: &AppState) -> Result<()> {
+    // A multi-room follower keeps its leader's times
+    if let Some(schedule) = state.multi_room.leader_schedule() {
+        *state.calculator.write() = schedule.calculator()?;
+        return Ok(());
+    }
+
+    let (lat, lon, tz_str) = state.storage.get_location()?;
+    let settings = state.storage.get_settings()?;
+    let custom_times = state.storage.get_custom_times()?;
+    let jumuah_time = state.storage.get_jumuah_time()?;
+
+    let tz: chrono_tz::Tz = tz_str.parse()
+        .map_err(|e| crate::error::AppError::Timezone(format!("Invalid timezone: {}", e)))?;
+
+    let mut calculator = state.calculator.write();
+    calculator.update_settings(lat, lon, &settings.calculation, tz, custom_times, jumuah_time)?;
+    Ok(())
+}
+
+/// Refuses settings changes while a locked kiosk display runs
+pub(crate) fn ensure_unlocked(state: &AppState) -> Result<()> {
+    state.kiosk.check_unlocked(&state.storage.get_settings()?.kiosk)
+}
+
+/// Makes a saved location the current one, shared by the command and the tray
+pub(crate) fn apply_location_profile(state: &AppState, name: &str) -> Result<()> {
+    // The tray switches profiles too
+    ensure_unlocked(state)?;
+    let profile = state.storage
+        .get_location_profiles()?
+        .into_iter()
+        .find(|p| p.name == name)
+        .ok_or_else(|| crate::error::AppError::NotFound(format!("Unknown location profile: {}", name)))?;
+
+    state.storage.save_location(profile.latitude, profile.longitude, &profile.timezone)?;
+    state.storage.set_active_profile(Some(&profile.name))?;
+    reload_calculator(state)
+}
+
+#[tauri::command]
+pub async fn get_location_profiles(state: State<'_, AppState>) -> Result<(Vec<LocationProfile>, Option<String>)> {
+    Ok((state.storage.get_location_profiles()?, state.storage.get_active_profile()))
+}
+
+#[tauri::command]
+pub async fn save_location_profile(
+    profile: LocationProfile,
+    state: State<'_, AppState>,
+    app: AppHandle,
+) -> Result<()> {
+    ensure_unlocked(&state)?;
+    if profile.name.trim().is_empty() {
+        return Err(crate::error::AppError::InvalidInput("Profile name can't be empty".to_string()));
+    }
+    profile.timezone.parse::<chrono_tz::Tz>()
+        .map_err(|e| crate::error::AppError::Timezone(format!("Invalid timezone: {}", e)))?;
+
+    let mut profiles = state.storage.get_location_profiles()?;
+    match profiles.iter_mut().find(|p| p.name == profile.name) {
+        Some(existing) => *existing = profile,
+        None => profiles.push(profile),
+    }
+    state.storage.save_location_profiles(&profiles)?;
+    tray::update_tray_menu(&app)
+}
+
+#[tauri::command]
+pub async fn delete_location_profile(
+    name: String,
+    state: State<'_, AppState>,
+    app: AppHandle,
+) -> Result<()> {
+    ensure_unlocked(&state)?;
+    let mut profiles = state.storage.get_location_profiles()?;
+    profiles.retain(|p| p.name != name);
+    state.storage.save_location_profiles(&profiles)?;
+    if state.storage.get_active_profile().as_deref() == Some(name.as_str()) {
+        state.storage.set_active_profile(None)?;
+    }
+    tray::update_tray_menu(&app)
+}
+
+#[tauri::command]
+pub async fn switch_location_profile(
+    name: String,
+    state: State<'_, AppState>,
+    app: AppHandle,
+) -> Result<()> {
+    apply_location_profile(&state, &name)?;
+    tray::update_tray_menu(&app)
 }
 
 #[tauri::command]
@@ -27,18 +122,15 @@ pub async fn update_location(
     lon: f64,
     timezone: String,
     state: State<'_, AppState>,
+    app: AppHandle,
 ) -> Result<()> {
+    ensure_unlocked(&state)?;
     state.storage.save_location(lat, lon, &timezone)?;
-    
-    let tz: chrono_tz::Tz = timezone.parse()
-        .map_err(|e| crate::error::AppError::Timezone(format!("Invalid timezone: {}", e)))?;
-    
-    let settings = state.storage.get_settings()?;
-    let custom_times = state.storage.get_custom_times()?;
-    let jumuah_time = state.storage.get_jumuah_time()?;
-    
-    let mut calculator = state.calculator.write();
-    calculator.update_settings(lat, lon, &settings.calculation, tz, custom_times, jumuah_time)?;
+    reload_calculator(&state)?;
+
+    // A manually entered location no longer matches any saved profile
+    state.storage.set_active_profile(None)?;
+    tray::update_tray_menu(&app)?;
     
     Ok(())
 }
@@ -50,33 +142,57 @@ pub async fn get_settings(state: State<'_, AppState>) -> Result<AppSettings> {
 
 #[tauri::command]
 pub async fn update_settings(
-    settings: AppSettings,
+    mut settings: AppSettings,
     state: State<'_, AppState>,
+    app: AppHandle,
 ) -> Result<()> {
+    ensure_unlocked(&state)?;
+    validation::validate_settings(&settings)?;
+    http_api::ensure_token(&mut settings.http_api);
+    // Only `set_kiosk_pin` changes the PIN, and only the mute commands the
+    // mute, which a settings page opened before muting would undo
+    let stored = state.storage.get_settings()?;
+    settings.kiosk.pin_hash = stored.kiosk.pin_hash;
+    settings.muted_until = stored.muted_until;
     state.storage.save_settings(&settings)?;
+
+    // Switch notification and tray strings right away
+    i18n::set_language(&settings.language);
     
     // Update calculator if needed
-    let (lat, lon, tz_str) = state.storage.get_location()?;
-    let tz: chrono_tz::Tz = tz_str.parse()
-        .map_err(|e| crate::error::AppError::Timezone(format!("Invalid timezone: {}", e)))?;
-    
-    let custom_times = state.storage.get_custom_times()?;
-    let jumuah_time = state.storage.get_jumuah_time()?;
-    
-    let mut calculator = state.calculator.write();
-    calculator.update_settings(lat, lon, &settings.calculation, tz, custom_times, jumuah_time)?;
+    reload_calculator(&state)?;
+
+    tray::update_tray_menu(&app)?;
+    apply_services(&state, &app, &settings);
     
     Ok(())
 }
 
-#[tauri::command]
-pub async fn play_adhan(state: State<'_, AppState>) -> Result<()> {
+/// Starts, stops or restarts the optional services to match `settings`
+pub(crate) fn apply_services(state: &AppState, app: &AppHandle, settings: &AppSettings) {
+    if let Err(e) = state.http_api.apply(app, &settings.http_api) {
+        eprintln!("Error starting HTTP API: {}", e);
+    }
+    state.mqtt.apply(Arc::new(app.clone()), &state.events, &settings.mqtt);
+    state.multi_room.apply(Arc::new(app.clone()), &state.events, &settings.multi_room);
+}
+
+/// Plays the configured adhan, or the Fajr one when asked for Fajr
+pub(crate) fn play_configured_adhan(state: &AppState, prayer: Option<&str>) -> Result<()> {
     let settings = state.storage.get_settings()?;
-    let mut player = state.audio_player.write();
-    player.play_adhan(&settings.adhan_path)?;
+    let path = checker::adhan_path(&settings, prayer.unwrap_or_default());
+    state.audio_player.write().play_adhan(path)?;
+    if settings.pause_media {
+        state.media.adhan_started(state.audio_player.clone());
+    }
     Ok(())
 }
 
+#[tauri::command]
+pub async fn play_adhan(state: State<'_, AppState>) -> Result<()> {
+    play_configured_adhan(&state, None)
+}
+
 #[tauri::command]
 pub async fn stop_adhan(state: State<'_, AppState>) -> Result<()> {
     let mut player = state.audio_player.write();
@@ -97,10 +213,385 @@ pub async fn is_audio_playing(state: State<'_, AppState>) -> Result<bool> {
     Ok(player.is_playing())
 }
 
+#[tauri::command]
+pub async fn pause_adhan(state: State<'_, AppState>) -> Result<()> {
+    let mut player = state.audio_player.write();
+    player.pause();
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn resume_adhan(state: State<'_, AppState>) -> Result<()> {
+    let mut player = state.audio_player.write();
+    player.resume();
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn seek_audio(position: f64, state: State<'_, AppState>) -> Result<()> {
+    if !position.is_finite() || position < 0.0 {
+        return Err(crate::error::AppError::InvalidInput(format!("Invalid seek position: {}", position)));
+    }
+    let mut player = state.audio_player.write();
+    player.seek(std::time::Duration::from_secs_f64(position))
+}
+
+#[tauri::command]
+pub async fn get_audio_progress(state: State<'_, AppState>) -> Result<AudioProgress> {
+    let player = state.audio_player.read();
+    Ok(player.progress())
+}
+
+#[tauri::command]
+pub async fn get_quran_chapters(state: State<'_, AppState>) -> Result<Vec<quran::ChapterInfo>> {
+    Ok(state.quran.chapters().to_vec())
+}
+
+#[tauri::command]
+pub async fn get_surah(chapter: u32, state: State<'_, AppState>) -> Result<quran::Surah> {
+    state.quran.surah(chapter)
+}
+
+#[tauri::command]
+pub async fn get_verses(
+    chapter: u32,
+    start: u32,
+    end: u32,
+    state: State<'_, AppState>,
+) -> Result<Vec<quran::Verse>> {
+    state.quran.verse_range(chapter, start, end)
+}
+
+#[tauri::command]
+pub async fn search_quran(
+    query: String,
+    scope: Option<quran::SearchScope>,
+    limit: Option<usize>,
+    state: State<'_, AppState>,
+) -> Result<Vec<quran::SearchResult>> {
+    Ok(state.quran.search(&query, scope.unwrap_or(quran::SearchScope::All), limit.unwrap_or(50)))
+}
+
+#[tauri::command]
+pub async fn get_translation_catalog(state: State<'_, AppState>) -> Result<Vec<quran_packs::LanguageGroup>> {
+    Ok(state.quran_packs.catalog())
+}
+
+#[tauri::command]
+pub async fn get_installed_packs(state: State<'_, AppState>) -> Result<Vec<quran_packs::PackInfo>> {
+    Ok(state.quran_packs.installed())
+}
+
+#[tauri::command]
+pub async fn download_quran_pack(
+    kind: quran_packs::PackKind,
+    resource_id: u32,
+    state: State<'_, AppState>,
+) -> Result<quran_packs::PackInfo> {
+    state.quran_packs.download(kind, resource_id).await
+}
+
+#[tauri::command]
+pub async fn import_quran_pack(path: String, state: State<'_, AppState>) -> Result<quran_packs::PackInfo> {
+    state.quran_packs.import(std::path::Path::new(&path))
+}
+
+#[tauri::command]
+pub async fn remove_quran_pack(slug: String, state: State<'_, AppState>) -> Result<()> {
+    state.quran_packs.remove(&slug)
+}
+
+#[tauri::command]
+pub async fn get_parallel_verses(
+    chapter: u32,
+    start: u32,
+    end: u32,
+    packs: Vec<String>,
+    state: State<'_, AppState>,
+) -> Result<Vec<quran_packs::ParallelVerse>> {
+    state.quran_packs.parallel(&state.quran, chapter, start, end, &packs)
+}
+
+#[tauri::command]
+pub async fn get_quran_progress(state: State<'_, AppState>) -> Result<QuranProgress> {
+    state.storage.get_quran_progress()
+}
+
+#[tauri::command]
+pub async fn toggle_bookmark(
+    chapter: u32,
+    verse: u32,
+    label: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<bool> {
+    state.quran.verse(chapter, verse)?;
+    let mut progress = state.storage.get_quran_progress()?;
+    let bookmarked = quran_progress::toggle_bookmark(&mut progress, chapter, verse, label);
+    state.storage.save_quran_progress(&progress)?;
+    Ok(bookmarked)
+}
+
+#[tauri::command]
+pub async fn set_verse_note(
+    chapter: u32,
+    verse: u32,
+    text: String,
+    state: State<'_, AppState>,
+) -> Result<()> {
+    state.quran.verse(chapter, verse)?;
+    let mut progress = state.storage.get_quran_progress()?;
+    quran_progress::set_note(&mut progress, chapter, verse, &text);
+    state.storage.save_quran_progress(&progress)
+}
+
+#[tauri::command]
+pub async fn set_last_read(chapter: u32, verse: u32, state: State<'_, AppState>) -> Result<()> {
+    let mut progress = state.storage.get_quran_progress()?;
+    quran_progress::set_last_read(&mut progress, &state.quran, chapter, verse)?;
+    state.storage.save_quran_progress(&progress)
+}
+
+#[tauri::command]
+pub async fn create_khatm_plan(
+    days: u32,
+    unit: KhatmUnit,
+    reminder: Option<KhatmReminder>,
+    state: State<'_, AppState>,
+) -> Result<KhatmStatus> {
+    let today = Local::now().date_naive();
+    let plan = quran_progress::new_plan(today, days, unit, reminder)?;
+    let status = quran_progress::khatm_status(&plan, today);
+
+    let mut progress = state.storage.get_quran_progress()?;
+    progress.khatm = Some(plan);
+    state.storage.save_quran_progress(&progress)?;
+    Ok(status)
+}
+
+#[tauri::command]
+pub async fn record_khatm_pages(pages_read: u32, state: State<'_, AppState>) -> Result<KhatmStatus> {
+    let mut progress = state.storage.get_quran_progress()?;
+    let plan = progress.khatm.as_mut()
+        .ok_or_else(|| crate::error::AppError::NotFound("No khatm plan".to_string()))?;
+    quran_progress::record_pages(plan, pages_read);
+    let status = quran_progress::khatm_status(plan, Local::now().date_naive());
+    state.storage.save_quran_progress(&progress)?;
+    Ok(status)
+}
+
+#[tauri::command]
+pub async fn cancel_khatm_plan(state: State<'_, AppState>) -> Result<()> {
+    let mut progress = state.storage.get_quran_progress()?;
+    progress.khatm = None;
+    state.storage.save_quran_progress(&progress)
+}
+
+#[tauri::command]
+pub async fn get_khatm_status(state: State<'_, AppState>) -> Result<Option<KhatmStatus>> {
+    let progress = state.storage.get_quran_progress()?;
+    Ok(progress.khatm.map(|plan| quran_progress::khatm_status(&plan, Local::now().date_naive())))
+}
+
+#[tauri::command]
+pub async fn export_quran_progress(path: String, state: State<'_, AppState>) -> Result<()> {
+    let progress = state.storage.get_quran_progress()?;
+    quran_progress::export(&progress, std::path::Path::new(&path))
+}
+
+#[tauri::command]
+pub async fn import_quran_progress(
+    path: String,
+    merge: bool,
+    state: State<'_, AppState>,
+) -> Result<QuranProgress> {
+    let current = state.storage.get_quran_progress()?;
+    let progress = quran_progress::import(&current, std::path::Path::new(&path), merge)?;
+    state.storage.save_quran_progress(&progress)?;
+    Ok(progress)
+}
+
+#[tauri::command]
+pub async fn get_recitations(state: State<'_, AppState>) -> Result<Vec<quran_audio::Recitation>> {
+    Ok(state.quran_audio.recitations().to_vec())
+}
+
+#[tauri::command]
+pub async fn play_quran(
+    request: QuranPlaybackRequest,
+    state: State<'_, AppState>,
+    app: AppHandle,
+) -> Result<()> {
+    quran_audio::play_surah(state.quran_audio.clone(), state.audio_player.clone(), request, app).await
+}
+
+#[tauri::command]
+pub async fn stop_quran(state: State<'_, AppState>) -> Result<()> {
+    state.quran_audio.stop(&state.audio_player);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_quran_playback_state(state: State<'_, AppState>) -> Result<QuranPlaybackState> {
+    Ok(state.quran_audio.state())
+}
+
+#[tauri::command]
+pub async fn download_surah_audio(
+    reciter_id: u32,
+    chapter: u32,
+    state: State<'_, AppState>,
+    app: AppHandle,
+) -> Result<()> {
+    state.quran_audio.download_surah(reciter_id, chapter, &app).await
+}
+
+#[tauri::command]
+pub async fn clear_quran_audio_cache(state: State<'_, AppState>) -> Result<()> {
+    state.quran_audio.clear_cache()
+}
+
+#[tauri::command]
+pub async fn mute_adhan(
+    request: MuteRequest,
+    state: State<'_, AppState>,
+    app: AppHandle,
+) -> Result<MuteStatus> {
+    ensure_unlocked(&state)?;
+    apply_mute(&state, &app, request)
+}
+
+/// Shared by the command and the HTTP API
+pub(crate) fn apply_mute(state: &AppState, app: &AppHandle, request: MuteRequest) -> Result<MuteStatus> {
+    let now = Local::now();
+    let next_prayer = state.calculator.read().get_next_prayer().ok().map(|(_, time)| time);
+
+    let mut settings = state.storage.get_settings()?;
+    settings.muted_until = Some(mute::mute_until(request, now, next_prayer)?);
+    state.storage.save_settings(&settings)?;
+    tray::update_tray_menu(app)?;
+
+    Ok(mute::mute_status(&settings, now))
+}
+
+#[tauri::command]
+pub async fn unmute_adhan(state: State<'_, AppState>, app: AppHandle) -> Result<MuteStatus> {
+    ensure_unlocked(&state)?;
+    apply_unmute(&state, &app)
+}
+
+pub(crate) fn apply_unmute(state: &AppState, app: &AppHandle) -> Result<MuteStatus> {
+    let mut settings = state.storage.get_settings()?;
+    settings.muted_until = None;
+    state.storage.save_settings(&settings)?;
+    tray::update_tray_menu(app)?;
+
+    // Quiet windows still apply, they're part of the saved schedule
+    Ok(mute::mute_status(&settings, Local::now()))
+}
+
+#[tauri::command]
+pub async fn get_mute_status(state: State<'_, AppState>) -> Result<MuteStatus> {
+    let settings = state.storage.get_settings()?;
+    Ok(mute::mute_status(&settings, Local::now()))
+}
+
 #[tauri::command]
 pub async fn get_qibla_direction(state: State<'_, AppState>) -> Result<f64> {
     let calculator = state.calculator.read();
-    calculator.get_qibla_direction()
+    Ok(calculator.get_qibla_direction()?)
+}
+
+#[tauri::command]
+pub async fn get_backend_language() -> Result<(String, bool)> {
+    let lang = i18n::language();
+    let rtl = i18n::is_rtl(&lang);
+    Ok((lang, rtl))
+}
+
+#[tauri::command]
+pub async fn notification_action(
+    action: notifications::NotificationAction,
+    prayer: String,
+    app: AppHandle,
+) -> Result<()> {
+    notifications::handle_action(&app, action, &prayer);
+    Ok(())
+}
+
+/// Marks a prayer as prayed from a notification button or the tray, on time
+/// or late depending on whether its window has closed
+pub(crate) fn mark_prayed(state: &AppState, prayer: &str) -> Result<PrayerLogEntry> {
+    let now = Local::now();
+    let times = state.calculator.read().calculate_prayer_times(now)?;
+    let (date, status) = tracker::status_for_mark(prayer, &times, now);
+    state.tracker.record(date, prayer, status)
+}
+
+#[tauri::command]
+pub async fn record_prayer(
+    date: NaiveDate,
+    prayer: String,
+    status: PrayerStatus,
+    state: State<'_, AppState>,
+) -> Result<PrayerLogEntry> {
+    state.tracker.record(date, &prayer, status)
+}
+
+#[tauri::command]
+pub async fn clear_prayer_record(date: NaiveDate, prayer: String, state: State<'_, AppState>) -> Result<()> {
+    state.tracker.clear(date, &prayer)
+}
+
+#[tauri::command]
+pub async fn get_prayer_log(
+    from: NaiveDate,
+    to: NaiveDate,
+    state: State<'_, AppState>,
+) -> Result<Vec<PrayerLogEntry>> {
+    state.tracker.entries(from, to)
+}
+
+#[tauri::command]
+pub async fn get_prayer_streak(state: State<'_, AppState>) -> Result<PrayerStreak> {
+    state.tracker.streak(Local::now().date_naive())
+}
+
+#[tauri::command]
+pub async fn get_prayer_stats(
+    period: StatsPeriod,
+    date: Option<NaiveDate>,
+    state: State<'_, AppState>,
+) -> Result<PrayerStats> {
+    let today = Local::now().date_naive();
+    state.tracker.stats(period, date.unwrap_or(today), today)
+}
+
+#[tauri::command]
+pub async fn get_qada_balance(state: State<'_, AppState>) -> Result<Vec<QadaBalance>> {
+    state.tracker.qada_balance()
+}
+
+#[tauri::command]
+pub async fn set_qada_backlog(prayer: String, count: u32, state: State<'_, AppState>) -> Result<Vec<QadaBalance>> {
+    state.tracker.set_qada_backlog(&prayer, count)?;
+    state.tracker.qada_balance()
+}
+
+#[tauri::command]
+pub async fn record_qada(prayer: String, count: u32, state: State<'_, AppState>) -> Result<Vec<QadaBalance>> {
+    state.tracker.record_qada(&prayer, count)?;
+    state.tracker.qada_balance()
+}
+
+#[tauri::command]
+pub async fn undo_qada(prayer: String, count: u32, state: State<'_, AppState>) -> Result<Vec<QadaBalance>> {
+    state.tracker.undo_qada(&prayer, count)?;
+    state.tracker.qada_balance()
+}
+
+#[tauri::command]
+pub async fn export_prayer_log(path: String, state: State<'_, AppState>) -> Result<()> {
+    state.tracker.export_csv(std::path::Path::new(&path))
 }
 
 #[tauri::command]
@@ -110,6 +601,121 @@ pub async fn get_next_prayer(state: State<'_, AppState>) -> Result<(String, Stri
     Ok((name, time.to_rfc3339()))
 }
 
+#[tauri::command]
+pub async fn export_settings_bundle(path: String, state: State<'_, AppState>) -> Result<()> {
+    settings_bundle::export(&state.storage, std::path::Path::new(&path))
+}
+
+/// Checks a bundle and lists what importing it would change
+#[tauri::command]
+pub async fn preview_settings_bundle(path: String, state: State<'_, AppState>) -> Result<BundlePreview> {
+    settings_bundle::preview(&state.storage, std::path::Path::new(&path))
+}
+
+#[tauri::command]
+pub async fn import_settings_bundle(
+    path: String,
+    state: State<'_, AppState>,
+    app: AppHandle,
+) -> Result<Vec<SettingChange>> {
+    ensure_unlocked(&state)?;
+    let (_, mut payload) = settings_bundle::read(std::path::Path::new(&path))?;
+    validation::validate_settings(&payload.settings)?;
+    if let Some(ref custom_times) = payload.custom_times {
+        validation::validate_custom_times(custom_times)?;
+    }
+    if let Some(ref jumuah_time) = payload.jumuah_time {
+        validation::validate_jumuah_time(jumuah_time)?;
+    }
+    let changes = settings_bundle::changes(&state.storage, &payload)?;
+    // Bundles never carry a mute or secrets, keep this machine's own
+    settings_bundle::keep_local(&mut payload.settings, &state.storage.get_settings()?);
+    http_api::ensure_token(&mut payload.settings.http_api);
+
+    state.storage.apply_bundle(&payload)?;
+
+    i18n::set_language(&payload.settings.language);
+    reload_calculator(&state)?;
+    tray::update_tray_menu(&app)?;
+    apply_services(&state, &app, &payload.settings);
+    let _ = app.emit("settings-updated", &payload.settings);
+    let _ = app.emit("prayers-updated", ());
+    Ok(changes)
+}
+
+#[tauri::command]
+pub async fn find_electron_config() -> Result<Option<String>> {
+    Ok(electron_import::find_config().map(|p| p.to_string_lossy().into_owned()))
+}
+
+/// Imports an Electron Muezzin config, from `path` or its usual location
+#[tauri::command]
+pub async fn import_electron_settings(
+    path: Option<String>,
+    dry_run: bool,
+    state: State<'_, AppState>,
+    app: AppHandle,
+) -> Result<ElectronImportResult> {
+    ensure_unlocked(&state)?;
+    let path = match path {
+        Some(path) => std::path::PathBuf::from(path),
+        None => electron_import::find_config()
+            .ok_or_else(|| crate::error::AppError::NotFound("No Electron Muezzin config found".to_string()))?,
+    };
+    let imported = electron_import::read_config(&path, state.storage.get_settings()?)?;
+
+    // A dry run reports the same field errors the import would fail with
+    validation::validate_settings(&imported.settings)?;
+    if let Some(ref custom_times) = imported.custom_times {
+        validation::validate_custom_times(custom_times)?;
+    }
+    if let Some(ref jumuah_time) = imported.jumuah_time {
+        validation::validate_jumuah_time(jumuah_time)?;
+    }
+
+    if !dry_run {
+        state.storage.save_settings(&imported.settings)?;
+        if let Some((lat, lon, ref tz)) = imported.location {
+            state.storage.save_location(lat, lon, tz)?;
+            state.storage.set_active_profile(None)?;
+            state.storage.set_first_time_done()?;
+        }
+        if let Some(ref custom_times) = imported.custom_times {
+            state.storage.save_custom_times(custom_times)?;
+        }
+        if let Some(ref jumuah_time) = imported.jumuah_time {
+            state.storage.save_jumuah_time(jumuah_time)?;
+        }
+
+        i18n::set_language(&imported.settings.language);
+        reload_calculator(&state)?;
+        tray::update_tray_menu(&app)?;
+        let _ = app.emit("settings-updated", &imported.settings);
+        let _ = app.emit("prayers-updated", ());
+    }
+
+    Ok(ElectronImportResult {
+        source: path.to_string_lossy().into_owned(),
+        applied: !dry_run,
+        report: imported.report,
+    })
+}
+
+#[tauri::command]
+pub async fn get_storage_report(state: State<'_, AppState>) -> Result<StorageReport> {
+    Ok(state.storage.report())
+}
+
+/// Resets a stored value the report flagged as corrupt, then reloads the calculator
+#[tauri::command]
+pub async fn reset_storage_value(key: String, state: State<'_, AppState>, app: AppHandle) -> Result<StorageReport> {
+    ensure_unlocked(&state)?;
+    state.storage.reset_value(&key)?;
+    reload_calculator(&state)?;
+    tray::update_tray_menu(&app)?;
+    Ok(state.storage.report())
+}
+
 #[tauri::command]
 pub async fn check_for_updates() -> Result<Option<String>> {
     let client = reqwest::Client::new();
@@ -180,17 +786,7 @@ pub async fn initialize_first_time(state: State<'_, AppState>) -> Result<bool> {
         }
     } else {
         // Not first time, load existing settings
-        let (lat, lon, tz_str) = state.storage.get_location()?;
-        let settings = state.storage.get_settings()?;
-        let custom_times = state.storage.get_custom_times()?;
-        let jumuah_time = state.storage.get_jumuah_time()?;
-        
-        let tz: chrono_tz::Tz = tz_str.parse()
-            .map_err(|e| crate::error::AppError::Timezone(format!("Invalid timezone: {}", e)))?;
-        
-        let mut calculator = state.calculator.write();
-        calculator.update_settings(lat, lon, &settings.calculation, tz, custom_times, jumuah_time)?;
-        
+        reload_calculator(&state)?;
         Ok(false)
     }
 }
@@ -200,20 +796,12 @@ pub async fn update_custom_times(
     custom_times: CustomTimes,
     state: State<'_, AppState>,
 ) -> Result<()> {
+    ensure_unlocked(&state)?;
+    validation::validate_custom_times(&custom_times)?;
     state.storage.save_custom_times(&custom_times)?;
     
     // Reload calculator settings
-    let (lat, lon, tz_str) = state.storage.get_location()?;
-    let settings = state.storage.get_settings()?;
-    let jumuah_time = state.storage.get_jumuah_time()?;
-    
-    let tz: chrono_tz::Tz = tz_str.parse()
-        .map_err(|e| crate::error::AppError::Timezone(format!("Invalid timezone: {}", e)))?;
-    
-    let mut calculator = state.calculator.write();
-    calculator.update_settings(lat, lon, &settings.calculation, tz, Some(custom_times), jumuah_time)?;
-    
-    Ok(())
+    reload_calculator(&state)
 }
 
 #[tauri::command]
@@ -221,18 +809,87 @@ pub async fn update_jumuah_time(
     jumuah_time: JumuahTime,
     state: State<'_, AppState>,
 ) -> Result<()> {
+    ensure_unlocked(&state)?;
+    validation::validate_jumuah_time(&jumuah_time)?;
     state.storage.save_jumuah_time(&jumuah_time)?;
     
     // Reload calculator settings
-    let (lat, lon, tz_str) = state.storage.get_location()?;
+    reload_calculator(&state)
+}
+
+#[tauri::command]
+pub async fn get_hook_log(state: State<'_, AppState>) -> Result<Vec<HookExecution>> {
+    Ok(state.hooks.log())
+}
+
+/// Runs a hook once with a sample payload, so it can be tried from the settings page
+#[tauri::command]
+pub async fn test_hook(hook: Hook, state: State<'_, AppState>) -> Result<HookExecution> {
+    // A hook can run any command, visitors at the display mustn't try one
+    ensure_unlocked(&state)?;
+    validation::validate_hook(&hook)?;
+    let event = hook.events.first().copied().unwrap_or(HookEvent::AdhanStart);
+    Ok(state.hooks.run(&hook, &hooks::HookPayload::sample(event, Local::now())).await)
+}
+
+#[tauri::command]
+pub async fn get_kiosk_display(state: State<'_, AppState>) -> Result<KioskDisplay> {
     let settings = state.storage.get_settings()?;
-    let custom_times = state.storage.get_custom_times()?;
-    
-    let tz: chrono_tz::Tz = tz_str.parse()
-        .map_err(|e| crate::error::AppError::Timezone(format!("Invalid timezone: {}", e)))?;
-    
-    let mut calculator = state.calculator.write();
-    calculator.update_settings(lat, lon, &settings.calculation, tz, custom_times, Some(jumuah_time))?;
-    
+    kiosk::display(&state.calculator.read(), &settings, state.kiosk.is_active(), Local::now())
+}
+
+#[tauri::command]
+pub async fn enter_kiosk(app: AppHandle) -> Result<()> {
+    kiosk::set_active(&app, true)
+}
+
+/// Leaves the display, which needs the PIN when one is set. A few wrong
+/// PINs in a row lock every try out for a while.
+#[tauri::command]
+pub async fn exit_kiosk(pin: String, state: State<'_, AppState>, app: AppHandle) -> Result<()> {
+    state.kiosk.unlock(&state.storage.get_settings()?.kiosk, &pin, std::time::Instant::now())?;
+    kiosk::set_active(&app, false)
+}
+
+/// Sets the PIN that locks settings on the display, an empty one removes it
+#[tauri::command]
+pub async fn set_kiosk_pin(pin: String, state: State<'_, AppState>, app: AppHandle) -> Result<()> {
+    ensure_unlocked(&state)?;
+    if !pin.is_empty() && pin.chars().count() < kiosk::MIN_PIN_LENGTH {
+        return Err(crate::error::AppError::InvalidInput(format!(
+            "The PIN needs at least {} characters",
+            kiosk::MIN_PIN_LENGTH
+        )));
+    }
+    let mut settings = state.storage.get_settings()?;
+    settings.kiosk.pin_hash = if pin.is_empty() { String::new() } else { kiosk::hash_pin(&pin) };
+    state.storage.save_settings(&settings)?;
+    kiosk::refresh(&app);
     Ok(())
 }
+
+#[tauri::command]
+pub async fn get_multi_room_status(state: State<'_, AppState>) -> Result<MultiRoomStatus> {
+    Ok(state.multi_room.status())
+}
+
+#[tauri::command]
+pub async fn get_mqtt_status(state: State<'_, AppState>) -> Result<MqttStatus> {
+    Ok(state.mqtt.status())
+}
+
+#[tauri::command]
+pub async fn get_http_api_status(state: State<'_, AppState>) -> Result<HttpApiStatus> {
+    Ok(state.http_api.status())
+}
+
+/// Replaces the token, locking out every client still using the old one
+#[tauri::command]
+pub async fn regenerate_http_api_token(state: State<'_, AppState>, app: AppHandle) -> Result<String> {
+    ensure_unlocked(&state)?;
+    let mut settings = state.storage.get_settings()?;
+    settings.http_api.token = http_api::generate_token();
+    state.storage.save_settings(&settings)?;
+    apply_services(&state, &app, &settings);
+    Ok(settings.http_api.token)
+}
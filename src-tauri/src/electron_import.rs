@@ -0,0 +1,52 @@
+//! Import from an Electron Muezzin (v2.6) installation.
+//!
+//! electron-store keeps everything in `muezzin/config.json` under the platform
+//! config directory: `~/.config` on Linux, `~/Library/Application Support` on
+//! macOS and `%APPDATA%` on Windows. The keys are mapped by
+//! `migrations::map_electron_config`, the same mapping used for a store that
+//! was copied over by hand.
+
+use serde_json::{Map, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::audio::resolve_resource_path;
+use crate::migrations::{self, ElectronImport};
+use crate::models::{AppSettings, SkippedKey};
+use crate::error::{AppError, Result};
+
+/// The old config file, if there is one where Electron Muezzin put it
+pub fn find_config() -> Option<PathBuf> {
+    let dirs = directories::BaseDirs::new()?;
+    let path = dirs.config_dir().join("muezzin").join("config.json");
+    path.is_file().then_some(path)
+}
+
+pub fn read_config(path: &Path, current: AppSettings) -> Result<ElectronImport> {
+    let config: Map<String, Value> = serde_json::from_str(&fs::read_to_string(path)?)
+        .map_err(|e| AppError::InvalidFile(format!("Invalid Electron Muezzin config: {}", e)))?;
+
+    let mut imported = migrations::map_electron_config(&config, current.clone());
+
+    // Custom Adhan files are only worth keeping if they are still there
+    if imported.settings.adhan_path != current.adhan_path
+        && !resolve_resource_path(&imported.settings.adhan_path).is_file()
+    {
+        imported.report.skipped.push(SkippedKey {
+            key: "adhan.adhan".to_string(),
+            reason: format!("File not found: {}", imported.settings.adhan_path),
+        });
+        imported.settings.adhan_path = current.adhan_path;
+    }
+    if let Some(path) = imported.settings.adhan_fajr_path.clone().filter(|p| Some(p) != current.adhan_fajr_path.as_ref()) {
+        if !resolve_resource_path(&path).is_file() {
+            imported.report.skipped.push(SkippedKey {
+                key: "adhan.adhanFajr".to_string(),
+                reason: format!("File not found: {}", path),
+            });
+            imported.settings.adhan_fajr_path = current.adhan_fajr_path;
+        }
+    }
+
+    Ok(imported)
+}
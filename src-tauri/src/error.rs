@@ -1,5 +1,9 @@
+use serde::Serialize;
+use serde_json::Value;
 use thiserror::Error;
 
+use crate::validation::FieldError;
+
 #[derive(Error, Debug)]
 pub enum AppError {
     #[error("Not initialized")]
@@ -11,12 +15,25 @@ pub enum AppError {
     #[error("Serialization error: {0}")]
     SerdeJson(#[from] serde_json::Error),
 
+    #[cfg(feature = "gui")]
     #[error("Tauri error: {0}")]
     Tauri(#[from] tauri::Error),
 
     #[error("HTTP request error: {0}")]
     Reqwest(#[from] reqwest::Error),
 
+    /// A remote service answered, but not with what was asked for
+    #[error("{0}")]
+    Http(String),
+
+    /// A peer that can't be found or reached, or that hung up
+    #[error("Network error: {0}")]
+    Network(String),
+
+    /// Something that took longer than it is allowed to
+    #[error("{0}")]
+    Timeout(String),
+
     #[error("Audio error: {0}")]
     Rodio(String),
 
@@ -26,23 +43,286 @@ pub enum AppError {
     #[error("Timezone parse error: {0}")]
     Timezone(String),
 
+    #[cfg(feature = "gui")]
     #[error("Store error: {0}")]
     Store(#[from] tauri_plugin_store::Error),
 
+    #[error("Stored value '{key}' is corrupt: {reason}")]
+    CorruptValue { key: String, reason: String },
+
+    #[error("Database error: {0}")]
+    Database(#[from] rusqlite::Error),
+
     #[error("DateTime parse error: {0}")]
     ChronoParse(#[from] chrono::ParseError),
 
+    /// A date or time that doesn't exist locally, e.g. in a DST gap
+    #[error("{0}")]
+    InvalidDate(String),
+
+    #[error("Invalid settings: {}", .0.iter().map(|e| format!("{} ({})", e.field, e.message)).collect::<Vec<_>>().join(", "))]
+    Validation(Vec<FieldError>),
+
+    /// A command argument out of range or malformed
+    #[error("{0}")]
+    InvalidInput(String),
+
+    #[error("{0}")]
+    NotFound(String),
+
+    /// An imported or downloaded file that can't be used
+    #[error("{0}")]
+    InvalidFile(String),
+
+    /// Bundled or stored data that contradicts itself
     #[error("{0}")]
-    Custom(String),
+    InvalidData(String),
+
+    /// A hook's program that ran but failed
+    #[error("Command failed: {0}")]
+    CommandFailed(String),
+
+    /// An HTTP API request without the right token
+    #[error("Missing or wrong access token")]
+    Unauthorized,
+
+    /// A settings change, or a wrong PIN, while the kiosk display is locked
+    #[error("Settings are locked on this display")]
+    Locked,
+
+    /// Too many wrong PINs, leaving the kiosk display waits a while
+    #[error("Too many wrong PINs, try again in {retry_after_secs}s")]
+    TooManyAttempts { retry_after_secs: u64 },
+}
+
+/// Stable identifiers for the frontend. Never rename one, add a new one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ErrorCode {
+    NotInitialized,
+    IoError,
+    SerializationError,
+    NetworkError,
+    HttpError,
+    AudioError,
+    CalculationError,
+    InvalidTimezone,
+    StorageError,
+    CorruptValue,
+    DatabaseError,
+    InvalidDate,
+    ValidationFailed,
+    InvalidInput,
+    NotFound,
+    InvalidFile,
+    Internal,
+    Unauthorized,
+    Locked,
+    TooManyAttempts,
+    Timeout,
+    InvalidData,
+    CommandFailed,
+}
+
+impl ErrorCode {
+    /// Key of the generic, translated message in the locale catalogs
+    pub fn message_key(self) -> &'static str {
+        match self {
+            ErrorCode::NotInitialized => "error.notInitialized",
+            ErrorCode::IoError => "error.ioError",
+            ErrorCode::SerializationError => "error.serializationError",
+            ErrorCode::NetworkError => "error.networkError",
+            ErrorCode::HttpError => "error.httpError",
+            ErrorCode::AudioError => "error.audioError",
+            ErrorCode::CalculationError => "error.calculationError",
+            ErrorCode::InvalidTimezone => "error.invalidTimezone",
+            ErrorCode::StorageError => "error.storageError",
+            ErrorCode::CorruptValue => "error.corruptValue",
+            ErrorCode::DatabaseError => "error.databaseError",
+            ErrorCode::InvalidDate => "error.invalidDate",
+            ErrorCode::ValidationFailed => "error.validationFailed",
+            ErrorCode::InvalidInput => "error.invalidInput",
+            ErrorCode::NotFound => "error.notFound",
+            ErrorCode::InvalidFile => "error.invalidFile",
+            ErrorCode::Internal => "error.internal",
+            ErrorCode::Unauthorized => "error.unauthorized",
+            ErrorCode::Locked => "error.locked",
+            ErrorCode::TooManyAttempts => "error.tooManyAttempts",
+            ErrorCode::Timeout => "error.timeout",
+            ErrorCode::InvalidData => "error.invalidData",
+            ErrorCode::CommandFailed => "error.commandFailed",
+        }
+    }
+}
+
+impl AppError {
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            AppError::NotInitialized => ErrorCode::NotInitialized,
+            AppError::Io(_) => ErrorCode::IoError,
+            AppError::SerdeJson(_) => ErrorCode::SerializationError,
+            #[cfg(feature = "gui")]
+            AppError::Tauri(_) => ErrorCode::Internal,
+            AppError::Reqwest(_) => ErrorCode::NetworkError,
+            AppError::Http(_) => ErrorCode::HttpError,
+            AppError::Network(_) => ErrorCode::NetworkError,
+            AppError::Timeout(_) => ErrorCode::Timeout,
+            AppError::Rodio(_) => ErrorCode::AudioError,
+            AppError::Prayer(_) => ErrorCode::CalculationError,
+            AppError::Timezone(_) => ErrorCode::InvalidTimezone,
+            #[cfg(feature = "gui")]
+            AppError::Store(_) => ErrorCode::StorageError,
+            AppError::CorruptValue { .. } => ErrorCode::CorruptValue,
+            AppError::Database(_) => ErrorCode::DatabaseError,
+            AppError::ChronoParse(_) | AppError::InvalidDate(_) => ErrorCode::InvalidDate,
+            AppError::Validation(_) => ErrorCode::ValidationFailed,
+            AppError::InvalidInput(_) => ErrorCode::InvalidInput,
+            AppError::NotFound(_) => ErrorCode::NotFound,
+            AppError::InvalidFile(_) => ErrorCode::InvalidFile,
+            AppError::InvalidData(_) => ErrorCode::InvalidData,
+            AppError::CommandFailed(_) => ErrorCode::CommandFailed,
+            AppError::Unauthorized => ErrorCode::Unauthorized,
+            AppError::Locked => ErrorCode::Locked,
+            AppError::TooManyAttempts { .. } => ErrorCode::TooManyAttempts,
+        }
+    }
+
+    /// Extra data the frontend can act on, e.g. which fields to highlight
+    pub fn details(&self) -> Option<Value> {
+        match self {
+            AppError::Validation(errors) => Some(serde_json::json!({ "errors": errors })),
+            AppError::CorruptValue { key, .. } => Some(serde_json::json!({ "key": key })),
+            AppError::Io(e) => Some(serde_json::json!({ "kind": format!("{:?}", e.kind()) })),
+            AppError::Reqwest(e) => e.status().map(|status| serde_json::json!({ "status": status.as_u16() })),
+            AppError::TooManyAttempts { retry_after_secs } => Some(serde_json::json!({ "retryAfterSecs": retry_after_secs })),
+            _ => None,
+        }
+    }
+}
+
+/// What the frontend receives when a command fails
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SerializedError {
+    code: ErrorCode,
+    message: String,
+    message_key: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    details: Option<Value>,
 }
 
 impl serde::Serialize for AppError {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
     where
         S: serde::ser::Serializer,
     {
-        serializer.serialize_str(self.to_string().as_ref())
+        let code = self.code();
+        SerializedError {
+            code,
+            message: self.to_string(),
+            message_key: code.message_key(),
+            details: self.details(),
+        }
+        .serialize(serializer)
+    }
+}
+
+/// Calculation errors keep the codes they had before the core was split out
+impl From<muezzin_core::Error> for AppError {
+    fn from(error: muezzin_core::Error) -> Self {
+        match error {
+            muezzin_core::Error::NotInitialized => AppError::NotInitialized,
+            muezzin_core::Error::Prayer(message) => AppError::Prayer(message),
+            muezzin_core::Error::InvalidInput(message) => AppError::InvalidInput(message),
+        }
     }
 }
 
 pub type Result<T> = std::result::Result<T, AppError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::validation::{FieldError, FieldErrorCode};
+    use serde_json::json;
+
+    #[test]
+    fn plain_error_has_code_message_and_key() {
+        let value = serde_json::to_value(AppError::NotInitialized).unwrap();
+        assert_eq!(value, json!({
+            "code": "NOT_INITIALIZED",
+            "message": "Not initialized",
+            "messageKey": "error.notInitialized",
+        }));
+    }
+
+    #[test]
+    fn timezone_error_is_distinct() {
+        let value = serde_json::to_value(AppError::Timezone("Invalid timezone: Mars/Olympus".into())).unwrap();
+        assert_eq!(value["code"], "INVALID_TIMEZONE");
+        assert_eq!(value["messageKey"], "error.invalidTimezone");
+        assert!(value.get("details").is_none());
+    }
+
+    #[test]
+    fn validation_error_lists_fields() {
+        let error = AppError::Validation(vec![FieldError {
+            field: "reminder_times.fajr".into(),
+            code: FieldErrorCode::OutOfRange,
+            message: "500 must be between 0 and 120 minutes".into(),
+        }]);
+        let value = serde_json::to_value(error).unwrap();
+        assert_eq!(value["code"], "VALIDATION_FAILED");
+        assert_eq!(value["details"], json!({
+            "errors": [{
+                "field": "reminder_times.fajr",
+                "code": "outOfRange",
+                "message": "500 must be between 0 and 120 minutes",
+            }]
+        }));
+    }
+
+    #[test]
+    fn corrupt_value_names_the_key() {
+        let error = AppError::CorruptValue { key: "settings".into(), reason: "expected a boolean".into() };
+        let value = serde_json::to_value(error).unwrap();
+        assert_eq!(value["code"], "CORRUPT_VALUE");
+        assert_eq!(value["details"], json!({ "key": "settings" }));
+        assert_eq!(value["message"], "Stored value 'settings' is corrupt: expected a boolean");
+    }
+
+    #[test]
+    fn not_found_code() {
+        let value = serde_json::to_value(AppError::NotFound("Unknown location profile: Home".into())).unwrap();
+        assert_eq!(value["code"], "NOT_FOUND");
+        assert_eq!(value["message"], "Unknown location profile: Home");
+    }
+
+    #[cfg(feature = "gui")]
+    #[test]
+    fn tauri_errors_are_internal() {
+        let value = serde_json::to_value(AppError::Tauri(tauri::Error::WindowNotFound)).unwrap();
+        assert_eq!(value["code"], "INTERNAL");
+        assert_eq!(value["messageKey"], "error.internal");
+    }
+
+    #[test]
+    fn timeouts_and_network_errors_are_distinct() {
+        let value = serde_json::to_value(AppError::Timeout("Timed out after 10s".into())).unwrap();
+        assert_eq!(value["code"], "TIMEOUT");
+        assert_eq!(value["messageKey"], "error.timeout");
+        assert_eq!(value["message"], "Timed out after 10s");
+
+        let value = serde_json::to_value(AppError::Network("The leader closed the connection".into())).unwrap();
+        assert_eq!(value["code"], "NETWORK_ERROR");
+        assert_eq!(value["message"], "Network error: The leader closed the connection");
+    }
+
+    #[test]
+    fn io_error_reports_its_kind() {
+        let error = AppError::from(std::io::Error::new(std::io::ErrorKind::NotFound, "missing"));
+        let value = serde_json::to_value(error).unwrap();
+        assert_eq!(value["code"], "IO_ERROR");
+        assert_eq!(value["details"], json!({ "kind": "NotFound" }));
+    }
+}
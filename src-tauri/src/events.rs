@@ -0,0 +1,73 @@
+//! Prayer events fanned out to integrations such as the HTTP API's event
+//! stream. Publishing never blocks, a subscriber that falls behind loses the
+//! oldest events.
+
+use chrono::{DateTime, Local, NaiveDate};
+use serde::Serialize;
+use tokio::sync::broadcast;
+
+const CAPACITY: usize = 64;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum PrayerEvent {
+    Reminder {
+        prayer: String,
+        time: DateTime<Local>,
+        minutes: u32,
+    },
+    Adhan {
+        prayer: String,
+        time: DateTime<Local>,
+        with_sound: bool,
+    },
+    AdhanEnd {
+        prayer: String,
+    },
+    Iqamah {
+        prayer: String,
+        time: DateTime<Local>,
+    },
+    DateChanged {
+        date: NaiveDate,
+    },
+}
+
+impl PrayerEvent {
+    /// `type` tag, also used as the SSE event name
+    pub fn kind(&self) -> &'static str {
+        match self {
+            PrayerEvent::Reminder { .. } => "reminder",
+            PrayerEvent::Adhan { .. } => "adhan",
+            PrayerEvent::AdhanEnd { .. } => "adhanEnd",
+            PrayerEvent::Iqamah { .. } => "iqamah",
+            PrayerEvent::DateChanged { .. } => "dateChanged",
+        }
+    }
+}
+
+pub struct EventBus {
+    sender: broadcast::Sender<PrayerEvent>,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        let (sender, _) = broadcast::channel(CAPACITY);
+        Self { sender }
+    }
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn publish(&self, event: PrayerEvent) {
+        // No subscribers is fine
+        let _ = self.sender.send(event);
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<PrayerEvent> {
+        self.sender.subscribe()
+    }
+}
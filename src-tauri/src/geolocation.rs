@@ -28,7 +28,7 @@ pub async fn get_location_from_ip() -> Result<LocationInfo> {
         .await?;
 
     if !response.status().is_success() {
-        return Err(crate::error::AppError::Custom(
+        return Err(crate::error::AppError::Http(
             format!("Failed to get geolocation: {}", response.status())
         ));
     }
@@ -36,10 +36,10 @@ pub async fn get_location_from_ip() -> Result<LocationInfo> {
     let geo_data: IpGeolocationResponse = response.json().await?;
 
     let latitude = geo_data.latitude.parse::<f64>()
-        .map_err(|e| crate::error::AppError::Custom(format!("Invalid latitude: {}", e)))?;
+        .map_err(|e| crate::error::AppError::InvalidInput(format!("Invalid latitude: {}", e)))?;
     
     let longitude = geo_data.longitude.parse::<f64>()
-        .map_err(|e| crate::error::AppError::Custom(format!("Invalid longitude: {}", e)))?;
+        .map_err(|e| crate::error::AppError::InvalidInput(format!("Invalid longitude: {}", e)))?;
 
     Ok(LocationInfo {
         latitude,
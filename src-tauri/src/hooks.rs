@@ -0,0 +1,347 @@
+//! User hooks run on prayer events: an HTTP POST with the event as JSON, or a
+//! local command with the event in environment variables.
+//!
+//! ```text
+//! MUEZZIN_EVENT      reminder, adhanStart, adhanEnd, iqamah or midnight
+//! MUEZZIN_PRAYER     Asr
+//! MUEZZIN_TIME       2025-03-01T15:42:00+01:00, the prayer or Iqamah time
+//! MUEZZIN_MINUTES    minutes before the prayer, for reminders
+//! MUEZZIN_WITH_SOUND whether the adhan plays, for adhanStart
+//! MUEZZIN_PAYLOAD    the same JSON an HTTP hook receives
+//! ```
+//!
+//! Each attempt has its own timeout, failed attempts are retried after a
+//! growing delay, and the last executions are kept for `get_hook_log`.
+
+use chrono::{DateTime, Local};
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::time::{sleep, timeout};
+
+use crate::checker::CheckerEvent;
+use crate::error::{AppError, Result};
+use crate::models::{Hook, HookAction, HookEvent, HookExecution};
+
+const LOG_SIZE: usize = 200;
+const RETRY_DELAY: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HookPayload {
+    pub event: HookEvent,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prayer: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time: Option<DateTime<Local>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub minutes: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub with_sound: Option<bool>,
+    pub fired_at: DateTime<Local>,
+}
+
+impl HookPayload {
+    fn new(event: HookEvent, fired_at: DateTime<Local>) -> Self {
+        Self { event, prayer: None, time: None, minutes: None, with_sound: None, fired_at }
+    }
+
+    /// None for checker events no hook runs on
+    pub fn from_checker(event: &CheckerEvent, now: DateTime<Local>) -> Option<Self> {
+        let payload = match event {
+            CheckerEvent::Minute { .. } => return None,
+            CheckerEvent::DateChanged => Self::new(HookEvent::Midnight, now),
+            CheckerEvent::Reminder { prayer, time, minutes } => Self {
+                prayer: Some(prayer.clone()),
+                time: Some(*time),
+                minutes: Some(*minutes),
+                ..Self::new(HookEvent::Reminder, now)
+            },
+            CheckerEvent::Prayer { prayer, time, with_sound, .. } => Self {
+                prayer: Some(prayer.clone()),
+                time: Some(*time),
+                with_sound: Some(*with_sound),
+                ..Self::new(HookEvent::AdhanStart, now)
+            },
+            CheckerEvent::AdhanEnded { prayer } => Self {
+                prayer: Some(prayer.clone()),
+                ..Self::new(HookEvent::AdhanEnd, now)
+            },
+            CheckerEvent::Iqamah { prayer, time } => Self {
+                prayer: Some(prayer.clone()),
+                time: Some(*time),
+                ..Self::new(HookEvent::Iqamah, now)
+            },
+        };
+        Some(payload)
+    }
+
+    /// Sample for trying a hook out from the settings page
+    pub fn sample(event: HookEvent, now: DateTime<Local>) -> Self {
+        Self {
+            prayer: (event != HookEvent::Midnight).then(|| "Asr".to_string()),
+            time: (event != HookEvent::Midnight).then_some(now),
+            minutes: (event == HookEvent::Reminder).then_some(10),
+            with_sound: (event == HookEvent::AdhanStart).then_some(true),
+            ..Self::new(event, now)
+        }
+    }
+
+    fn env(&self) -> Vec<(&'static str, String)> {
+        let mut env = vec![
+            ("MUEZZIN_EVENT", event_name(self.event).to_string()),
+            ("MUEZZIN_PAYLOAD", serde_json::to_string(self).unwrap_or_default()),
+        ];
+        if let Some(ref prayer) = self.prayer {
+            env.push(("MUEZZIN_PRAYER", prayer.clone()));
+        }
+        if let Some(time) = self.time {
+            env.push(("MUEZZIN_TIME", time.to_rfc3339()));
+        }
+        if let Some(minutes) = self.minutes {
+            env.push(("MUEZZIN_MINUTES", minutes.to_string()));
+        }
+        if let Some(with_sound) = self.with_sound {
+            env.push(("MUEZZIN_WITH_SOUND", with_sound.to_string()));
+        }
+        env
+    }
+}
+
+/// Serialized name, e.g. `adhanStart`
+fn event_name(event: HookEvent) -> &'static str {
+    match event {
+        HookEvent::Reminder => "reminder",
+        HookEvent::AdhanStart => "adhanStart",
+        HookEvent::AdhanEnd => "adhanEnd",
+        HookEvent::Iqamah => "iqamah",
+        HookEvent::Midnight => "midnight",
+    }
+}
+
+#[derive(Clone)]
+pub struct Hooks {
+    client: reqwest::Client,
+    log: Arc<Mutex<VecDeque<HookExecution>>>,
+    retry_delay: Duration,
+}
+
+impl Default for Hooks {
+    fn default() -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            log: Arc::new(Mutex::new(VecDeque::new())),
+            retry_delay: RETRY_DELAY,
+        }
+    }
+}
+
+impl Hooks {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Starts every enabled hook registered for `event` in the background
+    pub fn fire(&self, hooks: &[Hook], event: &CheckerEvent) {
+        let Some(payload) = HookPayload::from_checker(event, Local::now()) else {
+            return;
+        };
+        for hook in hooks.iter().filter(|h| h.enabled && h.events.contains(&payload.event)) {
+            let hooks = self.clone();
+            let hook = hook.clone();
+            let payload = payload.clone();
+            tokio::spawn(async move {
+                hooks.run(&hook, &payload).await;
+            });
+        }
+    }
+
+    /// Runs one hook to completion, retries included, and logs the outcome
+    pub async fn run(&self, hook: &Hook, payload: &HookPayload) -> HookExecution {
+        let started_at = Local::now();
+        let started = Instant::now();
+        let mut attempts = 0;
+        let mut result;
+        loop {
+            attempts += 1;
+            result = self.attempt(hook, payload).await;
+            if result.is_ok() || attempts > hook.retries {
+                break;
+            }
+            sleep(self.retry_delay * attempts).await;
+        }
+
+        let execution = HookExecution {
+            hook: hook.name.clone(),
+            event: payload.event,
+            started_at,
+            attempts,
+            success: result.is_ok(),
+            duration_ms: started.elapsed().as_millis() as u64,
+            error: result.err().map(|e| e.to_string()),
+        };
+        match execution.error {
+            Some(ref e) => eprintln!("Hook '{}' failed after {} attempts: {}", hook.name, attempts, e),
+            None => println!("Hook '{}' ran for {}", hook.name, event_name(payload.event)),
+        }
+
+        let mut log = self.log.lock();
+        if log.len() == LOG_SIZE {
+            log.pop_back();
+        }
+        log.push_front(execution.clone());
+        execution
+    }
+
+    /// Executions, newest first
+    pub fn log(&self) -> Vec<HookExecution> {
+        self.log.lock().iter().cloned().collect()
+    }
+
+    async fn attempt(&self, hook: &Hook, payload: &HookPayload) -> Result<()> {
+        let limit = Duration::from_secs(hook.timeout_secs.max(1) as u64);
+        match hook.action {
+            HookAction::Http { ref url, ref headers } => {
+                let mut request = self.client.post(url).timeout(limit).json(payload);
+                for (name, value) in headers {
+                    request = request.header(name, value);
+                }
+                request.send().await?.error_for_status()?;
+            }
+            HookAction::Command { ref program, ref args } => {
+                let mut command = tokio::process::Command::new(program);
+                command
+                    .args(args)
+                    .envs(payload.env())
+                    .stdin(Stdio::null())
+                    .stdout(Stdio::null())
+                    .stderr(Stdio::piped())
+                    // Dropping the output future on timeout kills the process
+                    .kill_on_drop(true);
+                let output = timeout(limit, command.output())
+                    .await
+                    .map_err(|_| AppError::Timeout(format!("Timed out after {}s", limit.as_secs())))??;
+                if !output.status.success() {
+                    let stderr = String::from_utf8_lossy(&output.stderr);
+                    let last_line = stderr.lines().last().unwrap_or_default().trim();
+                    return Err(AppError::CommandFailed(format!("{} {}", output.status, last_line).trim_end().to_string()));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::extract::State;
+    use axum::http::StatusCode;
+    use axum::routing::post;
+    use axum::{Json, Router};
+    use std::collections::BTreeMap;
+
+    fn hooks() -> Hooks {
+        Hooks { retry_delay: Duration::from_millis(10), ..Hooks::new() }
+    }
+
+    fn hook(action: HookAction, retries: u32) -> Hook {
+        Hook {
+            enabled: true,
+            name: "test".to_string(),
+            events: vec![HookEvent::AdhanStart],
+            action,
+            timeout_secs: 1,
+            retries,
+        }
+    }
+
+    #[cfg(unix)]
+    fn shell(script: &str) -> HookAction {
+        HookAction::Command { program: "sh".to_string(), args: vec!["-c".to_string(), script.to_string()] }
+    }
+
+    #[test]
+    fn maps_checker_events_to_hook_events() {
+        let now = Local::now();
+        let reminder = CheckerEvent::Reminder { prayer: "Asr".to_string(), time: now, minutes: 10 };
+        let payload = HookPayload::from_checker(&reminder, now).unwrap();
+        assert_eq!(payload.event, HookEvent::Reminder);
+        assert_eq!(payload.minutes, Some(10));
+
+        let midnight = HookPayload::from_checker(&CheckerEvent::DateChanged, now).unwrap();
+        assert_eq!(midnight.event, HookEvent::Midnight);
+        assert!(midnight.env().iter().all(|(name, _)| *name != "MUEZZIN_PRAYER"));
+
+        let minute = CheckerEvent::Minute { now, next_prayer: "Asr".to_string(), next_time: now, today: None };
+        assert!(HookPayload::from_checker(&minute, now).is_none());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn command_gets_the_event_in_its_environment() {
+        let action = shell(r#"test "$MUEZZIN_EVENT" = adhanStart && test "$MUEZZIN_PRAYER" = Asr && test "$MUEZZIN_WITH_SOUND" = true"#);
+        let execution = hooks().run(&hook(action, 0), &HookPayload::sample(HookEvent::AdhanStart, Local::now())).await;
+        assert!(execution.success, "{:?}", execution.error);
+        assert_eq!(execution.attempts, 1);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn failing_command_is_retried_and_logged() {
+        let hooks = hooks();
+        let payload = HookPayload::sample(HookEvent::AdhanStart, Local::now());
+        let execution = hooks.run(&hook(shell("echo no lights >&2; exit 3"), 2), &payload).await;
+        assert!(!execution.success);
+        assert_eq!(execution.attempts, 3);
+        assert!(execution.error.as_deref().unwrap().ends_with("no lights"), "{:?}", execution.error);
+
+        hooks.run(&hook(shell("true"), 2), &payload).await;
+        let log = hooks.log();
+        assert_eq!(log.len(), 2);
+        assert!(log[0].success && !log[1].success);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn slow_command_is_killed_at_the_timeout() {
+        let payload = HookPayload::sample(HookEvent::AdhanStart, Local::now());
+        let execution = hooks().run(&hook(shell("sleep 10"), 0), &payload).await;
+        assert!(!execution.success);
+        assert_eq!(execution.error.as_deref(), Some("Timed out after 1s"));
+        assert!(execution.duration_ms < 3000);
+    }
+
+    #[tokio::test]
+    async fn http_hook_posts_the_payload_and_retries_server_errors() {
+        // Fails the first request, accepts the second
+        let received = Arc::new(Mutex::new(Vec::<serde_json::Value>::new()));
+        let router = Router::new()
+            .route(
+                "/hook",
+                post(|State(received): State<Arc<Mutex<Vec<serde_json::Value>>>>, Json(body): Json<serde_json::Value>| async move {
+                    let mut received = received.lock();
+                    received.push(body);
+                    if received.len() == 1 { StatusCode::INTERNAL_SERVER_ERROR } else { StatusCode::OK }
+                }),
+            )
+            .with_state(received.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+
+        let action = HookAction::Http { url: format!("http://{}/hook", address), headers: BTreeMap::new() };
+        let payload = HookPayload::sample(HookEvent::Reminder, Local::now());
+        let execution = hooks().run(&hook(action, 1), &payload).await;
+
+        assert!(execution.success, "{:?}", execution.error);
+        assert_eq!(execution.attempts, 2);
+        let received = received.lock();
+        assert_eq!(received[1]["event"], "reminder");
+        assert_eq!(received[1]["prayer"], "Asr");
+        assert_eq!(received[1]["minutes"], 10);
+    }
+}
@@ -0,0 +1,442 @@
+//! Opt-in local HTTP/JSON API, so dashboards and mosque displays on the LAN
+//! can read today's times and drive the adhan. Every request needs the
+//! configured token, as `Authorization: Bearer <token>` or, for clients like
+//! `EventSource` that can't set headers, a `token` query parameter.
+//!
+//! ```text
+//! GET    /api/v1/times        today's times and Hijri date
+//! GET    /api/v1/next         next prayer and minutes until it
+//! GET    /api/v1/qibla        Qibla bearing for the current location
+//! GET    /api/v1/hijri        today's Hijri date
+//! POST   /api/v1/adhan/play   ?prayer=Fajr picks the Fajr adhan
+//! POST   /api/v1/adhan/stop
+//! GET    /api/v1/mute         mute status
+//! POST   /api/v1/mute         body: {"mode": "forHours", "hours": 2} and the other MuteRequest modes
+//! DELETE /api/v1/mute
+//! GET    /api/v1/events       server-sent events: reminder, adhan, adhanEnd, iqamah, dateChanged
+//! ```
+
+use axum::extract::{Query, Request, State};
+use axum::http::{header, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use chrono::{DateTime, Local, NaiveDate};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use tauri::{AppHandle, Manager};
+use tokio::sync::{broadcast, oneshot};
+use tokio_stream::wrappers::BroadcastStream;
+use tokio_stream::{Stream, StreamExt};
+
+use muezzin_core::timetable::{self, TimetableDay};
+use muezzin_core::{qibla, HijriDate};
+
+use crate::error::{AppError, ErrorCode, Result};
+use crate::events::PrayerEvent;
+use crate::models::{HttpApiSettings, HttpApiStatus, MuteRequest, MuteStatus};
+use crate::{commands, mute, AppState};
+
+/// A fresh random token, 48 hex characters
+pub fn generate_token() -> String {
+    rand::random::<[u8; 24]>().iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Gives an enabled API a token if it doesn't have one yet
+pub fn ensure_token(settings: &mut HttpApiSettings) {
+    if settings.enabled && settings.token.is_empty() {
+        settings.token = generate_token();
+    }
+}
+
+struct Running {
+    settings: HttpApiSettings,
+    address: SocketAddr,
+    shutdown: oneshot::Sender<()>,
+}
+
+#[derive(Default)]
+pub struct HttpApi {
+    running: Mutex<Option<Running>>,
+    last_error: Mutex<Option<String>>,
+}
+
+impl HttpApi {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Starts, stops or restarts the server so it matches `settings`
+    pub fn apply(&self, app: &AppHandle, settings: &HttpApiSettings) -> Result<()> {
+        let mut running = self.running.lock();
+        if running.as_ref().map(|r| &r.settings) == Some(settings) {
+            return Ok(());
+        }
+        if let Some(previous) = running.take() {
+            let _ = previous.shutdown.send(());
+        }
+        *self.last_error.lock() = None;
+        if !settings.enabled {
+            return Ok(());
+        }
+
+        match start(app, settings) {
+            Ok((address, shutdown)) => {
+                *running = Some(Running { settings: settings.clone(), address, shutdown });
+                Ok(())
+            }
+            Err(e) => {
+                *self.last_error.lock() = Some(e.to_string());
+                Err(e)
+            }
+        }
+    }
+
+    pub fn status(&self) -> HttpApiStatus {
+        let running = self.running.lock();
+        HttpApiStatus {
+            running: running.is_some(),
+            address: running.as_ref().map(|r| r.address.to_string()),
+            error: self.last_error.lock().clone(),
+        }
+    }
+}
+
+fn start(app: &AppHandle, settings: &HttpApiSettings) -> Result<(SocketAddr, oneshot::Sender<()>)> {
+    if settings.token.is_empty() {
+        return Err(AppError::InvalidInput("The HTTP API needs a token".to_string()));
+    }
+    let ip: IpAddr = settings.bind_address.parse()
+        .map_err(|_| AppError::InvalidInput(format!("'{}' is not an IP address", settings.bind_address)))?;
+
+    // Bind synchronously so a taken port is reported to the caller
+    let listener = std::net::TcpListener::bind((ip, settings.port))?;
+    listener.set_nonblocking(true)?;
+    let address = listener.local_addr()?;
+    let listener = tokio::net::TcpListener::from_std(listener)?;
+
+    let (shutdown, stopped) = oneshot::channel::<()>();
+    let router = router(app.clone(), Arc::new(settings.token.clone()));
+    tokio::spawn(async move {
+        let server = axum::serve(listener, router).with_graceful_shutdown(async {
+            let _ = stopped.await;
+        });
+        if let Err(e) = server.await {
+            eprintln!("HTTP API stopped: {}", e);
+        }
+    });
+
+    println!("HTTP API listening on {}", address);
+    Ok((address, shutdown))
+}
+
+fn router(app: AppHandle, token: Arc<String>) -> Router {
+    let routes = Router::new()
+        .route("/api/v1/times", get(times))
+        .route("/api/v1/next", get(next_prayer))
+        .route("/api/v1/qibla", get(qibla_bearing))
+        .route("/api/v1/hijri", get(hijri))
+        .route("/api/v1/adhan/play", post(play_adhan))
+        .route("/api/v1/adhan/stop", post(stop_adhan))
+        .route("/api/v1/mute", get(mute_status).post(mute_adhan).delete(unmute_adhan))
+        .route("/api/v1/events", get(events));
+    protect(routes, token).with_state(app)
+}
+
+/// Puts every route behind the token check
+fn protect<S: Clone + Send + Sync + 'static>(routes: Router<S>, token: Arc<String>) -> Router<S> {
+    routes.layer(middleware::from_fn_with_state(token, authorize))
+}
+
+fn token_from(request: &Request) -> Option<&str> {
+    let bearer = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "));
+    bearer.or_else(|| {
+        request
+            .uri()
+            .query()?
+            .split('&')
+            .find_map(|pair| pair.strip_prefix("token="))
+    })
+}
+
+/// Compares without stopping at the first differing byte
+fn same_token(given: &str, expected: &str) -> bool {
+    given.len() == expected.len()
+        && given.bytes().zip(expected.bytes()).fold(0u8, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+async fn authorize(State(token): State<Arc<String>>, request: Request, next: Next) -> Response {
+    match token_from(&request) {
+        Some(given) if same_token(given, &token) => next.run(request).await,
+        _ => AppError::Unauthorized.into_response(),
+    }
+}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        let status = match self.code() {
+            ErrorCode::Unauthorized => StatusCode::UNAUTHORIZED,
+            ErrorCode::Locked => StatusCode::FORBIDDEN,
+            ErrorCode::TooManyAttempts => StatusCode::TOO_MANY_REQUESTS,
+            ErrorCode::Timeout => StatusCode::GATEWAY_TIMEOUT,
+            ErrorCode::NetworkError | ErrorCode::HttpError => StatusCode::BAD_GATEWAY,
+            ErrorCode::NotFound => StatusCode::NOT_FOUND,
+            ErrorCode::InvalidInput | ErrorCode::ValidationFailed | ErrorCode::InvalidDate => StatusCode::BAD_REQUEST,
+            ErrorCode::NotInitialized => StatusCode::SERVICE_UNAVAILABLE,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        (status, Json(self)).into_response()
+    }
+}
+
+async fn times(State(app): State<AppHandle>) -> Result<Json<TimetableDay>> {
+    let state = app.state::<AppState>();
+    let day = timetable::day(&state.calculator.read(), Local::now().date_naive(), 0)?;
+    Ok(Json(day))
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct NextPrayer {
+    prayer: String,
+    time: DateTime<Local>,
+    minutes_until: i64,
+}
+
+async fn next_prayer(State(app): State<AppHandle>) -> Result<Json<NextPrayer>> {
+    let state = app.state::<AppState>();
+    let now = Local::now();
+    let (prayer, time) = state.calculator.read().get_prayer_after(now)?;
+    Ok(Json(NextPrayer { prayer, time, minutes_until: (time - now).num_minutes() }))
+}
+
+#[derive(Serialize)]
+struct Qibla {
+    latitude: f64,
+    longitude: f64,
+    bearing: f64,
+}
+
+async fn qibla_bearing(State(app): State<AppHandle>) -> Result<Json<Qibla>> {
+    let state = app.state::<AppState>();
+    let (latitude, longitude, _) = state.storage.get_location()?;
+    Ok(Json(Qibla { latitude, longitude, bearing: qibla::bearing(latitude, longitude) }))
+}
+
+#[derive(Serialize)]
+struct Hijri {
+    date: NaiveDate,
+    hijri: HijriDate,
+    text: String,
+}
+
+async fn hijri() -> Json<Hijri> {
+    let date = Local::now().date_naive();
+    let hijri = HijriDate::from_gregorian(date, 0);
+    Json(Hijri { date, hijri, text: hijri.to_string() })
+}
+
+#[derive(Deserialize)]
+struct PlayQuery {
+    prayer: Option<String>,
+}
+
+async fn play_adhan(State(app): State<AppHandle>, Query(query): Query<PlayQuery>) -> Result<StatusCode> {
+    commands::play_configured_adhan(&app.state::<AppState>(), query.prayer.as_deref())?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn stop_adhan(State(app): State<AppHandle>) -> StatusCode {
+    app.state::<AppState>().audio_player.write().stop();
+    StatusCode::NO_CONTENT
+}
+
+async fn mute_status(State(app): State<AppHandle>) -> Result<Json<MuteStatus>> {
+    let settings = app.state::<AppState>().storage.get_settings()?;
+    Ok(Json(mute::mute_status(&settings, Local::now())))
+}
+
+async fn mute_adhan(State(app): State<AppHandle>, Json(request): Json<MuteRequest>) -> Result<Json<MuteStatus>> {
+    Ok(Json(commands::apply_mute(&app.state::<AppState>(), &app, request)?))
+}
+
+async fn unmute_adhan(State(app): State<AppHandle>) -> Result<Json<MuteStatus>> {
+    Ok(Json(commands::apply_unmute(&app.state::<AppState>(), &app)?))
+}
+
+async fn events(State(app): State<AppHandle>) -> Sse<impl Stream<Item = std::result::Result<Event, axum::Error>>> {
+    event_stream(app.state::<AppState>().events.subscribe())
+}
+
+/// Each event is sent under its `type` tag as the SSE event name
+fn event_stream(receiver: broadcast::Receiver<PrayerEvent>) -> Sse<impl Stream<Item = std::result::Result<Event, axum::Error>>> {
+    // A lagging client skips what it missed rather than being dropped
+    let stream = BroadcastStream::new(receiver)
+        .filter_map(|event| event.ok())
+        .map(|event| Event::default().event(event.kind()).json_data(&event));
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::{to_bytes, Body};
+    use chrono::TimeZone;
+    use serde_json::Value;
+
+    const TOKEN: &str = "0123456789abcdef";
+
+    /// The token check in front of handlers that don't need the app, served
+    /// on a free local port
+    async fn serve() -> String {
+        let routes = Router::new()
+            .route("/api/v1/hijri", get(hijri))
+            .route("/api/v1/mute", post(|| async { Err::<StatusCode, _>(AppError::InvalidInput("Invalid mute duration".into())) }));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, protect(routes, Arc::new(TOKEN.to_string()))).await });
+        format!("http://{}", address)
+    }
+
+    fn request(uri: &str, authorization: Option<&str>) -> Request {
+        let mut builder = axum::http::Request::builder().uri(uri);
+        if let Some(value) = authorization {
+            builder = builder.header(header::AUTHORIZATION, value);
+        }
+        builder.body(Body::empty()).unwrap()
+    }
+
+    async fn body(response: Response) -> Value {
+        serde_json::from_slice(&to_bytes(response.into_body(), usize::MAX).await.unwrap()).unwrap()
+    }
+
+    #[test]
+    fn token_from_header_or_query() {
+        assert_eq!(token_from(&request("/api/v1/times", Some("Bearer abc"))), Some("abc"));
+        assert_eq!(token_from(&request("/api/v1/events?since=1&token=abc", None)), Some("abc"));
+        assert_eq!(token_from(&request("/api/v1/events?token=query", Some("Bearer header"))), Some("header"));
+        // Only bearer tokens are read from the header
+        assert_eq!(token_from(&request("/api/v1/times?token=abc", Some("Basic abc"))), Some("abc"));
+        assert_eq!(token_from(&request("/api/v1/times", Some("Basic abc"))), None);
+        assert_eq!(token_from(&request("/api/v1/times?mytoken=abc", None)), None);
+    }
+
+    #[test]
+    fn same_token_needs_every_byte() {
+        assert!(same_token(TOKEN, TOKEN));
+        assert!(!same_token("0123456789abcdeF", TOKEN));
+        assert!(!same_token("0123456789abcde", TOKEN));
+        assert!(!same_token(&format!("{}0", TOKEN), TOKEN));
+        assert!(!same_token("", TOKEN));
+    }
+
+    #[tokio::test]
+    async fn requests_need_the_token() {
+        let base = serve().await;
+        let client = reqwest::Client::new();
+        let hijri = format!("{}/api/v1/hijri", base);
+
+        let response = client.get(&hijri).send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let error: Value = response.json().await.unwrap();
+        assert_eq!(error["code"], "UNAUTHORIZED");
+
+        for wrong in ["wrong", "0123456789abcde", "0123456789abcdef0"] {
+            let response = client.get(&hijri).bearer_auth(wrong).send().await.unwrap();
+            assert_eq!(response.status(), StatusCode::UNAUTHORIZED, "{}", wrong);
+        }
+        let response = client.get(format!("{}?token=wrong", hijri)).send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let response = client.get(&hijri).bearer_auth(TOKEN).send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let today: Value = response.json().await.unwrap();
+        assert_eq!(today["date"], Local::now().date_naive().to_string());
+
+        let response = client.get(format!("{}?token={}", hijri, TOKEN)).send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn handler_errors_keep_their_code() {
+        let base = serve().await;
+        let response = reqwest::Client::new()
+            .post(format!("{}/api/v1/mute", base))
+            .bearer_auth(TOKEN)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let error: Value = response.json().await.unwrap();
+        assert_eq!(error["code"], "INVALID_INPUT");
+        assert_eq!(error["messageKey"], "error.invalidInput");
+    }
+
+    #[tokio::test]
+    async fn errors_map_to_statuses() {
+        for (error, status) in [
+            (AppError::Unauthorized, StatusCode::UNAUTHORIZED),
+            (AppError::Locked, StatusCode::FORBIDDEN),
+            (AppError::TooManyAttempts { retry_after_secs: 30 }, StatusCode::TOO_MANY_REQUESTS),
+            (AppError::Timeout("leader".into()), StatusCode::GATEWAY_TIMEOUT),
+            (AppError::Network("mDNS: no route".into()), StatusCode::BAD_GATEWAY),
+            (AppError::InvalidInput("bad".into()), StatusCode::BAD_REQUEST),
+            (AppError::InvalidDate("2025-02-30".into()), StatusCode::BAD_REQUEST),
+            (AppError::Validation(Vec::new()), StatusCode::BAD_REQUEST),
+            (AppError::NotInitialized, StatusCode::SERVICE_UNAVAILABLE),
+            (AppError::InvalidData("tracker".into()), StatusCode::INTERNAL_SERVER_ERROR),
+        ] {
+            let code = serde_json::to_value(error.code()).unwrap();
+            let response = error.into_response();
+            assert_eq!(response.status(), status, "{}", code);
+            assert_eq!(body(response).await["code"], code);
+        }
+    }
+
+    #[tokio::test]
+    async fn sse_events_are_named_after_their_type() {
+        let (sender, receiver) = broadcast::channel(8);
+        let time = Local.with_ymd_and_hms(2025, 3, 3, 16, 0, 0).unwrap();
+        for event in [
+            PrayerEvent::Reminder { prayer: "Asr".into(), time, minutes: 10 },
+            PrayerEvent::Adhan { prayer: "Asr".into(), time, with_sound: true },
+            PrayerEvent::AdhanEnd { prayer: "Asr".into() },
+            PrayerEvent::Iqamah { prayer: "Asr".into(), time },
+            PrayerEvent::DateChanged { date: time.date_naive() },
+        ] {
+            sender.send(event).unwrap();
+        }
+        // The stream ends once the sender is gone
+        drop(sender);
+
+        let bytes = to_bytes(event_stream(receiver).into_response().into_body(), usize::MAX).await.unwrap();
+        let text = String::from_utf8(bytes.to_vec()).unwrap();
+        let events: Vec<(String, Value)> = text
+            .split("\n\n")
+            .filter(|block| !block.trim().is_empty())
+            .map(|block| {
+                let field = |name: &str| {
+                    block.lines().find_map(|line| line.strip_prefix(name)).map(str::trim).unwrap_or_default().to_string()
+                };
+                (field("event:"), serde_json::from_str(&field("data:")).unwrap())
+            })
+            .collect();
+
+        let names: Vec<_> = events.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, ["reminder", "adhan", "adhanEnd", "iqamah", "dateChanged"]);
+        for (name, data) in &events {
+            assert_eq!(data["type"], name.as_str());
+        }
+        assert_eq!(events[0].1["minutes"], 10);
+        assert_eq!(events[1].1["withSound"], true);
+        assert_eq!(events[4].1["date"], "2025-03-03");
+    }
+}
@@ -0,0 +1,129 @@
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+use std::collections::HashMap;
+
+/// Same language codes as the frontend's `language.js`
+pub const LANGUAGES: &[&str] = &[
+    "en", "fr", "es", "ar", "it", "de", "nl", "sv", "no", "da", "ur", "tr", "bn", "ru", "id", "uz", "fa",
+];
+
+const FALLBACK: &str = "en";
+
+static CATALOGS: Lazy<HashMap<&'static str, HashMap<String, String>>> = Lazy::new(|| {
+    let sources: [(&str, &str); 17] = [
+        ("en", include_str!("../locales/en.json")),
+        ("fr", include_str!("../locales/fr.json")),
+        ("es", include_str!("../locales/es.json")),
+        ("ar", include_str!("../locales/ar.json")),
+        ("it", include_str!("../locales/it.json")),
+        ("de", include_str!("../locales/de.json")),
+        ("nl", include_str!("../locales/nl.json")),
+        ("sv", include_str!("../locales/sv.json")),
+        ("no", include_str!("../locales/no.json")),
+        ("da", include_str!("../locales/da.json")),
+        ("ur", include_str!("../locales/ur.json")),
+        ("tr", include_str!("../locales/tr.json")),
+        ("bn", include_str!("../locales/bn.json")),
+        ("ru", include_str!("../locales/ru.json")),
+        ("id", include_str!("../locales/id.json")),
+        ("uz", include_str!("../locales/uz.json")),
+        ("fa", include_str!("../locales/fa.json")),
+    ];
+
+    sources
+        .into_iter()
+        .map(|(lang, json)| {
+            let catalog = serde_json::from_str(json)
+                .unwrap_or_else(|e| panic!("Invalid message catalog for '{}': {}", lang, e));
+            (lang, catalog)
+        })
+        .collect()
+});
+
+static CURRENT: Lazy<RwLock<String>> = Lazy::new(|| RwLock::new(FALLBACK.to_string()));
+
+/// Switches the backend language, unknown codes fall back to English
+pub fn set_language(lang: &str) {
+    let lang = if LANGUAGES.contains(&lang) { lang } else { FALLBACK };
+    *CURRENT.write() = lang.to_string();
+}
+
+pub fn language() -> String {
+    CURRENT.read().clone()
+}
+
+pub fn is_rtl(lang: &str) -> bool {
+    matches!(lang, "ar" | "ur" | "fa")
+}
+
+/// Looks a message up in the current language, then English, then returns the key
+pub fn t(key: &str) -> String {
+    let lang = language();
+    lookup(&lang, key)
+}
+
+fn lookup(lang: &str, key: &str) -> String {
+    CATALOGS
+        .get(lang)
+        .and_then(|c| c.get(key))
+        .or_else(|| CATALOGS.get(FALLBACK).and_then(|c| c.get(key)))
+        .cloned()
+        .unwrap_or_else(|| key.to_string())
+}
+
+/// Translates a message and fills in its `{placeholders}`, with digits and
+/// text direction adapted to the current language.
+pub fn t_with(key: &str, vars: &[(&str, String)]) -> String {
+    let lang = language();
+    localize(&fill(lookup(&lang, key), vars), &lang)
+}
+
+/// Localized name for a prayer as used by the checker ("Fajr", "Dhuhr", ...)
+pub fn prayer_name(prayer: &str) -> String {
+    t(&format!("prayer.{}", prayer.to_lowercase()))
+}
+
+/// "1h 12m" style countdown, left unlocalized so it can be nested in `t_with`
+pub fn format_duration(total_minutes: i64) -> String {
+    let lang = language();
+    let total_minutes = total_minutes.max(0);
+    let (hours, minutes) = (total_minutes / 60, total_minutes % 60);
+    let (key, vars) = if hours > 0 {
+        ("tray.hoursMinutes", vec![("hours", hours.to_string()), ("minutes", minutes.to_string())])
+    } else {
+        ("tray.minutes", vec![("minutes", minutes.to_string())])
+    };
+    fill(lookup(&lang, key), &vars)
+}
+
+fn fill(template: String, vars: &[(&str, String)]) -> String {
+    vars.iter().fold(template, |text, (name, value)| {
+        text.replace(&format!("{{{}}}", name), value)
+    })
+}
+
+fn localize(text: &str, lang: &str) -> String {
+    let text = localize_digits(text, lang);
+    if is_rtl(lang) {
+        // A leading RLM per line keeps mixed Latin/Arabic lines right-aligned
+        text.lines().map(|line| format!("\u{200F}{}", line)).collect::<Vec<_>>().join("\n")
+    } else {
+        text
+    }
+}
+
+pub fn localize_digits(text: &str, lang: &str) -> String {
+    let zero = match lang {
+        "ar" => '\u{0660}',
+        "fa" | "ur" => '\u{06F0}',
+        "bn" => '\u{09E6}',
+        _ => return text.to_string(),
+    };
+
+    text.chars()
+        .map(|c| match c.to_digit(10) {
+            Some(d) if c.is_ascii_digit() => char::from_u32(zero as u32 + d).unwrap_or(c),
+            _ => c,
+        })
+        .collect()
+}
@@ -0,0 +1,343 @@
+//! Mosque display mode: the main window full-screen on a TV, showing a clock,
+//! today's adhan and Iqamah times, a countdown to the coming Iqamah, rotating
+//! announcements and a Jumuah banner on Fridays. While it runs, a PIN can
+//! lock every command that changes settings, so visitors can't.
+
+use chrono::{DateTime, Datelike, Duration, Local, Weekday};
+use parking_lot::Mutex;
+use sha2::Sha256;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
+use tauri::{AppHandle, Emitter, Manager};
+
+use muezzin_core::{HijriDate, PrayerCalculator};
+use crate::error::{AppError, Result};
+use crate::models::{AppSettings, KioskDisplay, KioskJumuah, KioskPrayer, KioskSettings};
+use crate::AppState;
+
+/// Shortest PIN `set_kiosk_pin` accepts
+pub const MIN_PIN_LENGTH: usize = 4;
+
+/// PBKDF2 rounds, a PIN has few digits so each guess has to be expensive
+const PIN_ROUNDS: u32 = 100_000;
+
+/// Wrong PINs allowed before leaving the display is refused for a while
+const FREE_ATTEMPTS: u32 = 3;
+const FIRST_LOCKOUT: std::time::Duration = std::time::Duration::from_secs(30);
+const MAX_LOCKOUT: std::time::Duration = std::time::Duration::from_secs(15 * 60);
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn derive(pin: &str, salt: &str, rounds: u32) -> String {
+    let mut key = [0u8; 32];
+    pbkdf2::pbkdf2_hmac::<Sha256>(pin.as_bytes(), salt.as_bytes(), rounds, &mut key);
+    hex(&key)
+}
+
+/// Salted PBKDF2-SHA256, stored as `pbkdf2-sha256$rounds$salt$hash`
+pub fn hash_pin(pin: &str) -> String {
+    let salt = hex(&rand::random::<[u8; 16]>());
+    format!("pbkdf2-sha256${}${}${}", PIN_ROUNDS, salt, derive(pin, &salt, PIN_ROUNDS))
+}
+
+/// Whether `pin` unlocks the display, any PIN does when none is set
+pub fn pin_matches(settings: &KioskSettings, pin: &str) -> bool {
+    if settings.pin_hash.is_empty() {
+        return true;
+    }
+    let mut parts = settings.pin_hash.split('$');
+    let (Some("pbkdf2-sha256"), Some(rounds), Some(salt), Some(expected), None) =
+        (parts.next(), parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        return false;
+    };
+    let Ok(rounds) = rounds.parse::<u32>() else {
+        return false;
+    };
+    let given = derive(pin, salt, rounds);
+    given.len() == expected.len()
+        && given.bytes().zip(expected.bytes()).fold(0u8, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+/// Wait after `failed` wrong PINs, doubling from the first lockout
+fn lockout(failed: u32) -> std::time::Duration {
+    if failed < FREE_ATTEMPTS {
+        return std::time::Duration::ZERO;
+    }
+    FIRST_LOCKOUT
+        .checked_mul(1 << (failed - FREE_ATTEMPTS).min(16))
+        .map_or(MAX_LOCKOUT, |wait| wait.min(MAX_LOCKOUT))
+}
+
+#[derive(Default)]
+struct Attempts {
+    failed: u32,
+    retry_at: Option<Instant>,
+}
+
+#[derive(Default)]
+pub struct Kiosk {
+    active: AtomicBool,
+    attempts: Mutex<Attempts>,
+}
+
+impl Kiosk {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active.load(Ordering::SeqCst)
+    }
+
+    pub fn is_locked(&self, settings: &KioskSettings) -> bool {
+        self.is_active() && !settings.pin_hash.is_empty()
+    }
+
+    /// Fails while the display runs behind a PIN
+    pub fn check_unlocked(&self, settings: &KioskSettings) -> Result<()> {
+        if self.is_locked(settings) {
+            return Err(AppError::Locked);
+        }
+        Ok(())
+    }
+
+    /// Checks the PIN to leave the display, refusing every try for a while
+    /// after a few wrong ones
+    pub fn unlock(&self, settings: &KioskSettings, pin: &str, now: Instant) -> Result<()> {
+        let mut attempts = self.attempts.lock();
+        if let Some(retry_at) = attempts.retry_at.filter(|&at| now < at) {
+            return Err(AppError::TooManyAttempts { retry_after_secs: (retry_at - now).as_secs().max(1) });
+        }
+        if pin_matches(settings, pin) {
+            *attempts = Attempts::default();
+            return Ok(());
+        }
+        attempts.failed += 1;
+        let wait = lockout(attempts.failed);
+        attempts.retry_at = (!wait.is_zero()).then(|| now + wait);
+        Err(AppError::Locked)
+    }
+}
+
+fn prayer(settings: &AppSettings, name: &str, adhan: DateTime<Local>) -> KioskPrayer {
+    let friday = adhan.weekday() == Weekday::Fri;
+    let iqamah = settings
+        .iqamah
+        .as_ref()
+        .and_then(|i| i.offset_for(name, friday))
+        .map(|offset| adhan + Duration::minutes(offset as i64));
+    KioskPrayer { name: name.to_string(), adhan, iqamah }
+}
+
+/// What the display shows at `now`
+pub fn display(calculator: &PrayerCalculator, settings: &AppSettings, active: bool, now: DateTime<Local>) -> Result<KioskDisplay> {
+    let times = calculator.calculate_prayer_times(now)?;
+    let prayers: Vec<KioskPrayer> = [
+        ("Fajr", times.fajr),
+        ("Sunrise", times.sunrise),
+        ("Dhuhr", times.dhuhr),
+        ("Asr", times.asr),
+        ("Maghrib", times.maghrib),
+        ("Isha", times.isha),
+    ]
+    .into_iter()
+    .map(|(name, adhan)| prayer(settings, name, adhan))
+    .collect();
+
+    let (next_name, next_time) = calculator.get_prayer_after(now)?;
+    let awaiting_iqamah = prayers
+        .iter()
+        .find(|p| p.adhan <= now && p.iqamah.is_some_and(|iqamah| now < iqamah))
+        .cloned();
+
+    let jumuah = (now.weekday() == Weekday::Fri && now < times.asr).then(|| KioskJumuah {
+        khutbah: times.dhuhr,
+        iqamah: prayer(settings, "Dhuhr", times.dhuhr).iqamah,
+        banner: settings.kiosk.jumuah_banner.clone(),
+    });
+
+    let date = now.date_naive();
+    Ok(KioskDisplay {
+        active,
+        locked: active && !settings.kiosk.pin_hash.is_empty(),
+        date,
+        hijri: HijriDate::from_gregorian(date, 0).to_string(),
+        next: Some(prayer(settings, &next_name, next_time)),
+        awaiting_iqamah,
+        jumuah,
+        prayers,
+        announcements: settings.kiosk.announcements.clone(),
+        announcement_secs: settings.kiosk.announcement_secs,
+    })
+}
+
+fn current(app: &AppHandle) -> Result<KioskDisplay> {
+    let state = app.state::<AppState>();
+    let settings = state.storage.get_settings()?;
+    let calculator = state.calculator.read();
+    display(&calculator, &settings, state.kiosk.is_active(), Local::now())
+}
+
+/// Sends the display a fresh `kiosk-display` while it runs
+pub fn refresh(app: &AppHandle) {
+    if !app.state::<AppState>().kiosk.is_active() {
+        return;
+    }
+    match current(app) {
+        Ok(display) => {
+            let _ = app.emit("kiosk-display", display);
+        }
+        Err(e) => eprintln!("Error updating the kiosk display: {}", e),
+    }
+}
+
+/// Turns the display on or off: full-screen main window and `kiosk-mode`
+pub fn set_active(app: &AppHandle, active: bool) -> Result<()> {
+    app.state::<AppState>().kiosk.active.store(active, Ordering::SeqCst);
+    if let Some(window) = app.get_webview_window("main") {
+        window.set_fullscreen(active)?;
+        if active {
+            window.show()?;
+            window.set_focus()?;
+        }
+    }
+    app.emit("kiosk-mode", active)?;
+    refresh(app);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{CalculationSettings, IqamahTimes};
+    use chrono::TimeZone;
+
+    /// On the test machine's meridian, as in the checker tests, so the
+    /// prayers fall inside its local day
+    fn calculator() -> PrayerCalculator {
+        let longitude = at(3, 0, 0).offset().local_minus_utc() as f64 / 240.0;
+        let mut calculator = PrayerCalculator::new();
+        calculator
+            .update_settings(21.4225, longitude, &CalculationSettings::default(), chrono_tz::UTC, None, None)
+            .unwrap();
+        calculator
+    }
+
+    /// March 2025, the 3rd being a Monday and the 7th a Friday
+    fn at(day: u32, hour: u32, minute: u32) -> DateTime<Local> {
+        Local.with_ymd_and_hms(2025, 3, day, hour, minute, 0).earliest().unwrap()
+    }
+
+    fn settings() -> AppSettings {
+        AppSettings {
+            iqamah: Some(IqamahTimes { enabled: true, fajr: 20, dhuhr: 10, asr: 10, maghrib: 5, isha: 15, jumuah: 30 }),
+            kiosk: KioskSettings {
+                announcements: vec!["Tafsir class after Isha".to_string()],
+                jumuah_banner: "Khutbah in English".to_string(),
+                ..KioskSettings::default()
+            },
+            ..AppSettings::default()
+        }
+    }
+
+    #[test]
+    fn iqamah_times_follow_each_adhan() {
+        let display = display(&calculator(), &settings(), true, at(3, 0, 30)).unwrap();
+        let names: Vec<_> = display.prayers.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, ["Fajr", "Sunrise", "Dhuhr", "Asr", "Maghrib", "Isha"]);
+        for (prayer, offset) in display.prayers.iter().zip([Some(20), None, Some(10), Some(10), Some(5), Some(15)]) {
+            assert_eq!(prayer.iqamah, offset.map(|o| prayer.adhan + Duration::minutes(o)), "{}", prayer.name);
+        }
+        assert_eq!(display.next.as_ref(), Some(&display.prayers[0]));
+        assert_eq!(display.awaiting_iqamah, None);
+        assert_eq!(display.announcements, ["Tafsir class after Isha"]);
+        assert!(display.jumuah.is_none());
+    }
+
+    #[test]
+    fn counts_down_to_iqamah_between_adhan_and_iqamah() {
+        let calculator = calculator();
+        let asr = calculator.calculate_prayer_times(at(3, 12, 0)).unwrap().asr;
+
+        let display = display(&calculator, &settings(), true, asr + Duration::minutes(4)).unwrap();
+        let awaiting = display.awaiting_iqamah.unwrap();
+        assert_eq!(awaiting.name, "Asr");
+        assert_eq!(awaiting.iqamah, Some(asr + Duration::minutes(10)));
+        assert_eq!(display.next.unwrap().name, "Maghrib");
+
+        let after = super::display(&calculator, &settings(), true, asr + Duration::minutes(10)).unwrap();
+        assert_eq!(after.awaiting_iqamah, None);
+    }
+
+    #[test]
+    fn jumuah_banner_shows_on_friday_until_asr() {
+        let calculator = calculator();
+        let times = calculator.calculate_prayer_times(at(7, 12, 0)).unwrap();
+
+        let display = display(&calculator, &settings(), true, at(7, 0, 30)).unwrap();
+        let jumuah = display.jumuah.unwrap();
+        assert_eq!(jumuah.khutbah, times.dhuhr);
+        assert_eq!(jumuah.iqamah, Some(times.dhuhr + Duration::minutes(30)));
+        assert_eq!(jumuah.banner, "Khutbah in English");
+
+        assert!(super::display(&calculator, &settings(), true, times.asr).unwrap().jumuah.is_none());
+    }
+
+    #[test]
+    fn pin_locks_only_a_running_display() {
+        let kiosk = Kiosk::new();
+        assert!(pin_matches(&KioskSettings::default(), "anything"));
+
+        let settings = KioskSettings { pin_hash: hash_pin("4321"), ..KioskSettings::default() };
+        assert!(kiosk.check_unlocked(&settings).is_ok());
+        kiosk.active.store(true, Ordering::SeqCst);
+        assert!(matches!(kiosk.check_unlocked(&settings), Err(AppError::Locked)));
+        assert!(pin_matches(&settings, "4321"));
+        assert!(!pin_matches(&settings, "1234"));
+    }
+
+    #[test]
+    fn pin_hash_is_salted() {
+        let first = hash_pin("4321");
+        assert!(first.starts_with("pbkdf2-sha256$100000$"));
+        assert_ne!(first, hash_pin("4321"));
+        assert!(!pin_matches(&KioskSettings { pin_hash: "not a hash".to_string(), ..KioskSettings::default() }, "4321"));
+    }
+
+    #[test]
+    fn wrong_pins_lock_out_for_longer_each_time() {
+        let kiosk = Kiosk::new();
+        let settings = KioskSettings { pin_hash: hash_pin("4321"), ..KioskSettings::default() };
+        let start = Instant::now();
+
+        for _ in 0..FREE_ATTEMPTS {
+            assert!(matches!(kiosk.unlock(&settings, "0000", start), Err(AppError::Locked)));
+        }
+        // Even the right PIN waits out the lockout
+        assert!(matches!(
+            kiosk.unlock(&settings, "4321", start),
+            Err(AppError::TooManyAttempts { retry_after_secs: 30 })
+        ));
+
+        let later = start + FIRST_LOCKOUT;
+        assert!(matches!(kiosk.unlock(&settings, "0000", later), Err(AppError::Locked)));
+        assert!(matches!(
+            kiosk.unlock(&settings, "4321", later),
+            Err(AppError::TooManyAttempts { retry_after_secs: 60 })
+        ));
+
+        assert!(kiosk.unlock(&settings, "4321", later + FIRST_LOCKOUT * 2).is_ok());
+        assert!(matches!(kiosk.unlock(&settings, "0000", later + FIRST_LOCKOUT * 2), Err(AppError::Locked)));
+    }
+
+    #[test]
+    fn lockout_is_capped() {
+        assert_eq!(lockout(FREE_ATTEMPTS - 1), std::time::Duration::ZERO);
+        assert_eq!(lockout(FREE_ATTEMPTS), FIRST_LOCKOUT);
+        assert_eq!(lockout(FREE_ATTEMPTS + 1), FIRST_LOCKOUT * 2);
+        assert_eq!(lockout(100), MAX_LOCKOUT);
+    }
+}
@@ -0,0 +1,57 @@
+//! The Muezzin app. The `muezzin` binary runs it, and `muezzin-cli` and
+//! `muezzin-daemon` reuse the public modules. Those build without the `gui`
+//! feature, which leaves out the window and Tauri with it.
+
+pub mod checker;
+#[cfg(feature = "gui")]
+mod prayer_times;
+#[cfg(feature = "gui")]
+mod storage;
+pub mod audio;
+#[cfg(feature = "gui")]
+mod geolocation;
+pub mod hooks;
+#[cfg(feature = "gui")]
+mod http_api;
+pub mod models;
+#[cfg(feature = "gui")]
+mod commands;
+pub mod error;
+pub mod events;
+#[cfg(feature = "gui")]
+mod electron_import;
+#[cfg(feature = "gui")]
+mod tray;
+pub mod i18n;
+#[cfg(feature = "gui")]
+mod kiosk;
+#[cfg(feature = "gui")]
+mod media_pause;
+#[cfg(feature = "gui")]
+mod migrations;
+#[cfg(feature = "gui")]
+mod mqtt;
+pub mod multi_room;
+pub mod mute;
+#[cfg(feature = "gui")]
+mod notifications;
+#[cfg(feature = "gui")]
+mod quran;
+#[cfg(feature = "gui")]
+mod quran_audio;
+#[cfg(feature = "gui")]
+mod quran_packs;
+#[cfg(feature = "gui")]
+mod quran_progress;
+#[cfg(feature = "gui")]
+mod settings_bundle;
+pub mod settings_file;
+#[cfg(feature = "gui")]
+mod tracker;
+pub mod validation;
+
+#[cfg(feature = "gui")]
+mod app;
+
+#[cfg(feature = "gui")]
+pub use app::{run, AppState};
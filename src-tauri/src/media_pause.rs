@@ -0,0 +1,198 @@
+//! Pauses desktop media players while the adhan plays and resumes them after.
+//! On Linux this talks MPRIS over the D-Bus session bus. Only players that
+//! were playing are paused, and only those still paused are resumed, so a
+//! player the user stopped in the meantime stays stopped. Elsewhere this
+//! does nothing.
+
+use std::sync::atomic::AtomicBool;
+#[cfg(target_os = "linux")]
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use crate::checker::AudioSink;
+
+#[derive(Default)]
+pub struct MediaPause {
+    /// Set while players are paused for an adhan
+    #[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+    active: Arc<AtomicBool>,
+}
+
+impl MediaPause {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Pauses playing players and resumes them once `audio` has stopped,
+    /// whether the adhan ended or was stopped
+    #[cfg(target_os = "linux")]
+    pub fn adhan_started(&self, audio: Arc<dyn AudioSink>) {
+        if self.active.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        let active = self.active.clone();
+        tokio::spawn(async move {
+            let paused = match mpris::pause_playing().await {
+                Ok(paused) => paused,
+                Err(e) => {
+                    eprintln!("Error pausing media players: {}", e);
+                    Vec::new()
+                }
+            };
+            while audio.is_playing() {
+                tokio::time::sleep(mpris::POLL_INTERVAL).await;
+            }
+            if let Err(e) = mpris::resume(&paused).await {
+                eprintln!("Error resuming media players: {}", e);
+            }
+            active.store(false, Ordering::SeqCst);
+        });
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub fn adhan_started(&self, _audio: Arc<dyn AudioSink>) {}
+}
+
+#[cfg(target_os = "linux")]
+mod mpris {
+    use std::time::Duration;
+    use zbus::zvariant::OwnedValue;
+    use zbus::Connection;
+
+    pub const POLL_INTERVAL: Duration = Duration::from_millis(500);
+    const PREFIX: &str = "org.mpris.MediaPlayer2.";
+    const PATH: &str = "/org/mpris/MediaPlayer2";
+    const PLAYER: &str = "org.mpris.MediaPlayer2.Player";
+
+    async fn player_names(connection: &Connection) -> zbus::Result<Vec<String>> {
+        let names = zbus::fdo::DBusProxy::new(connection).await?.list_names().await?;
+        Ok(names
+            .iter()
+            .map(|name| name.to_string())
+            .filter(|name| name.starts_with(PREFIX))
+            .collect())
+    }
+
+    async fn playback_status(connection: &Connection, player: &str) -> zbus::Result<String> {
+        let reply = connection
+            .call_method(Some(player), PATH, Some("org.freedesktop.DBus.Properties"), "Get", &(PLAYER, "PlaybackStatus"))
+            .await?;
+        let value: OwnedValue = reply.body().deserialize()?;
+        Ok(String::try_from(value)?)
+    }
+
+    async fn call(connection: &Connection, player: &str, method: &str) -> zbus::Result<()> {
+        connection.call_method(Some(player), PATH, Some(PLAYER), method, &()).await?;
+        Ok(())
+    }
+
+    /// Pauses every playing player, returning the ones it paused
+    pub async fn pause_playing() -> zbus::Result<Vec<String>> {
+        pause_playing_on(&Connection::session().await?).await
+    }
+
+    pub async fn resume(players: &[String]) -> zbus::Result<()> {
+        if players.is_empty() {
+            return Ok(());
+        }
+        resume_on(&Connection::session().await?, players).await
+    }
+
+    pub async fn pause_playing_on(connection: &Connection) -> zbus::Result<Vec<String>> {
+        let mut paused = Vec::new();
+        for player in player_names(connection).await? {
+            // One misbehaving player shouldn't keep the others playing
+            match playback_status(connection, &player).await {
+                Ok(status) if status == "Playing" => match call(connection, &player, "Pause").await {
+                    Ok(()) => paused.push(player),
+                    Err(e) => eprintln!("Error pausing {}: {}", player, e),
+                },
+                Ok(_) => {}
+                Err(e) => eprintln!("Error reading {} status: {}", player, e),
+            }
+        }
+        Ok(paused)
+    }
+
+    pub async fn resume_on(connection: &Connection, players: &[String]) -> zbus::Result<()> {
+        for player in players {
+            // Gone, or started or stopped by the user meanwhile
+            if playback_status(connection, player).await.ok().as_deref() != Some("Paused") {
+                continue;
+            }
+            if let Err(e) = call(connection, player, "Play").await {
+                eprintln!("Error resuming {}: {}", player, e);
+            }
+        }
+        Ok(())
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use parking_lot::Mutex;
+        use std::sync::Arc;
+
+        /// The Player interface of an MPRIS service, as far as we use it
+        struct FakePlayer {
+            status: Arc<Mutex<String>>,
+        }
+
+        #[zbus::interface(name = "org.mpris.MediaPlayer2.Player")]
+        impl FakePlayer {
+            fn pause(&mut self) {
+                *self.status.lock() = "Paused".to_string();
+            }
+
+            fn play(&mut self) {
+                *self.status.lock() = "Playing".to_string();
+            }
+
+            #[zbus(property)]
+            fn playback_status(&self) -> String {
+                self.status.lock().clone()
+            }
+        }
+
+        async fn fake_player(name: &str, status: &str) -> (Connection, Arc<Mutex<String>>) {
+            let status = Arc::new(Mutex::new(status.to_string()));
+            let connection = zbus::connection::Builder::session()
+                .unwrap()
+                .name(format!("{}{}", PREFIX, name))
+                .unwrap()
+                .serve_at(PATH, FakePlayer { status: status.clone() })
+                .unwrap()
+                .build()
+                .await
+                .unwrap();
+            (connection, status)
+        }
+
+        /// Needs a session bus, run it in a private one with
+        /// `dbus-run-session -- cargo test mpris -- --ignored`
+        #[tokio::test]
+        #[ignore = "needs a D-Bus session bus"]
+        async fn pauses_playing_players_and_resumes_only_those() {
+            let id = std::process::id();
+            let (_music, music) = fake_player(&format!("music_{}", id), "Playing").await;
+            let (_video, video) = fake_player(&format!("video_{}", id), "Paused").await;
+            let (_radio, radio) = fake_player(&format!("radio_{}", id), "Playing").await;
+
+            let connection = Connection::session().await.unwrap();
+            let mut paused = pause_playing_on(&connection).await.unwrap();
+            paused.retain(|name| name.ends_with(&format!("_{}", id)));
+            paused.sort();
+            assert_eq!(paused, [format!("{}music_{}", PREFIX, id), format!("{}radio_{}", PREFIX, id)]);
+            assert_eq!(*music.lock(), "Paused");
+            assert_eq!(*video.lock(), "Paused");
+            assert_eq!(*radio.lock(), "Paused");
+
+            // The user stops the radio during the adhan
+            *radio.lock() = "Stopped".to_string();
+            resume_on(&connection, &paused).await.unwrap();
+            assert_eq!(*music.lock(), "Playing");
+            assert_eq!(*video.lock(), "Paused");
+            assert_eq!(*radio.lock(), "Stopped");
+        }
+    }
+}
@@ -0,0 +1,509 @@
+//! Versioned layout of the `settings.json` store.
+//!
+//! The store carries a `schemaVersion` key. At launch `migrate` brings an older
+//! store up to `SCHEMA_VERSION` one step at a time, after copying the file to
+//! `settings.v<old>.<timestamp>.bak.json` next to it. Steps never fail on a bad
+//! value: whatever can't be converted is left out and listed in the report.
+//!
+//! Versions:
+//! - 0: no `schemaVersion`. Either a store written before versioning, or the
+//!   electron-store `config.json` of Muezzin 2.6 copied over (see MIGRATION.md).
+//!   Electron keys (`calculationMethod`, `adhan`, `adj`, `darkMode`, ...) are
+//!   folded into `settings` by `map_electron_config`.
+//! - 1: current layout.
+
+use chrono::Local;
+use serde_json::{Map, Value};
+use std::fs;
+use std::path::Path;
+
+use crate::i18n;
+use crate::models::{
+    Adjustments, AppSettings, BgImage, CustomTimes, ImportReport, JumuahTime, ReminderTimes,
+    SkippedKey, StorageReport,
+};
+use crate::error::Result;
+
+pub const SCHEMA_VERSION: u32 = 1;
+pub const VERSION_KEY: &str = "schemaVersion";
+
+type Migration = fn(&mut Map<String, Value>, &mut ImportReport) -> Result<()>;
+
+/// `MIGRATIONS[n]` upgrades a store from version `n` to `n + 1`
+const MIGRATIONS: [Migration; SCHEMA_VERSION as usize] = [
+    fold_electron_keys,
+];
+
+/// Every key the Electron app wrote to its store
+const ELECTRON_KEYS: &[&str] = &[
+    "settings", "calculationMethod", "adj", "adhan", "language", "darkMode", "bgImage",
+    "reminderTimes", "latitude", "longitude", "timezone", "customTimes", "jumuahTime",
+    "sunnahTimes", "weather", "timeDisplay", "quran", "customSettings", "delay", "hijriAdj",
+    "locationName", "volume",
+];
+
+/// Keys that only ever existed in the Electron store
+const ELECTRON_ONLY_KEYS: &[&str] = &[
+    "calculationMethod", "adj", "adhan", "language", "darkMode", "bgImage", "reminderTimes",
+    "sunnahTimes", "weather", "timeDisplay", "quran", "customSettings", "delay", "hijriAdj",
+    "locationName", "volume",
+];
+
+/// Electron settings with no counterpart here
+const UNSUPPORTED_KEYS: &[&str] = &[
+    "sunnahTimes", "weather", "timeDisplay", "quran", "customSettings", "delay", "hijriAdj",
+    "locationName", "volume",
+];
+
+/// Upgrades `values` in place. Returns whether anything changed and has to be
+/// written back, recording the outcome in `report`.
+pub fn migrate(values: &mut Map<String, Value>, file: &Path, report: &mut StorageReport) -> Result<bool> {
+    let version = values.get(VERSION_KEY).and_then(|v| v.as_u64()).unwrap_or(0) as u32;
+    report.schema_version = version;
+
+    if version > SCHEMA_VERSION {
+        eprintln!(
+            "Settings were written by a newer version (schema {}), leaving them as they are",
+            version
+        );
+        return Ok(false);
+    }
+    if version == SCHEMA_VERSION {
+        return Ok(false);
+    }
+
+    let fresh = values.is_empty();
+    if !fresh {
+        report.migrated_from = Some(version);
+        if file.exists() {
+            let backup = file.with_file_name(format!(
+                "settings.v{}.{}.bak.json",
+                version,
+                Local::now().format("%Y%m%d%H%M%S")
+            ));
+            fs::copy(file, &backup)?;
+            report.backup_path = Some(backup.to_string_lossy().into_owned());
+        }
+
+        let mut migration = ImportReport::default();
+        for step in &MIGRATIONS[version as usize..] {
+            step(values, &mut migration)?;
+        }
+        println!(
+            "Migrated settings from schema {} to {} ({} mapped, {} skipped)",
+            version,
+            SCHEMA_VERSION,
+            migration.mapped.len(),
+            migration.skipped.len()
+        );
+        report.migration = Some(migration);
+    }
+
+    values.insert(VERSION_KEY.to_string(), Value::from(SCHEMA_VERSION));
+    report.schema_version = SCHEMA_VERSION;
+    Ok(true)
+}
+
+/// 0 → 1: rewrites a copied Electron store into the current layout
+fn fold_electron_keys(values: &mut Map<String, Value>, report: &mut ImportReport) -> Result<()> {
+    let electron_settings = values
+        .get("settings")
+        .is_some_and(|s| s.get("startupSound").is_some());
+    if !electron_settings && !ELECTRON_ONLY_KEYS.iter().any(|k| values.contains_key(*k)) {
+        return Ok(());
+    }
+
+    let electron: Map<String, Value> = ELECTRON_KEYS
+        .iter()
+        .filter_map(|&k| values.remove(k).map(|v| (k.to_string(), v)))
+        .collect();
+
+    let base = electron
+        .get("settings")
+        .filter(|_| !electron_settings)
+        .and_then(|v| serde_json::from_value(v.clone()).ok())
+        .unwrap_or_default();
+    let imported = map_electron_config(&electron, base);
+
+    values.insert("settings".to_string(), serde_json::to_value(&imported.settings)?);
+    if let Some((lat, lon, tz)) = imported.location {
+        values.insert("latitude".to_string(), Value::from(lat));
+        values.insert("longitude".to_string(), Value::from(lon));
+        values.insert("timezone".to_string(), Value::from(tz));
+    }
+    if let Some(custom_times) = imported.custom_times {
+        values.insert("customTimes".to_string(), serde_json::to_value(custom_times)?);
+    }
+    if let Some(jumuah_time) = imported.jumuah_time {
+        values.insert("jumuahTime".to_string(), serde_json::to_value(jumuah_time)?);
+    }
+
+    report.mapped.extend(imported.report.mapped);
+    report.skipped.extend(imported.report.skipped);
+    Ok(())
+}
+
+/// Settings recovered from an Electron Muezzin store
+pub struct ElectronImport {
+    pub settings: AppSettings,
+    pub location: Option<(f64, f64, String)>,
+    pub custom_times: Option<CustomTimes>,
+    pub jumuah_time: Option<JumuahTime>,
+    pub report: ImportReport,
+}
+
+/// Electron saved form inputs as they were, so numbers are often strings
+fn as_number(value: &Value) -> Option<f64> {
+    value.as_f64().or_else(|| value.as_str()?.trim().parse().ok())
+}
+
+fn as_minutes(value: Option<&Value>) -> Option<u32> {
+    value.and_then(as_number).filter(|n| *n >= 0.0).map(|n| n as u32)
+}
+
+fn as_offset(value: Option<&Value>) -> Option<i32> {
+    value.and_then(as_number).map(|n| n as i32)
+}
+
+/// Electron paths were relative to `src/main/`
+fn resource_path(path: &str) -> String {
+    path.trim_start_matches("../").to_string()
+}
+
+/// Maps an Electron store onto `settings`, keeping its value for anything the
+/// old store doesn't set. Every top-level key ends up mapped or skipped.
+pub fn map_electron_config(config: &Map<String, Value>, mut settings: AppSettings) -> ElectronImport {
+    let mut report = ImportReport::default();
+    let mut skip = |key: &str, reason: &str| {
+        report.skipped.push(SkippedKey { key: key.to_string(), reason: reason.to_string() });
+    };
+    let mut mapped = Vec::new();
+    let mut custom_times = None;
+    let mut jumuah_time = None;
+
+    for (key, value) in config {
+        let ok = match key.as_str() {
+            "settings" => match value.as_object() {
+                Some(old) => {
+                    let flags: [(&str, &mut bool); 6] = [
+                        ("startupSound", &mut settings.startup_sound),
+                        ("notifCheck", &mut settings.notif_check),
+                        ("systray", &mut settings.systray),
+                        ("adhanCheck", &mut settings.adhan_check),
+                        ("autoStart", &mut settings.auto_start),
+                        ("minStart", &mut settings.min_start),
+                    ];
+                    for (name, field) in flags {
+                        if let Some(flag) = old.get(name).and_then(|v| v.as_bool()) {
+                            *field = flag;
+                        }
+                    }
+                    true
+                }
+                None => false,
+            },
+            "calculationMethod" => match value.as_object() {
+                Some(old) => {
+                    let calc = &mut settings.calculation;
+                    let fields: [(&str, &mut String); 5] = [
+                        ("calcMethod", &mut calc.calc_method),
+                        ("madhab", &mut calc.madhab),
+                        ("hlr", &mut calc.hlr),
+                        ("pcr", &mut calc.pcr),
+                        ("shafaq", &mut calc.shafaq),
+                    ];
+                    for (name, field) in fields {
+                        if let Some(text) = old.get(name).and_then(|v| v.as_str()) {
+                            *field = text.to_string();
+                        }
+                    }
+                    true
+                }
+                None => false,
+            },
+            // [enabled, fajr, dhuhr, asr, maghrib, isha]
+            "adj" => match value.as_array() {
+                Some(old) => {
+                    let enabled = old.first().and_then(|v| v.as_bool()).unwrap_or(false);
+                    let offset = |i: usize| as_offset(old.get(i)).unwrap_or(0);
+                    settings.calculation.adjustments = enabled.then(|| Adjustments {
+                        fajr: offset(1),
+                        dhuhr: offset(2),
+                        asr: offset(3),
+                        maghrib: offset(4),
+                        isha: offset(5),
+                    });
+                    true
+                }
+                None => false,
+            },
+            "adhan" => match value.as_object() {
+                Some(old) => {
+                    let custom_path = |name: &str| {
+                        let entry = old.get(name)?;
+                        entry.get("custom")?.as_bool()?.then(|| entry.get("path")?.as_str().map(resource_path))?
+                    };
+                    if let Some(path) = custom_path("adhan") {
+                        settings.adhan_path = path;
+                    }
+                    settings.adhan_fajr_path = custom_path("adhanFajr");
+                    if let Some(dua) = old.get("dua").and_then(|d| d.get("enabled")).and_then(|v| v.as_bool()) {
+                        settings.dua_enabled = dua;
+                    }
+                    true
+                }
+                None => false,
+            },
+            "language" => match value.as_str() {
+                Some(lang) if i18n::LANGUAGES.contains(&lang) => {
+                    settings.language = lang.to_string();
+                    true
+                }
+                _ => false,
+            },
+            "darkMode" => match value.as_bool() {
+                Some(dark) => {
+                    settings.dark_mode = dark;
+                    true
+                }
+                None => false,
+            },
+            // [enabled, path]
+            "bgImage" => match (value.get(0).and_then(|v| v.as_bool()), value.get(1).and_then(|v| v.as_str())) {
+                (Some(enabled), Some(path)) => {
+                    settings.bg_image = Some(BgImage { enabled, path: resource_path(path) });
+                    true
+                }
+                _ => false,
+            },
+            "reminderTimes" => {
+                let minutes = |name: &str| as_minutes(value.get(name));
+                match (value.get("enabled").and_then(|v| v.as_bool()), minutes("fajr"), minutes("dhuhr"), minutes("asr"), minutes("maghrib"), minutes("isha")) {
+                    (Some(enabled), Some(fajr), Some(dhuhr), Some(asr), Some(maghrib), Some(isha)) => {
+                        settings.reminder_times = Some(ReminderTimes {
+                            enabled,
+                            fajr,
+                            dhuhr,
+                            asr,
+                            maghrib,
+                            isha,
+                            jumuah: minutes("jumuah").unwrap_or(0),
+                        });
+                        true
+                    }
+                    _ => false,
+                }
+            }
+            // Mapped together below
+            "latitude" | "longitude" | "timezone" => continue,
+            "customTimes" => {
+                custom_times = serde_json::from_value::<CustomTimes>(value.clone()).ok();
+                custom_times.is_some()
+            }
+            "jumuahTime" => {
+                jumuah_time = serde_json::from_value::<JumuahTime>(value.clone()).ok();
+                jumuah_time.is_some()
+            }
+            other if UNSUPPORTED_KEYS.contains(&other) => {
+                skip(other, "No equivalent in this version");
+                continue;
+            }
+            other => {
+                skip(other, "Unknown key");
+                continue;
+            }
+        };
+
+        if ok {
+            mapped.push(key.clone());
+        } else {
+            skip(key, "Unexpected value");
+        }
+    }
+
+    let lat = config.get("latitude").and_then(as_number);
+    let lon = config.get("longitude").and_then(as_number);
+    let tz = config.get("timezone").and_then(|v| v.as_str()).filter(|tz| tz.parse::<chrono_tz::Tz>().is_ok());
+    let location = match (lat, lon, tz) {
+        (Some(lat), Some(lon), Some(tz)) if (-90.0..=90.0).contains(&lat) && (-180.0..=180.0).contains(&lon) => {
+            mapped.extend(["latitude", "longitude", "timezone"].map(String::from));
+            Some((lat, lon, tz.to_string()))
+        }
+        _ => {
+            for key in ["latitude", "longitude", "timezone"] {
+                if config.contains_key(key) {
+                    skip(key, "Incomplete or invalid location");
+                }
+            }
+            None
+        }
+    };
+
+    report.mapped = mapped;
+    ElectronImport { settings, location, custom_times, jumuah_time, report }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::path::PathBuf;
+
+    /// A `settings.json` holding `values` in a directory of its own
+    fn store_file(name: &str, values: &Value) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("muezzin-migrations-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("settings.json");
+        fs::write(&file, values.to_string()).unwrap();
+        file
+    }
+
+    fn object(value: Value) -> Map<String, Value> {
+        value.as_object().unwrap().clone()
+    }
+
+    fn electron_config() -> Value {
+        json!({
+            "settings": { "startupSound": true, "notifCheck": true, "systray": false, "adhanCheck": false, "autoStart": true, "minStart": true },
+            "calculationMethod": { "calcMethod": "ISNA", "madhab": "Hanafi", "hlr": "MM", "pcr": "CA", "shafaq": "general" },
+            "adj": [true, "2", -1, 0, 3, "0"],
+            "adhan": {
+                "adhan": { "custom": true, "path": "../ressources/audio/custom.mp3" },
+                "adhanFajr": { "custom": false, "path": "" },
+                "dua": { "enabled": false },
+            },
+            "language": "fr",
+            "darkMode": false,
+            "reminderTimes": { "enabled": true, "fajr": "10", "dhuhr": 5, "asr": 0, "maghrib": 0, "isha": "15" },
+            "latitude": "51.5074",
+            "longitude": -0.1278,
+            "timezone": "Europe/London",
+            "weather": { "enabled": true },
+        })
+    }
+
+    #[test]
+    fn fresh_store_is_stamped_without_a_backup() {
+        let mut values = Map::new();
+        let mut report = StorageReport::default();
+        let file = std::env::temp_dir().join("muezzin-migrations-missing").join("settings.json");
+
+        assert!(migrate(&mut values, &file, &mut report).unwrap());
+        assert_eq!(values.get(VERSION_KEY), Some(&Value::from(SCHEMA_VERSION)));
+        assert_eq!(report.schema_version, SCHEMA_VERSION);
+        assert_eq!(report.migrated_from, None);
+        assert_eq!(report.backup_path, None);
+    }
+
+    #[test]
+    fn current_and_newer_stores_are_left_alone() {
+        for version in [SCHEMA_VERSION, SCHEMA_VERSION + 1] {
+            let original = object(json!({ "schemaVersion": version, "darkMode": true }));
+            let mut values = original.clone();
+            let mut report = StorageReport::default();
+            let file = store_file(&format!("v{}", version), &Value::Object(original.clone()));
+
+            assert!(!migrate(&mut values, &file, &mut report).unwrap());
+            assert_eq!(values, original);
+            assert_eq!(report.schema_version, version);
+            assert_eq!(report.backup_path, None);
+            let _ = fs::remove_dir_all(file.parent().unwrap());
+        }
+    }
+
+    #[test]
+    fn unversioned_store_is_backed_up_and_kept() {
+        let settings = serde_json::to_value(AppSettings { dark_mode: false, ..AppSettings::default() }).unwrap();
+        let original = json!({ "settings": settings, "latitude": 21.4225, "longitude": 39.8262, "timezone": "Asia/Riyadh" });
+        let file = store_file("v0", &original);
+        let mut values = object(original.clone());
+        let mut report = StorageReport::default();
+
+        assert!(migrate(&mut values, &file, &mut report).unwrap());
+        assert_eq!(report.migrated_from, Some(0));
+        assert_eq!(report.schema_version, SCHEMA_VERSION);
+        let migration = report.migration.unwrap();
+        assert!(migration.mapped.is_empty() && migration.skipped.is_empty());
+
+        // Nothing Electron about it, so only the version is new
+        values.remove(VERSION_KEY);
+        assert_eq!(Value::Object(values), original);
+
+        let backup = PathBuf::from(report.backup_path.unwrap());
+        assert!(backup.file_name().unwrap().to_string_lossy().starts_with("settings.v0."));
+        assert_eq!(fs::read_to_string(&backup).unwrap(), original.to_string());
+        let _ = fs::remove_dir_all(file.parent().unwrap());
+    }
+
+    #[test]
+    fn electron_store_is_folded_into_settings() {
+        let file = store_file("electron", &electron_config());
+        let mut values = object(electron_config());
+        let mut report = StorageReport::default();
+
+        assert!(migrate(&mut values, &file, &mut report).unwrap());
+        assert!(report.backup_path.is_some());
+        for key in ["calculationMethod", "adj", "adhan", "language", "darkMode", "reminderTimes", "weather"] {
+            assert!(!values.contains_key(key), "{} left behind", key);
+        }
+        assert_eq!(values["latitude"], json!(51.5074));
+        assert_eq!(values["longitude"], json!(-0.1278));
+        assert_eq!(values["timezone"], json!("Europe/London"));
+
+        let settings: AppSettings = serde_json::from_value(values["settings"].clone()).unwrap();
+        assert!(settings.startup_sound && !settings.systray && !settings.adhan_check && settings.min_start);
+        assert_eq!(settings.calculation.calc_method, "ISNA");
+        assert_eq!(settings.calculation.madhab, "Hanafi");
+        let adjustments = settings.calculation.adjustments.unwrap();
+        assert_eq!(
+            (adjustments.fajr, adjustments.dhuhr, adjustments.asr, adjustments.maghrib, adjustments.isha),
+            (2, -1, 0, 3, 0)
+        );
+        assert_eq!(settings.adhan_path, "ressources/audio/custom.mp3");
+        assert_eq!(settings.adhan_fajr_path, None);
+        assert!(!settings.dua_enabled && !settings.dark_mode);
+        assert_eq!(settings.language, "fr");
+        let reminders = settings.reminder_times.unwrap();
+        assert!(reminders.enabled);
+        assert_eq!((reminders.fajr, reminders.dhuhr, reminders.isha, reminders.jumuah), (10, 5, 15, 0));
+
+        let migration = report.migration.unwrap();
+        assert_eq!(migration.skipped.len(), 1);
+        assert_eq!(migration.skipped[0].key, "weather");
+        assert_eq!(migration.skipped[0].reason, "No equivalent in this version");
+        for key in ["settings", "calculationMethod", "adj", "adhan", "language", "reminderTimes", "latitude", "timezone"] {
+            assert!(migration.mapped.iter().any(|k| k == key), "{} not mapped", key);
+        }
+        let _ = fs::remove_dir_all(file.parent().unwrap());
+    }
+
+    #[test]
+    fn bad_electron_values_are_skipped_not_guessed() {
+        let mut config = object(electron_config());
+        config.insert("latitude".to_string(), json!(120.0));
+        config.insert("language".to_string(), json!("xx"));
+        config.insert("adj".to_string(), json!("yes"));
+
+        let imported = map_electron_config(&config, AppSettings::default());
+        assert_eq!(imported.location, None);
+        assert_eq!(imported.settings.language, "en");
+        assert!(imported.settings.calculation.adjustments.is_none());
+
+        let skipped: Vec<(&str, &str)> = imported
+            .report
+            .skipped
+            .iter()
+            .map(|s| (s.key.as_str(), s.reason.as_str()))
+            .collect();
+        for expected in [
+            ("adj", "Unexpected value"),
+            ("language", "Unexpected value"),
+            ("latitude", "Incomplete or invalid location"),
+            ("longitude", "Incomplete or invalid location"),
+            ("timezone", "Incomplete or invalid location"),
+        ] {
+            assert!(skipped.contains(&expected), "{:?} not in {:?}", expected, skipped);
+        }
+    }
+}
@@ -1,15 +1,8 @@
 use serde::{Deserialize, Serialize};
-use chrono::{DateTime, Local};
+use chrono::{DateTime, Local, NaiveDate};
+use std::collections::BTreeMap;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct PrayerTimes {
-    pub fajr: DateTime<Local>,
-    pub sunrise: DateTime<Local>,
-    pub dhuhr: DateTime<Local>,
-    pub asr: DateTime<Local>,
-    pub maghrib: DateTime<Local>,
-    pub isha: DateTime<Local>,
-}
+pub use muezzin_core::models::{Adjustments, CalculationSettings, CustomTimes, JumuahTime, PrayerTimes};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
@@ -28,6 +21,19 @@ pub struct AppSettings {
     pub language: String,
     pub dark_mode: bool,
     pub bg_image: Option<BgImage>,
+    pub alert_modes: PrayerAlertModes,
+    pub iqamah: Option<IqamahTimes>,
+    pub snooze_minutes: u32,
+    /// Adhan audio stays off until this time, notifications still show
+    pub muted_until: Option<DateTime<Local>>,
+    pub quiet_windows: Vec<QuietWindow>,
+    pub http_api: HttpApiSettings,
+    pub mqtt: MqttSettings,
+    pub hooks: Vec<Hook>,
+    /// Pause desktop media players while the adhan plays (Linux only)
+    pub pause_media: bool,
+    pub multi_room: MultiRoomSettings,
+    pub kiosk: KioskSettings,
 }
 
 impl Default for AppSettings {
@@ -47,16 +53,216 @@ impl Default for AppSettings {
             language: "en".to_string(),
             dark_mode: true,
             bg_image: None,
+            alert_modes: PrayerAlertModes::default(),
+            iqamah: None,
+            snooze_minutes: 5,
+            muted_until: None,
+            quiet_windows: Vec::new(),
+            http_api: HttpApiSettings::default(),
+            mqtt: MqttSettings::default(),
+            hooks: Vec::new(),
+            pause_media: true,
+            multi_room: MultiRoomSettings::default(),
+            kiosk: KioskSettings::default(),
+        }
+    }
+}
+
+/// Opt-in HTTP API for dashboards and displays on the LAN. Every request
+/// must carry `token`, which is generated when the API is first enabled.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HttpApiSettings {
+    pub enabled: bool,
+    pub bind_address: String,
+    pub port: u16,
+    pub token: String,
+}
+
+impl Default for HttpApiSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            bind_address: "127.0.0.1".to_string(),
+            port: 7447,
+            token: String::new(),
+        }
+    }
+}
+
+/// Opt-in MQTT publisher, e.g. for Home Assistant. State goes under
+/// `base_topic`, discovery configs under `discovery_prefix`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MqttSettings {
+    pub enabled: bool,
+    pub host: String,
+    pub port: u16,
+    pub username: String,
+    pub password: String,
+    pub client_id: String,
+    pub base_topic: String,
+    pub discovery: bool,
+    pub discovery_prefix: String,
+}
+
+impl Default for MqttSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            host: "localhost".to_string(),
+            port: 1883,
+            username: String::new(),
+            password: String::new(),
+            client_id: "muezzin".to_string(),
+            base_topic: "muezzin".to_string(),
+            discovery: true,
+            discovery_prefix: "homeassistant".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MultiRoomRole {
+    #[default]
+    Off,
+    /// Announces prayer events to the other rooms
+    Leader,
+    Follower,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FollowMode {
+    /// Play the adhan when the leader does
+    #[default]
+    Adhan,
+    /// Use the leader's location and calculation settings, play locally
+    Schedule,
+}
+
+/// Several instances on a LAN sharing one adhan. Followers find the leader
+/// by mDNS, or at `leader_address` (`host:port`) when it is set.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MultiRoomSettings {
+    pub role: MultiRoomRole,
+    /// Announced by a leader, shown by followers
+    pub room_name: String,
+    /// Where a leader listens
+    pub port: u16,
+    pub follow: FollowMode,
+    /// Room name of the leader to follow, empty for the first one found
+    pub leader: String,
+    pub leader_address: String,
+}
+
+impl Default for MultiRoomSettings {
+    fn default() -> Self {
+        Self {
+            role: MultiRoomRole::Off,
+            room_name: "Muezzin".to_string(),
+            port: 7448,
+            follow: FollowMode::Adhan,
+            leader: String::new(),
+            leader_address: String::new(),
+        }
+    }
+}
+
+/// Full-screen mosque display
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KioskSettings {
+    /// Start the app on the display instead of the main window
+    pub enabled: bool,
+    /// Rotated along the bottom of the display
+    pub announcements: Vec<String>,
+    pub announcement_secs: u32,
+    /// Shown on Fridays until Asr, e.g. the khutbah topic
+    pub jumuah_banner: String,
+    /// SHA-256 of the PIN that unlocks settings while the display runs,
+    /// empty for no lock. Only changed through `set_kiosk_pin`.
+    pub pin_hash: String,
+}
+
+impl Default for KioskSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            announcements: Vec::new(),
+            announcement_secs: 15,
+            jumuah_banner: String::new(),
+            pin_hash: String::new(),
         }
     }
 }
 
+/// Moments a hook can run at
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum HookEvent {
+    Reminder,
+    AdhanStart,
+    AdhanEnd,
+    Iqamah,
+    Midnight,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum HookAction {
+    /// POSTs the event as JSON
+    Http {
+        url: String,
+        #[serde(default)]
+        headers: BTreeMap<String, String>,
+    },
+    /// Runs a program with the event in `MUEZZIN_*` environment variables
+    Command {
+        program: String,
+        #[serde(default)]
+        args: Vec<String>,
+    },
+}
+
+/// User action run on prayer events, e.g. dimming the lights at the adhan
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Hook {
+    pub enabled: bool,
+    pub name: String,
+    pub events: Vec<HookEvent>,
+    pub action: HookAction,
+    /// Per attempt
+    #[serde(default = "default_hook_timeout")]
+    pub timeout_secs: u32,
+    /// Attempts after the first failed one
+    #[serde(default)]
+    pub retries: u32,
+}
+
+fn default_hook_timeout() -> u32 {
+    10
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BgImage {
     pub enabled: bool,
     pub path: String,
 }
 
+/// Recurring span where the adhan is silent but notifications are kept,
+/// e.g. weekdays 09:00-17:00. `end` before `start` wraps past midnight.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuietWindow {
+    pub enabled: bool,
+    pub name: Option<String>,
+    pub days: Vec<chrono::Weekday>,
+    pub start: String,
+    pub end: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ReminderTimes {
     pub enabled: bool,
@@ -68,53 +274,73 @@ pub struct ReminderTimes {
     pub jumuah: u32,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AlertMode {
+    Silent,
+    Notification,
+    #[default]
+    NotificationAndSound,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(default)]
-pub struct CalculationSettings {
-    pub calc_method: String,
-    pub madhab: String,
-    pub hlr: String,
-    pub pcr: String,
-    pub shafaq: String,
-    pub adjustments: Option<Adjustments>,
+pub struct PrayerAlertModes {
+    pub fajr: AlertMode,
+    pub dhuhr: AlertMode,
+    pub asr: AlertMode,
+    pub maghrib: AlertMode,
+    pub isha: AlertMode,
 }
 
-impl Default for CalculationSettings {
-    fn default() -> Self {
-        Self {
-            calc_method: "MWL".to_string(),
-            madhab: "Shafi".to_string(),
-            hlr: "TA".to_string(),
-            pcr: "CC".to_string(),
-            shafaq: "shafaqG".to_string(),
-            adjustments: None,
+impl PrayerAlertModes {
+    pub fn for_prayer(&self, prayer: &str) -> AlertMode {
+        match prayer {
+            "Fajr" => self.fajr,
+            "Dhuhr" => self.dhuhr,
+            "Asr" => self.asr,
+            "Maghrib" => self.maghrib,
+            "Isha" => self.isha,
+            _ => AlertMode::NotificationAndSound,
         }
     }
 }
 
+/// Minutes between the adhan and the Iqamah for each prayer
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Adjustments {
-    pub fajr: i32,
-    pub dhuhr: i32,
-    pub asr: i32,
-    pub maghrib: i32,
-    pub isha: i32,
+pub struct IqamahTimes {
+    pub enabled: bool,
+    pub fajr: u32,
+    pub dhuhr: u32,
+    pub asr: u32,
+    pub maghrib: u32,
+    pub isha: u32,
+    pub jumuah: u32,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct CustomTimes {
-    pub enabled: bool,
-    pub fajr: Option<String>,
-    pub dhuhr: Option<String>,
-    pub asr: Option<String>,
-    pub maghrib: Option<String>,
-    pub isha: Option<String>,
+impl IqamahTimes {
+    pub fn offset_for(&self, prayer: &str, friday: bool) -> Option<u32> {
+        if !self.enabled {
+            return None;
+        }
+        match prayer {
+            "Fajr" => Some(self.fajr),
+            "Dhuhr" if friday => Some(self.jumuah),
+            "Dhuhr" => Some(self.dhuhr),
+            "Asr" => Some(self.asr),
+            "Maghrib" => Some(self.maghrib),
+            "Isha" => Some(self.isha),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct JumuahTime {
-    pub enabled: bool,
-    pub time: String,
+pub struct LocationProfile {
+    pub name: String,
+    pub latitude: f64,
+    pub longitude: f64,
+    pub timezone: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -125,3 +351,422 @@ pub struct LocationInfo {
     pub continent_code: Option<String>,
     pub country_code: Option<String>,
 }
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AudioProgress {
+    pub playing: bool,
+    pub paused: bool,
+    /// Seconds since the start of the track
+    pub position: f64,
+    /// Track length in seconds, when the decoder can tell
+    pub duration: Option<f64>,
+    pub percent: f64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(tag = "mode", rename_all = "camelCase")]
+pub enum RepeatMode {
+    #[default]
+    None,
+    /// Play every verse `times` times before moving on
+    Verse { times: u32 },
+    /// Loop the whole range, forever when `times` is 0
+    Range { times: u32 },
+    /// Repeat each verse, then review the range learnt so far
+    #[serde(rename_all = "camelCase")]
+    Memorization { verse_times: u32, review_times: u32 },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuranPlaybackRequest {
+    pub reciter_id: u32,
+    pub chapter: u32,
+    pub start_verse: Option<u32>,
+    pub end_verse: Option<u32>,
+    #[serde(default)]
+    pub repeat: RepeatMode,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuranPlaybackState {
+    pub active: bool,
+    pub reciter_id: Option<u32>,
+    pub chapter: Option<u32>,
+    pub verse: Option<u32>,
+    pub index: usize,
+    pub total: Option<usize>,
+    pub repeat: RepeatMode,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuranBookmark {
+    pub chapter: u32,
+    pub verse: u32,
+    pub label: Option<String>,
+    pub created_at: DateTime<Local>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VerseNote {
+    pub chapter: u32,
+    pub verse: u32,
+    pub text: String,
+    pub updated_at: DateTime<Local>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReadingPosition {
+    pub chapter: u32,
+    pub verse: u32,
+    pub page: u32,
+    pub updated_at: DateTime<Local>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum KhatmUnit {
+    Pages,
+    Juz,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KhatmReminder {
+    /// Prayer name as used by the checker ("Fajr", "Dhuhr", ...)
+    pub after_prayer: String,
+    pub delay_minutes: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KhatmPlan {
+    pub start_date: NaiveDate,
+    pub days: u32,
+    pub unit: KhatmUnit,
+    pub reminder: Option<KhatmReminder>,
+    /// Highest mushaf page finished so far
+    pub pages_read: u32,
+    pub completed_at: Option<DateTime<Local>>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct QuranProgress {
+    pub bookmarks: Vec<QuranBookmark>,
+    pub notes: Vec<VerseNote>,
+    pub last_read: Option<ReadingPosition>,
+    pub khatm: Option<KhatmPlan>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KhatmStatus {
+    pub day: u32,
+    pub days: u32,
+    pub unit: KhatmUnit,
+    /// Today's target as a page range, and as juz numbers for juz plans
+    pub target_from_page: u32,
+    pub target_to_page: u32,
+    pub target_juz: Option<(u32, u32)>,
+    pub pages_read: u32,
+    /// Pages behind schedule at the end of today's target, 0 when on track
+    pub behind_by: u32,
+    pub today_done: bool,
+    pub completed: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PrayerStatus {
+    OnTime,
+    Late,
+    Congregation,
+    Missed,
+}
+
+impl PrayerStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PrayerStatus::OnTime => "onTime",
+            PrayerStatus::Late => "late",
+            PrayerStatus::Congregation => "congregation",
+            PrayerStatus::Missed => "missed",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "onTime" => Some(PrayerStatus::OnTime),
+            "late" => Some(PrayerStatus::Late),
+            "congregation" => Some(PrayerStatus::Congregation),
+            "missed" => Some(PrayerStatus::Missed),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PrayerLogEntry {
+    pub date: NaiveDate,
+    pub prayer: String,
+    pub status: PrayerStatus,
+    pub recorded_at: DateTime<Local>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum StatsPeriod {
+    Week,
+    Month,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatusCounts {
+    pub on_time: u32,
+    pub late: u32,
+    pub congregation: u32,
+    pub missed: u32,
+    /// Past prayers with no entry, counted from the first day ever logged
+    pub unlogged: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PrayerCounts {
+    pub prayer: String,
+    #[serde(flatten)]
+    pub counts: StatusCounts,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PrayerStats {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub prayers: Vec<PrayerCounts>,
+    pub total: StatusCounts,
+}
+
+/// Consecutive days with all five prayers prayed
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PrayerStreak {
+    pub current: u32,
+    pub longest: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QadaBalance {
+    pub prayer: String,
+    /// Prayers owed from before tracking started, entered by the user
+    pub backlog: u32,
+    pub missed: u32,
+    pub made_up: u32,
+    pub outstanding: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "mode", rename_all = "camelCase")]
+pub enum MuteRequest {
+    ForHours { hours: f64 },
+    UntilNextPrayer,
+    Today,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MuteStatus {
+    pub muted: bool,
+    pub until: Option<DateTime<Local>>,
+    /// Name of the quiet window currently silencing the adhan, if any
+    pub quiet_window: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SkippedKey {
+    pub key: String,
+    pub reason: String,
+}
+
+/// What a migration or an import did with each key it found
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportReport {
+    pub mapped: Vec<String>,
+    pub skipped: Vec<SkippedKey>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CorruptValue {
+    pub key: String,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageReport {
+    pub schema_version: u32,
+    /// Version the store was migrated from at this launch, if it was
+    pub migrated_from: Option<u32>,
+    pub backup_path: Option<String>,
+    pub migration: Option<ImportReport>,
+    /// Stored values that no longer deserialize, left untouched on disk
+    pub corrupt: Vec<CorruptValue>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ElectronImportResult {
+    pub source: String,
+    /// False for a dry run, which only reports what would be imported
+    pub applied: bool,
+    #[serde(flatten)]
+    pub report: ImportReport,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleLocation {
+    pub latitude: f64,
+    pub longitude: f64,
+    pub timezone: String,
+}
+
+/// Everything a settings bundle carries. Iqamah offsets are part of `settings`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BundlePayload {
+    pub settings: AppSettings,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location: Option<BundleLocation>,
+    #[serde(default)]
+    pub location_profiles: Vec<LocationProfile>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub active_profile: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub custom_times: Option<CustomTimes>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub jumuah_time: Option<JumuahTime>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SettingChange {
+    /// Dotted path into the payload, e.g. `settings.calculation.madhab`
+    pub path: String,
+    pub current: Option<serde_json::Value>,
+    pub incoming: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BundlePreview {
+    pub exported_at: DateTime<Local>,
+    pub changes: Vec<SettingChange>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HttpApiStatus {
+    pub running: bool,
+    /// Where it listens, e.g. `127.0.0.1:7447`
+    pub address: Option<String>,
+    /// Why the last start failed, e.g. the port being taken
+    pub error: Option<String>,
+}
+
+/// One run of a hook, with every attempt it took
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HookExecution {
+    pub hook: String,
+    pub event: HookEvent,
+    pub started_at: DateTime<Local>,
+    pub attempts: u32,
+    pub success: bool,
+    pub duration_ms: u64,
+    /// Last attempt's error, e.g. a timeout or HTTP status
+    pub error: Option<String>,
+}
+
+/// A row of the display's timetable
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KioskPrayer {
+    pub name: String,
+    pub adhan: DateTime<Local>,
+    /// None for Sunrise and when no Iqamah offsets are set
+    pub iqamah: Option<DateTime<Local>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KioskJumuah {
+    pub khutbah: DateTime<Local>,
+    pub iqamah: Option<DateTime<Local>>,
+    pub banner: String,
+}
+
+/// Everything the mosque display shows, apart from the ticking clock
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KioskDisplay {
+    pub active: bool,
+    /// Settings need the PIN
+    pub locked: bool,
+    pub date: NaiveDate,
+    pub hijri: String,
+    pub prayers: Vec<KioskPrayer>,
+    pub next: Option<KioskPrayer>,
+    /// Prayer whose adhan has been called and whose Iqamah is still to come
+    pub awaiting_iqamah: Option<KioskPrayer>,
+    /// Set on Fridays until Asr
+    pub jumuah: Option<KioskJumuah>,
+    pub announcements: Vec<String>,
+    pub announcement_secs: u32,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MultiRoomState {
+    #[default]
+    Off,
+    Leading,
+    /// No leader found yet
+    Searching,
+    Connected,
+    /// The leader was found but stopped answering or refuses connections
+    Unreachable,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MultiRoomStatus {
+    pub state: MultiRoomState,
+    /// Leader's room name, our own when leading
+    pub leader: Option<String>,
+    pub address: Option<String>,
+    /// Followers connected to us, when leading
+    pub followers: usize,
+    /// Last message from the leader, when following
+    pub last_seen: Option<DateTime<Local>>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MqttStatus {
+    pub connected: bool,
+    /// Last connection error, cleared once the broker accepts the client
+    pub error: Option<String>,
+}
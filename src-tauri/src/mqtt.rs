@@ -0,0 +1,469 @@
+//! Opt-in MQTT publisher for home automation. Publishes the next prayer and a
+//! countdown as retained state, prayer events as they happen, and Home
+//! Assistant discovery configs so the entities show up on their own.
+//!
+//! ```text
+//! <base>/status              online / offline (retained, also the last will)
+//! <base>/next_prayer         Asr (retained)
+//! <base>/next_prayer_time    2025-03-01T15:42:00+01:00 (retained)
+//! <base>/countdown           minutes until the next prayer (retained)
+//! <base>/event               {"type": "adhan", "prayer": "Asr", ...}
+//! <base>/command/stop        stop the adhan
+//! <base>/command/play        play the adhan, payload `Fajr` for the Fajr one
+//! <base>/command/mute        payload a MuteRequest, empty mutes until the next prayer
+//! <base>/command/unmute
+//! ```
+
+use chrono::{DateTime, Local};
+use parking_lot::Mutex;
+use rumqttc::{AsyncClient, Event, LastWill, MqttOptions, Packet, QoS};
+use serde_json::json;
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tokio::sync::{broadcast, oneshot};
+use tokio::time::{interval, sleep, timeout, MissedTickBehavior};
+
+use crate::error::{AppError, Result};
+use crate::events::{EventBus, PrayerEvent};
+use crate::models::{MqttSettings, MqttStatus, MuteRequest};
+use crate::{commands, AppState};
+
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+const STATE_INTERVAL: Duration = Duration::from_secs(60);
+/// Home Assistant buttons send this unless told otherwise
+const HA_PRESS: &str = "PRESS";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MqttCommand {
+    Stop,
+    Play { prayer: Option<String> },
+    Mute(MuteRequest),
+    Unmute,
+}
+
+/// What the publisher needs from the app, so it can run against a fake in tests
+pub trait MqttHost: Send + Sync {
+    fn next_prayer(&self) -> Result<(String, DateTime<Local>)>;
+    fn run_command(&self, command: MqttCommand) -> Result<()>;
+}
+
+struct Topics {
+    base: String,
+}
+
+impl Topics {
+    fn new(settings: &MqttSettings) -> Self {
+        Self { base: settings.base_topic.trim_end_matches('/').to_string() }
+    }
+
+    fn status(&self) -> String {
+        format!("{}/status", self.base)
+    }
+
+    fn state(&self, name: &str) -> String {
+        format!("{}/{}", self.base, name)
+    }
+
+    fn event(&self) -> String {
+        format!("{}/event", self.base)
+    }
+
+    fn command(&self, name: &str) -> String {
+        format!("{}/command/{}", self.base, name)
+    }
+
+    /// `stop` for `<base>/command/stop`
+    fn command_name<'a>(&self, topic: &'a str) -> Option<&'a str> {
+        topic.strip_prefix(&self.base)?.strip_prefix("/command/")
+    }
+}
+
+fn parse_command(name: &str, payload: &[u8]) -> Result<MqttCommand> {
+    let payload = std::str::from_utf8(payload)
+        .map_err(|_| AppError::InvalidInput("MQTT payload is not UTF-8".to_string()))?
+        .trim();
+    let pressed = payload.is_empty() || payload == HA_PRESS;
+    match name {
+        "stop" => Ok(MqttCommand::Stop),
+        "play" => Ok(MqttCommand::Play { prayer: (!pressed).then(|| payload.to_string()) }),
+        "mute" if pressed => Ok(MqttCommand::Mute(MuteRequest::UntilNextPrayer)),
+        "mute" => Ok(MqttCommand::Mute(serde_json::from_str(payload)?)),
+        "unmute" => Ok(MqttCommand::Unmute),
+        _ => Err(AppError::InvalidInput(format!("Unknown MQTT command '{}'", name))),
+    }
+}
+
+/// Home Assistant discovery configs as `(topic, payload)` pairs
+fn discovery(settings: &MqttSettings, topics: &Topics) -> Vec<(String, String)> {
+    let node = settings.client_id.replace(|c: char| !c.is_ascii_alphanumeric() && c != '_' && c != '-', "_");
+    let device = json!({
+        "identifiers": [node],
+        "name": "Muezzin",
+        "model": "Muezzin",
+        "sw_version": env!("CARGO_PKG_VERSION"),
+    });
+
+    let sensors = [
+        ("next_prayer", "Next prayer", json!({ "icon": "mdi:mosque" })),
+        ("next_prayer_time", "Next prayer time", json!({ "device_class": "timestamp" })),
+        ("countdown", "Next prayer in", json!({ "unit_of_measurement": "min", "icon": "mdi:timer-sand" })),
+    ];
+    let buttons = [
+        ("stop", "Stop adhan", "mdi:stop"),
+        ("play", "Play adhan", "mdi:play"),
+        ("mute", "Mute until next prayer", "mdi:volume-off"),
+        ("unmute", "Unmute adhan", "mdi:volume-high"),
+    ];
+
+    let mut configs = Vec::new();
+    for (key, name, extra) in sensors {
+        let mut config = json!({
+            "name": name,
+            "unique_id": format!("{}_{}", node, key),
+            "state_topic": topics.state(key),
+            "availability_topic": topics.status(),
+            "device": device,
+        });
+        config.as_object_mut().unwrap().extend(extra.as_object().unwrap().clone());
+        configs.push((format!("{}/sensor/{}/{}/config", settings.discovery_prefix, node, key), config.to_string()));
+    }
+    for (key, name, icon) in buttons {
+        let config = json!({
+            "name": name,
+            "unique_id": format!("{}_{}", node, key),
+            "command_topic": topics.command(key),
+            "availability_topic": topics.status(),
+            "icon": icon,
+            "device": device,
+        });
+        configs.push((format!("{}/button/{}/{}/config", settings.discovery_prefix, node, key), config.to_string()));
+    }
+    configs
+}
+
+struct Running {
+    settings: MqttSettings,
+    status: Arc<Mutex<MqttStatus>>,
+    shutdown: oneshot::Sender<()>,
+}
+
+#[derive(Default)]
+pub struct Mqtt {
+    running: Mutex<Option<Running>>,
+}
+
+impl Mqtt {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Connects, disconnects or reconnects so the client matches `settings`.
+    /// Connection errors don't fail this, they show up in `status` and the
+    /// client keeps retrying.
+    pub fn apply(&self, host: Arc<dyn MqttHost>, events: &EventBus, settings: &MqttSettings) {
+        let mut running = self.running.lock();
+        if running.as_ref().map(|r| &r.settings) == Some(settings) {
+            return;
+        }
+        if let Some(previous) = running.take() {
+            let _ = previous.shutdown.send(());
+        }
+        if !settings.enabled {
+            return;
+        }
+
+        let status = Arc::new(Mutex::new(MqttStatus::default()));
+        let (shutdown, stopped) = oneshot::channel();
+        tokio::spawn(run(settings.clone(), host, events.subscribe(), status.clone(), stopped));
+        *running = Some(Running { settings: settings.clone(), status, shutdown });
+    }
+
+    pub fn status(&self) -> MqttStatus {
+        self.running.lock().as_ref().map(|r| r.status.lock().clone()).unwrap_or_default()
+    }
+}
+
+/// Queues a message without waiting, the event loop may be busy in the same task
+fn publish(client: &AsyncClient, topic: String, retain: bool, payload: String) {
+    if let Err(e) = client.try_publish(topic, QoS::AtLeastOnce, retain, payload) {
+        eprintln!("Error publishing to MQTT: {}", e);
+    }
+}
+
+fn publish_state(client: &AsyncClient, topics: &Topics, host: &dyn MqttHost) {
+    match host.next_prayer() {
+        Ok((prayer, time)) => {
+            let minutes = (time - Local::now()).num_minutes().max(0);
+            publish(client, topics.state("next_prayer"), true, prayer);
+            publish(client, topics.state("next_prayer_time"), true, time.to_rfc3339());
+            publish(client, topics.state("countdown"), true, minutes.to_string());
+        }
+        Err(e) => eprintln!("Error getting next prayer for MQTT: {}", e),
+    }
+}
+
+fn on_connect(client: &AsyncClient, settings: &MqttSettings, topics: &Topics, host: &dyn MqttHost) {
+    if let Err(e) = client.try_subscribe(topics.command("+"), QoS::AtLeastOnce) {
+        eprintln!("Error subscribing to MQTT commands: {}", e);
+    }
+    if settings.discovery {
+        for (topic, config) in discovery(settings, topics) {
+            publish(client, topic, true, config);
+        }
+    }
+    publish(client, topics.status(), true, "online".to_string());
+    publish_state(client, topics, host);
+}
+
+async fn run(
+    settings: MqttSettings,
+    host: Arc<dyn MqttHost>,
+    mut events: broadcast::Receiver<PrayerEvent>,
+    status: Arc<Mutex<MqttStatus>>,
+    mut stopped: oneshot::Receiver<()>,
+) {
+    let topics = Topics::new(&settings);
+    let mut options = MqttOptions::new(settings.client_id.clone(), settings.host.clone(), settings.port);
+    options.set_keep_alive(Duration::from_secs(30));
+    options.set_last_will(LastWill::new(topics.status(), "offline", QoS::AtLeastOnce, true));
+    if !settings.username.is_empty() {
+        options.set_credentials(settings.username.clone(), settings.password.clone());
+    }
+    let (client, mut eventloop) = AsyncClient::new(options, 64);
+
+    let mut tick = interval(STATE_INTERVAL);
+    tick.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    loop {
+        tokio::select! {
+            _ = &mut stopped => break,
+            polled = eventloop.poll() => match polled {
+                Ok(Event::Incoming(Packet::ConnAck(_))) => {
+                    *status.lock() = MqttStatus { connected: true, error: None };
+                    on_connect(&client, &settings, &topics, host.as_ref());
+                }
+                Ok(Event::Incoming(Packet::Publish(message))) => {
+                    let Some(name) = topics.command_name(&message.topic) else { continue };
+                    if let Err(e) = parse_command(name, &message.payload).and_then(|c| host.run_command(c)) {
+                        eprintln!("Error running MQTT command '{}': {}", name, e);
+                    }
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    *status.lock() = MqttStatus { connected: false, error: Some(e.to_string()) };
+                    // The next poll reconnects
+                    tokio::select! {
+                        _ = &mut stopped => return,
+                        _ = sleep(RECONNECT_DELAY) => {}
+                    }
+                }
+            },
+            _ = tick.tick() => {
+                if status.lock().connected {
+                    publish_state(&client, &topics, host.as_ref());
+                }
+            }
+            event = events.recv() => match event {
+                Ok(event) => {
+                    publish(&client, topics.event(), false, serde_json::to_string(&event).unwrap_or_default());
+                    // The next prayer moves on at the adhan and at midnight
+                    if !matches!(event, PrayerEvent::Reminder { .. }) {
+                        publish_state(&client, &topics, host.as_ref());
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(_)) => {}
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+        }
+    }
+
+    // A clean disconnect doesn't fire the last will, so say goodbye first
+    if status.lock().connected {
+        publish(&client, topics.status(), true, "offline".to_string());
+        let _ = client.try_disconnect();
+        let _ = timeout(Duration::from_secs(2), async {
+            while eventloop.poll().await.is_ok() {}
+        })
+        .await;
+    }
+}
+
+impl MqttHost for AppHandle {
+    fn next_prayer(&self) -> Result<(String, DateTime<Local>)> {
+        Ok(self.state::<AppState>().calculator.read().get_next_prayer()?)
+    }
+
+    fn run_command(&self, command: MqttCommand) -> Result<()> {
+        let state = self.state::<AppState>();
+        match command {
+            MqttCommand::Stop => state.audio_player.write().stop(),
+            MqttCommand::Play { prayer } => commands::play_configured_adhan(&state, prayer.as_deref())?,
+            MqttCommand::Mute(request) => {
+                commands::apply_mute(&state, self, request)?;
+            }
+            MqttCommand::Unmute => {
+                commands::apply_unmute(&state, self)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration as ChronoDuration;
+    use tokio::sync::mpsc;
+
+    #[test]
+    fn parses_commands() {
+        assert_eq!(parse_command("stop", b"").unwrap(), MqttCommand::Stop);
+        assert_eq!(parse_command("play", b"PRESS").unwrap(), MqttCommand::Play { prayer: None });
+        assert_eq!(parse_command("play", b"Fajr\n").unwrap(), MqttCommand::Play { prayer: Some("Fajr".to_string()) });
+        assert_eq!(parse_command("mute", b"").unwrap(), MqttCommand::Mute(MuteRequest::UntilNextPrayer));
+        assert_eq!(
+            parse_command("mute", br#"{"mode": "forHours", "hours": 2}"#).unwrap(),
+            MqttCommand::Mute(MuteRequest::ForHours { hours: 2.0 })
+        );
+        assert_eq!(parse_command("unmute", b"").unwrap(), MqttCommand::Unmute);
+        assert!(parse_command("mute", b"{").is_err());
+        assert!(parse_command("reboot", b"").is_err());
+    }
+
+    #[test]
+    fn command_names_only_match_the_base_topic() {
+        let topics = Topics::new(&MqttSettings { base_topic: "home/muezzin/".to_string(), ..Default::default() });
+        assert_eq!(topics.command_name("home/muezzin/command/stop"), Some("stop"));
+        assert_eq!(topics.command_name("home/muezzin2/command/stop"), None);
+        assert_eq!(topics.command_name("home/muezzin/countdown"), None);
+    }
+
+    #[test]
+    fn discovery_points_at_the_state_and_command_topics() {
+        let settings = MqttSettings { client_id: "living room".to_string(), ..Default::default() };
+        let topics = Topics::new(&settings);
+        let configs = discovery(&settings, &topics);
+        assert_eq!(configs.len(), 7);
+
+        let (topic, payload) = &configs[1];
+        assert_eq!(topic, "homeassistant/sensor/living_room/next_prayer_time/config");
+        let payload: serde_json::Value = serde_json::from_str(payload).unwrap();
+        assert_eq!(payload["state_topic"], "muezzin/next_prayer_time");
+        assert_eq!(payload["device_class"], "timestamp");
+        assert_eq!(payload["availability_topic"], "muezzin/status");
+
+        let (topic, payload) = &configs[3];
+        assert_eq!(topic, "homeassistant/button/living_room/stop/config");
+        let payload: serde_json::Value = serde_json::from_str(payload).unwrap();
+        assert_eq!(payload["command_topic"], "muezzin/command/stop");
+    }
+
+    struct FakeHost {
+        next: DateTime<Local>,
+        commands: Mutex<Vec<MqttCommand>>,
+    }
+
+    impl MqttHost for FakeHost {
+        fn next_prayer(&self) -> Result<(String, DateTime<Local>)> {
+            Ok(("Asr".to_string(), self.next))
+        }
+
+        fn run_command(&self, command: MqttCommand) -> Result<()> {
+            self.commands.lock().push(command);
+            Ok(())
+        }
+    }
+
+    /// Waits for a message on `topic`, skipping everything else
+    async fn expect(messages: &mut mpsc::UnboundedReceiver<(String, String)>, topic: &str) -> String {
+        let wait = async {
+            loop {
+                let (t, payload) = messages.recv().await.expect("observer stopped");
+                if t == topic {
+                    return payload;
+                }
+            }
+        };
+        timeout(Duration::from_secs(10), wait).await.unwrap_or_else(|_| panic!("nothing on {}", topic))
+    }
+
+    /// Needs a broker, e.g. `mosquitto -p 1883`, then
+    /// `MUEZZIN_TEST_MQTT=localhost:1883 cargo test mqtt -- --ignored`
+    #[tokio::test]
+    #[ignore = "needs an MQTT broker"]
+    async fn round_trip_through_a_broker() {
+        let address = std::env::var("MUEZZIN_TEST_MQTT").unwrap_or_else(|_| "localhost:1883".to_string());
+        let (broker, port) = address.rsplit_once(':').expect("MUEZZIN_TEST_MQTT is host:port");
+        let port: u16 = port.parse().unwrap();
+        let base = format!("muezzin-test-{}", std::process::id());
+
+        // Observer that sees everything under the test's base topic
+        let (observer, mut observer_loop) = AsyncClient::new(MqttOptions::new(format!("{}-observer", base), broker, port), 64);
+        observer.subscribe(format!("{}/#", base), QoS::AtLeastOnce).await.unwrap();
+        let (sender, mut messages) = mpsc::unbounded_channel();
+        let (subscribed, ready) = oneshot::channel();
+        tokio::spawn(async move {
+            let mut subscribed = Some(subscribed);
+            loop {
+                match observer_loop.poll().await {
+                    Ok(Event::Incoming(Packet::SubAck(_))) => {
+                        if let Some(s) = subscribed.take() {
+                            let _ = s.send(());
+                        }
+                    }
+                    Ok(Event::Incoming(Packet::Publish(p))) => {
+                        let _ = sender.send((p.topic, String::from_utf8_lossy(&p.payload).into_owned()));
+                    }
+                    Ok(_) => {}
+                    Err(e) => panic!("observer lost the broker: {}", e),
+                }
+            }
+        });
+        ready.await.unwrap();
+
+        let host = Arc::new(FakeHost { next: Local::now() + ChronoDuration::minutes(90), commands: Mutex::new(Vec::new()) });
+        let bus = EventBus::new();
+        let mqtt = Mqtt::new();
+        let settings = MqttSettings {
+            enabled: true,
+            host: broker.to_string(),
+            port,
+            client_id: base.clone(),
+            base_topic: base.clone(),
+            discovery_prefix: format!("{}/ha", base),
+            ..Default::default()
+        };
+        mqtt.apply(host.clone(), &bus, &settings);
+
+        assert!(expect(&mut messages, &format!("{}/ha/button/{}/stop/config", base, base)).await.contains("command/stop"));
+        assert_eq!(expect(&mut messages, &format!("{}/status", base)).await, "online");
+        assert_eq!(expect(&mut messages, &format!("{}/next_prayer", base)).await, "Asr");
+        let countdown: i64 = expect(&mut messages, &format!("{}/countdown", base)).await.parse().unwrap();
+        assert!((88..=90).contains(&countdown));
+        assert!(mqtt.status().connected);
+
+        bus.publish(PrayerEvent::Adhan { prayer: "Asr".to_string(), time: Local::now(), with_sound: true });
+        let event: serde_json::Value = serde_json::from_str(&expect(&mut messages, &format!("{}/event", base)).await).unwrap();
+        assert_eq!(event["type"], "adhan");
+        assert_eq!(event["withSound"], true);
+
+        observer.publish(format!("{}/command/stop", base), QoS::AtLeastOnce, false, "PRESS").await.unwrap();
+        observer.publish(format!("{}/command/play", base), QoS::AtLeastOnce, false, "Fajr").await.unwrap();
+        timeout(Duration::from_secs(10), async {
+            while host.commands.lock().len() < 2 {
+                sleep(Duration::from_millis(50)).await;
+            }
+        })
+        .await
+        .expect("commands never arrived");
+        assert_eq!(
+            *host.commands.lock(),
+            vec![MqttCommand::Stop, MqttCommand::Play { prayer: Some("Fajr".to_string()) }]
+        );
+
+        mqtt.apply(host, &bus, &MqttSettings { enabled: false, ..settings });
+        assert_eq!(expect(&mut messages, &format!("{}/status", base)).await, "offline");
+        assert!(!mqtt.status().connected);
+    }
+}
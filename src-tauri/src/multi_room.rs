@@ -0,0 +1,647 @@
+//! Several instances on a LAN sharing one adhan, e.g. the rooms of a
+//! community center. A leader advertises itself over mDNS as
+//! `_muezzin._tcp` and streams newline-delimited JSON to every follower that
+//! connects:
+//!
+//! ```text
+//! {"type":"hello","name":"Main hall","version":"1.0.0"}
+//! {"type":"schedule","schedule":{"latitude":51.5,"longitude":-0.12,...}}
+//! {"type":"adhan","prayer":"Asr","time":"2025-03-01T15:42:00+00:00","withSound":true}
+//! {"type":"ping"}
+//! ```
+//!
+//! A follower either plays the adhan when the leader does, or takes over the
+//! leader's schedule and plays on its own. A leader that goes quiet for
+//! longer than `LEADER_TIMEOUT` is shown as unreachable, and the follower
+//! falls back to its own schedule until it is back. The leader greets every
+//! connection with its schedule, so a reconnect takes it over again.
+
+use chrono::{DateTime, Local};
+use mdns_sd::{ServiceDaemon, ServiceEvent, ServiceInfo};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{broadcast, oneshot};
+use tokio::time::{interval, sleep, timeout};
+
+use muezzin_core::PrayerCalculator;
+use crate::error::{AppError, Result};
+use crate::events::{EventBus, PrayerEvent};
+use crate::models::{
+    CalculationSettings, CustomTimes, FollowMode, JumuahTime, MultiRoomRole, MultiRoomSettings, MultiRoomState,
+    MultiRoomStatus,
+};
+
+pub const SERVICE_TYPE: &str = "_muezzin._tcp.local.";
+const HEARTBEAT: Duration = Duration::from_secs(5);
+const LEADER_TIMEOUT: Duration = Duration::from_secs(15);
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+const DISCOVERY_TIMEOUT: Duration = Duration::from_secs(10);
+const RETRY_DELAY: Duration = Duration::from_secs(3);
+
+/// What a follower needs to calculate the leader's times
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Schedule {
+    pub latitude: f64,
+    pub longitude: f64,
+    pub timezone: String,
+    pub calculation: CalculationSettings,
+    pub custom_times: Option<CustomTimes>,
+    pub jumuah_time: Option<JumuahTime>,
+}
+
+impl Schedule {
+    pub fn calculator(&self) -> Result<PrayerCalculator> {
+        let tz: chrono_tz::Tz = self.timezone.parse()
+            .map_err(|e| AppError::Timezone(format!("Invalid timezone: {}", e)))?;
+        let mut calculator = PrayerCalculator::new();
+        calculator.update_settings(
+            self.latitude,
+            self.longitude,
+            &self.calculation,
+            tz,
+            self.custom_times.clone(),
+            self.jumuah_time.clone(),
+        )?;
+        Ok(calculator)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase", rename_all_fields = "camelCase")]
+enum Message {
+    Hello {
+        name: String,
+        version: String,
+    },
+    Schedule {
+        schedule: Box<Schedule>,
+    },
+    Adhan {
+        prayer: String,
+        time: DateTime<Local>,
+        with_sound: bool,
+    },
+    Ping,
+}
+
+impl Message {
+    fn line(&self) -> String {
+        let mut line = serde_json::to_string(self).unwrap_or_default();
+        line.push('\n');
+        line
+    }
+}
+
+/// What leading and following need from the app or the daemon
+pub trait MultiRoomHost: Send + Sync {
+    /// Leader: the schedule followers take over
+    fn schedule(&self) -> Result<Schedule>;
+    /// Follower: use the leader's schedule instead of our own
+    fn apply_schedule(&self, schedule: &Schedule) -> Result<()>;
+    /// Follower: back to our own schedule
+    fn restore_schedule(&self) -> Result<()>;
+    /// Follower: the leader started the adhan
+    fn play_adhan(&self, prayer: &str) -> Result<()>;
+    fn status_changed(&self, _status: &MultiRoomStatus) {}
+}
+
+struct Shared {
+    settings: MultiRoomSettings,
+    host: Arc<dyn MultiRoomHost>,
+    status: Mutex<MultiRoomStatus>,
+    /// Leader's schedule, once a schedule follower has received one
+    schedule: Mutex<Option<Schedule>>,
+}
+
+impl Shared {
+    /// Updates the status, telling the host unless only `last_seen` moved
+    fn update(&self, change: impl FnOnce(&mut MultiRoomStatus)) {
+        let changed = {
+            let mut status = self.status.lock();
+            let before = MultiRoomStatus { last_seen: None, ..status.clone() };
+            change(&mut status);
+            let after = MultiRoomStatus { last_seen: None, ..status.clone() };
+            (before != after).then_some(status.clone())
+        };
+        if let Some(status) = changed {
+            self.host.status_changed(&status);
+        }
+    }
+
+    fn follows_schedule(&self) -> bool {
+        self.settings.follow == FollowMode::Schedule
+    }
+}
+
+struct Running {
+    shared: Arc<Shared>,
+    shutdown: oneshot::Sender<()>,
+}
+
+#[derive(Default)]
+pub struct MultiRoom {
+    running: Mutex<Option<Running>>,
+}
+
+impl MultiRoom {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Starts, stops or switches leading and following to match `settings`
+    pub fn apply(&self, host: Arc<dyn MultiRoomHost>, events: &EventBus, settings: &MultiRoomSettings) {
+        let previous = {
+            let mut running = self.running.lock();
+            if running.as_ref().map(|r| &r.shared.settings) == Some(settings) {
+                return;
+            }
+            running.take()
+        };
+
+        // Unlocked, as restoring may ask for `leader_schedule`
+        if let Some(previous) = previous {
+            let _ = previous.shutdown.send(());
+            if previous.shared.schedule.lock().is_some() {
+                if let Err(e) = host.restore_schedule() {
+                    eprintln!("Error restoring the local schedule: {}", e);
+                }
+            }
+        }
+        if settings.role == MultiRoomRole::Off {
+            host.status_changed(&MultiRoomStatus::default());
+            return;
+        }
+
+        let shared = Arc::new(Shared {
+            settings: settings.clone(),
+            host,
+            status: Mutex::new(MultiRoomStatus::default()),
+            schedule: Mutex::new(None),
+        });
+        let (shutdown, stopped) = oneshot::channel();
+        match settings.role {
+            MultiRoomRole::Leader => {
+                tokio::spawn(lead(shared.clone(), events.subscribe(), stopped));
+            }
+            _ => {
+                tokio::spawn(follow(shared.clone(), stopped));
+            }
+        }
+        *self.running.lock() = Some(Running { shared, shutdown });
+    }
+
+    pub fn status(&self) -> MultiRoomStatus {
+        self.running.lock().as_ref().map(|r| r.shared.status.lock().clone()).unwrap_or_default()
+    }
+
+    /// The leader's schedule a schedule follower is on, which recalculating
+    /// from our own settings must not replace
+    pub fn leader_schedule(&self) -> Option<Schedule> {
+        self.running.lock().as_ref().and_then(|r| r.shared.schedule.lock().clone())
+    }
+
+    /// Whether a connected leader decides when the adhan plays, so our own
+    /// schedule must stay quiet
+    pub fn leader_plays_adhan(&self) -> bool {
+        self.running.lock().as_ref().is_some_and(|r| {
+            r.shared.settings.role == MultiRoomRole::Follower
+                && !r.shared.follows_schedule()
+                && r.shared.status.lock().state == MultiRoomState::Connected
+        })
+    }
+}
+
+fn mdns_error(e: mdns_sd::Error) -> AppError {
+    AppError::Network(format!("mDNS: {}", e))
+}
+
+/// Advertises the leader, failing when mDNS is unavailable. Followers can
+/// still use `leader_address` then.
+fn advertise(settings: &MultiRoomSettings, port: u16) -> Result<ServiceDaemon> {
+    let mdns = ServiceDaemon::new().map_err(mdns_error)?;
+    let host: String = settings
+        .room_name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '-' })
+        .collect();
+    let info = ServiceInfo::new(
+        SERVICE_TYPE,
+        &settings.room_name,
+        &format!("muezzin-{}.local.", host),
+        "",
+        port,
+        &[("version", env!("CARGO_PKG_VERSION"))][..],
+    )
+    .map_err(mdns_error)?
+    .enable_addr_auto();
+    mdns.register(info).map_err(mdns_error)?;
+    Ok(mdns)
+}
+
+async fn lead(shared: Arc<Shared>, mut events: broadcast::Receiver<PrayerEvent>, mut stopped: oneshot::Receiver<()>) {
+    let settings = &shared.settings;
+    let listener = match TcpListener::bind(("0.0.0.0", settings.port)).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            shared.update(|s| s.error = Some(format!("Can't listen on port {}: {}", settings.port, e)));
+            return;
+        }
+    };
+    let mdns = match advertise(settings, settings.port) {
+        Ok(mdns) => Some(mdns),
+        Err(e) => {
+            eprintln!("Not advertising over mDNS: {}", e);
+            None
+        }
+    };
+    shared.update(|s| {
+        s.state = MultiRoomState::Leading;
+        s.leader = Some(settings.room_name.clone());
+        s.address = listener.local_addr().ok().map(|a| a.to_string());
+    });
+
+    let (lines, _) = broadcast::channel::<String>(64);
+    let hello = Message::Hello { name: settings.room_name.clone(), version: env!("CARGO_PKG_VERSION").to_string() }.line();
+    let schedule_line = Arc::new(Mutex::new(None::<String>));
+    let followers = Arc::new(AtomicUsize::new(0));
+    let mut heartbeat = interval(HEARTBEAT);
+
+    loop {
+        tokio::select! {
+            _ = &mut stopped => break,
+            accepted = listener.accept() => match accepted {
+                Ok((stream, _)) => {
+                    let greeting = format!("{}{}", hello, schedule_line.lock().clone().unwrap_or_default());
+                    tokio::spawn(serve_follower(stream, greeting, lines.subscribe(), shared.clone(), followers.clone()));
+                }
+                Err(e) => eprintln!("Error accepting a follower: {}", e),
+            },
+            event = events.recv() => match event {
+                Ok(PrayerEvent::Adhan { prayer, time, with_sound }) => {
+                    let _ = lines.send(Message::Adhan { prayer, time, with_sound }.line());
+                }
+                Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => {}
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+            _ = heartbeat.tick() => {
+                // Resend the schedule only when it changed
+                match shared.host.schedule() {
+                    Ok(schedule) => {
+                        let line = Message::Schedule { schedule: Box::new(schedule) }.line();
+                        let mut current = schedule_line.lock();
+                        if current.as_ref() != Some(&line) {
+                            let _ = lines.send(line.clone());
+                            *current = Some(line);
+                        }
+                    }
+                    Err(e) => eprintln!("Error reading the schedule to share: {}", e),
+                }
+                let _ = lines.send(Message::Ping.line());
+            }
+        }
+    }
+
+    if let Some(mdns) = mdns {
+        let _ = mdns.shutdown();
+    }
+}
+
+async fn serve_follower(
+    mut stream: TcpStream,
+    greeting: String,
+    mut lines: broadcast::Receiver<String>,
+    shared: Arc<Shared>,
+    followers: Arc<AtomicUsize>,
+) {
+    let count = followers.fetch_add(1, Ordering::SeqCst) + 1;
+    shared.update(|s| s.followers = count);
+
+    if stream.write_all(greeting.as_bytes()).await.is_ok() {
+        loop {
+            let line = match lines.recv().await {
+                Ok(line) => line,
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => break,
+            };
+            if stream.write_all(line.as_bytes()).await.is_err() {
+                break;
+            }
+        }
+    }
+
+    let count = followers.fetch_sub(1, Ordering::SeqCst) - 1;
+    shared.update(|s| s.followers = count);
+}
+
+/// Room name and address of the leader to follow
+async fn find_leader(settings: &MultiRoomSettings, mdns: Option<&ServiceDaemon>) -> Result<(String, SocketAddr)> {
+    if !settings.leader_address.is_empty() {
+        let address = tokio::net::lookup_host(&settings.leader_address)
+            .await?
+            .next()
+            .ok_or_else(|| AppError::NotFound(format!("Can't resolve {}", settings.leader_address)))?;
+        let name = if settings.leader.is_empty() { settings.leader_address.clone() } else { settings.leader.clone() };
+        return Ok((name, address));
+    }
+
+    let mdns = mdns.ok_or_else(|| AppError::Network("mDNS is unavailable, set a leader address".to_string()))?;
+    let browsing = mdns.browse(SERVICE_TYPE).map_err(mdns_error)?;
+    let suffix = format!(".{}", SERVICE_TYPE);
+    let found = timeout(DISCOVERY_TIMEOUT, async {
+        while let Ok(event) = browsing.recv_async().await {
+            let ServiceEvent::ServiceResolved(info) = event else { continue };
+            let name = info.get_fullname().trim_end_matches(&suffix).to_string();
+            if !settings.leader.is_empty() && name != settings.leader {
+                continue;
+            }
+            if let Some(ip) = info.get_addresses().iter().next() {
+                return Some((name, SocketAddr::new(*ip, info.get_port())));
+            }
+        }
+        None
+    })
+    .await;
+    let _ = mdns.stop_browse(SERVICE_TYPE);
+
+    found.ok().flatten().ok_or_else(|| AppError::NotFound("No leader found on the network".to_string()))
+}
+
+/// Follows one leader until it goes away, returning why
+async fn follow_leader(shared: &Shared, mdns: Option<&ServiceDaemon>) -> Result<()> {
+    let (name, address) = find_leader(&shared.settings, mdns).await?;
+    shared.update(|s| {
+        s.leader = Some(name.clone());
+        s.address = Some(address.to_string());
+    });
+
+    let stream = timeout(CONNECT_TIMEOUT, TcpStream::connect(address))
+        .await
+        .map_err(|_| AppError::Timeout(format!("Timed out connecting to {}", address)))??;
+    let mut lines = BufReader::new(stream).lines();
+
+    loop {
+        let line = timeout(LEADER_TIMEOUT, lines.next_line())
+            .await
+            .map_err(|_| AppError::Timeout(format!("No word from the leader in {}s", LEADER_TIMEOUT.as_secs())))??
+            .ok_or_else(|| AppError::Network("The leader closed the connection".to_string()))?;
+        let message: Message = serde_json::from_str(&line)?;
+        shared.update(|s| {
+            s.state = MultiRoomState::Connected;
+            s.last_seen = Some(Local::now());
+            s.error = None;
+        });
+
+        match message {
+            Message::Hello { name, .. } => shared.update(|s| s.leader = Some(name)),
+            Message::Schedule { schedule } if shared.follows_schedule() => {
+                if let Err(e) = shared.host.apply_schedule(&schedule) {
+                    eprintln!("Error applying the leader's schedule: {}", e);
+                }
+                *shared.schedule.lock() = Some(*schedule);
+            }
+            Message::Adhan { prayer, with_sound, .. } if with_sound && !shared.follows_schedule() => {
+                if let Err(e) = shared.host.play_adhan(&prayer) {
+                    eprintln!("Error playing the leader's adhan: {}", e);
+                }
+            }
+            Message::Schedule { .. } | Message::Adhan { .. } | Message::Ping => {}
+        }
+    }
+}
+
+async fn follow(shared: Arc<Shared>, mut stopped: oneshot::Receiver<()>) {
+    shared.update(|s| s.state = MultiRoomState::Searching);
+    let mdns = if shared.settings.leader_address.is_empty() {
+        match ServiceDaemon::new() {
+            Ok(mdns) => Some(mdns),
+            Err(e) => {
+                eprintln!("mDNS unavailable: {}", e);
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    loop {
+        tokio::select! {
+            _ = &mut stopped => break,
+            result = follow_leader(&shared, mdns.as_ref()) => {
+                let error = result.err().map(|e| e.to_string());
+                // Cleared first, restoring recalculates unless a leader schedule is set
+                let had_schedule = shared.schedule.lock().take().is_some();
+                if had_schedule {
+                    if let Err(e) = shared.host.restore_schedule() {
+                        eprintln!("Error restoring the local schedule: {}", e);
+                    }
+                }
+                shared.update(|s| {
+                    // Until a leader has been seen we are still looking
+                    s.state = if s.leader.is_some() { MultiRoomState::Unreachable } else { MultiRoomState::Searching };
+                    s.error = error;
+                });
+            }
+        }
+        tokio::select! {
+            _ = &mut stopped => break,
+            _ = sleep(RETRY_DELAY) => {}
+        }
+    }
+
+    if let Some(mdns) = mdns {
+        let _ = mdns.shutdown();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    /// Records what a follower was asked to do
+    #[derive(Default)]
+    struct Room {
+        played: Mutex<Vec<String>>,
+        schedules: Mutex<Vec<f64>>,
+        restores: Mutex<usize>,
+        states: Mutex<Vec<MultiRoomState>>,
+    }
+
+    impl MultiRoomHost for Room {
+        fn schedule(&self) -> Result<Schedule> {
+            Ok(Schedule {
+                latitude: 21.4225,
+                longitude: 39.8262,
+                timezone: "Asia/Riyadh".to_string(),
+                calculation: CalculationSettings::default(),
+                custom_times: None,
+                jumuah_time: None,
+            })
+        }
+
+        fn apply_schedule(&self, schedule: &Schedule) -> Result<()> {
+            schedule.calculator()?;
+            self.schedules.lock().push(schedule.latitude);
+            Ok(())
+        }
+
+        fn restore_schedule(&self) -> Result<()> {
+            *self.restores.lock() += 1;
+            Ok(())
+        }
+
+        fn play_adhan(&self, prayer: &str) -> Result<()> {
+            self.played.lock().push(prayer.to_string());
+            Ok(())
+        }
+
+        fn status_changed(&self, status: &MultiRoomStatus) {
+            let mut states = self.states.lock();
+            if states.last() != Some(&status.state) {
+                states.push(status.state);
+            }
+        }
+    }
+
+    fn free_port() -> u16 {
+        std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port()
+    }
+
+    fn settings(role: MultiRoomRole, port: u16, follow: FollowMode) -> MultiRoomSettings {
+        MultiRoomSettings {
+            role,
+            room_name: "Main hall".to_string(),
+            port,
+            follow,
+            leader: String::new(),
+            leader_address: format!("127.0.0.1:{}", port),
+        }
+    }
+
+    async fn wait_for(what: &str, done: impl Fn() -> bool) {
+        timeout(Duration::from_secs(20), async {
+            while !done() {
+                sleep(Duration::from_millis(50)).await;
+            }
+        })
+        .await
+        .unwrap_or_else(|_| panic!("timed out waiting for {}", what));
+    }
+
+    fn time() -> DateTime<Local> {
+        Local.with_ymd_and_hms(2025, 3, 3, 15, 42, 0).earliest().unwrap()
+    }
+
+    #[test]
+    fn messages_round_trip_as_lines() {
+        let line = Message::Adhan { prayer: "Asr".to_string(), time: time(), with_sound: true }.line();
+        assert!(line.ends_with('\n'));
+        assert!(line.starts_with(r#"{"type":"adhan","prayer":"Asr","#));
+        assert!(line.contains(r#""withSound":true"#));
+        match serde_json::from_str(line.trim()).unwrap() {
+            Message::Adhan { prayer, with_sound, .. } => assert!(prayer == "Asr" && with_sound),
+            other => panic!("{:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn followers_play_with_the_leader_and_notice_when_it_goes() {
+        let port = free_port();
+        let leader_events = EventBus::new();
+        let leader = MultiRoom::new();
+        leader.apply(Arc::new(Room::default()), &leader_events, &settings(MultiRoomRole::Leader, port, FollowMode::Adhan));
+        wait_for("the leader to listen", || leader.status().state == MultiRoomState::Leading).await;
+
+        let rooms: Vec<_> = (0..2).map(|_| Arc::new(Room::default())).collect();
+        let followers: Vec<_> = rooms
+            .iter()
+            .map(|room| {
+                let follower = MultiRoom::new();
+                follower.apply(room.clone(), &EventBus::new(), &settings(MultiRoomRole::Follower, port, FollowMode::Adhan));
+                follower
+            })
+            .collect();
+
+        wait_for("followers to connect", || followers.iter().all(|f| f.leader_plays_adhan())).await;
+        wait_for("the leader to count them", || leader.status().followers == 2).await;
+        assert_eq!(followers[0].status().leader.as_deref(), Some("Main hall"));
+
+        leader_events.publish(PrayerEvent::Adhan { prayer: "Asr".to_string(), time: time(), with_sound: true });
+        // A muted leader stays quiet everywhere
+        leader_events.publish(PrayerEvent::Adhan { prayer: "Maghrib".to_string(), time: time(), with_sound: false });
+        wait_for("followers to play", || rooms.iter().all(|r| !r.played.lock().is_empty())).await;
+        sleep(Duration::from_millis(200)).await;
+        for room in &rooms {
+            assert_eq!(*room.played.lock(), ["Asr"]);
+            // Adhan followers keep their own schedule
+            assert!(room.schedules.lock().is_empty());
+        }
+
+        leader.apply(Arc::new(Room::default()), &leader_events, &MultiRoomSettings::default());
+        wait_for("followers to notice", || {
+            followers.iter().all(|f| f.status().state == MultiRoomState::Unreachable)
+        })
+        .await;
+        assert!(followers.iter().all(|f| !f.leader_plays_adhan()));
+        assert_eq!(
+            *rooms[0].states.lock(),
+            [MultiRoomState::Searching, MultiRoomState::Connected, MultiRoomState::Unreachable]
+        );
+    }
+
+    #[tokio::test]
+    async fn schedule_followers_take_over_the_leaders_times() {
+        let port = free_port();
+        let events = EventBus::new();
+        let leader = MultiRoom::new();
+        leader.apply(Arc::new(Room::default()), &events, &settings(MultiRoomRole::Leader, port, FollowMode::Adhan));
+        wait_for("the leader to listen", || leader.status().state == MultiRoomState::Leading).await;
+
+        let room = Arc::new(Room::default());
+        let follower = MultiRoom::new();
+        follower.apply(room.clone(), &EventBus::new(), &settings(MultiRoomRole::Follower, port, FollowMode::Schedule));
+
+        wait_for("the schedule", || follower.leader_schedule().is_some()).await;
+        assert_eq!(room.schedules.lock()[0], 21.4225);
+        assert_eq!(follower.leader_schedule().unwrap().timezone, "Asia/Riyadh");
+        // Playing stays with our own checker
+        assert!(!follower.leader_plays_adhan());
+        events.publish(PrayerEvent::Adhan { prayer: "Asr".to_string(), time: time(), with_sound: true });
+        sleep(Duration::from_millis(200)).await;
+        assert!(room.played.lock().is_empty());
+    }
+
+    #[tokio::test]
+    async fn schedule_followers_fall_back_while_the_leader_is_gone() {
+        let port = free_port();
+        let events = EventBus::new();
+        let leader_settings = settings(MultiRoomRole::Leader, port, FollowMode::Adhan);
+        let leader = MultiRoom::new();
+        leader.apply(Arc::new(Room::default()), &events, &leader_settings);
+        wait_for("the leader to listen", || leader.status().state == MultiRoomState::Leading).await;
+
+        let room = Arc::new(Room::default());
+        let follower = MultiRoom::new();
+        follower.apply(room.clone(), &EventBus::new(), &settings(MultiRoomRole::Follower, port, FollowMode::Schedule));
+        wait_for("the schedule", || follower.leader_schedule().is_some()).await;
+
+        leader.apply(Arc::new(Room::default()), &events, &MultiRoomSettings::default());
+        wait_for("the follower to notice", || follower.status().state == MultiRoomState::Unreachable).await;
+        assert!(follower.leader_schedule().is_none());
+        assert_eq!(*room.restores.lock(), 1);
+
+        // Back again, the greeting brings the schedule along
+        leader.apply(Arc::new(Room::default()), &events, &leader_settings);
+        wait_for("the follower to reconnect", || follower.leader_schedule().is_some()).await;
+        assert_eq!(follower.status().state, MultiRoomState::Connected);
+        assert_eq!(room.schedules.lock().len(), 2);
+        assert_eq!(*room.restores.lock(), 1);
+    }
+}
@@ -0,0 +1,156 @@
+use chrono::{DateTime, Datelike, Duration, Local, NaiveTime, Timelike};
+
+use crate::models::{AppSettings, MuteRequest, MuteStatus, QuietWindow};
+use crate::error::{AppError, Result};
+
+pub fn parse_hhmm(time: &str) -> Result<NaiveTime> {
+    NaiveTime::parse_from_str(time, "%H:%M")
+        .map_err(|e| AppError::InvalidInput(format!("Invalid time '{}': {}", time, e)))
+}
+
+/// Start of tomorrow in local time
+pub fn end_of_today(now: DateTime<Local>) -> Option<DateTime<Local>> {
+    let tomorrow = now.date_naive() + Duration::days(1);
+    tomorrow.and_hms_opt(0, 0, 0)?.and_local_timezone(Local).earliest()
+}
+
+/// When a mute request should end. `next_prayer` is only used by
+/// `UntilNextPrayer`, which keeps that prayer's adhan silent too.
+pub fn mute_until(request: MuteRequest, now: DateTime<Local>, next_prayer: Option<DateTime<Local>>) -> Result<DateTime<Local>> {
+    match request {
+        MuteRequest::ForHours { hours } => {
+            if !hours.is_finite() || hours <= 0.0 || hours > 24.0 * 7.0 {
+                return Err(AppError::InvalidInput(format!("Invalid mute duration: {} hours", hours)));
+            }
+            Ok(now + Duration::seconds((hours * 3600.0) as i64))
+        }
+        MuteRequest::UntilNextPrayer => next_prayer
+            .map(|time| time + Duration::minutes(1))
+            .ok_or(AppError::NotInitialized),
+        MuteRequest::Today => end_of_today(now)
+            .ok_or_else(|| AppError::InvalidDate("Unable to compute midnight".to_string())),
+    }
+}
+
+pub fn in_quiet_window(window: &QuietWindow, now: DateTime<Local>) -> bool {
+    if !window.enabled {
+        return false;
+    }
+    let (Ok(start), Ok(end)) = (parse_hhmm(&window.start), parse_hhmm(&window.end)) else {
+        return false;
+    };
+
+    let time = now.time().with_second(0).unwrap_or(now.time());
+    let today = now.weekday();
+    let yesterday = today.pred();
+
+    if start <= end {
+        window.days.contains(&today) && time >= start && time < end
+    } else {
+        // Overnight window, e.g. 22:00-06:00 belongs to the day it starts on
+        (window.days.contains(&today) && time >= start) || (window.days.contains(&yesterday) && time < end)
+    }
+}
+
+pub fn mute_status(settings: &AppSettings, now: DateTime<Local>) -> MuteStatus {
+    let until = settings.muted_until.filter(|until| *until > now);
+    let quiet_window = settings.quiet_windows
+        .iter()
+        .find(|w| in_quiet_window(w, now))
+        .map(|w| w.name.clone().unwrap_or_else(|| format!("{}-{}", w.start, w.end)));
+
+    MuteStatus {
+        muted: until.is_some() || quiet_window.is_some(),
+        until,
+        quiet_window,
+    }
+}
+
+/// Checked by the prayer checker right before it plays the adhan
+pub fn is_adhan_muted(settings: &AppSettings, now: DateTime<Local>) -> bool {
+    mute_status(settings, now).muted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Weekday};
+
+    /// March 2025, the 3rd being a Monday
+    fn at(day: u32, hour: u32, minute: u32) -> DateTime<Local> {
+        Local.with_ymd_and_hms(2025, 3, day, hour, minute, 0).earliest().unwrap()
+    }
+
+    fn window(days: &[Weekday], start: &str, end: &str) -> QuietWindow {
+        QuietWindow {
+            enabled: true,
+            name: None,
+            days: days.to_vec(),
+            start: start.to_string(),
+            end: end.to_string(),
+        }
+    }
+
+    #[test]
+    fn overnight_window_belongs_to_the_day_it_starts() {
+        let window = window(&[Weekday::Mon], "22:00", "06:00");
+        assert!(!in_quiet_window(&window, at(3, 21, 59)));
+        assert!(in_quiet_window(&window, at(3, 22, 0)));
+        assert!(in_quiet_window(&window, at(3, 23, 30)));
+        assert!(in_quiet_window(&window, at(4, 5, 59)));
+        assert!(!in_quiet_window(&window, at(4, 6, 0)));
+        // Sunday night isn't in the window, nor Tuesday's
+        assert!(!in_quiet_window(&window, at(3, 5, 0)));
+        assert!(!in_quiet_window(&window, at(4, 23, 0)));
+    }
+
+    #[test]
+    fn daytime_window_keeps_to_its_weekdays() {
+        let weekdays = [Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Thu, Weekday::Fri];
+        let window = window(&weekdays, "09:00", "17:00");
+        assert!(in_quiet_window(&window, at(3, 9, 0)));
+        assert!(in_quiet_window(&window, at(7, 16, 59)));
+        assert!(!in_quiet_window(&window, at(3, 17, 0)));
+        assert!(!in_quiet_window(&window, at(3, 8, 59)));
+        assert!(!in_quiet_window(&window, at(8, 12, 0)));
+
+        assert!(!in_quiet_window(&QuietWindow { enabled: false, ..window.clone() }, at(3, 12, 0)));
+        assert!(!in_quiet_window(&QuietWindow { start: "9am".to_string(), ..window }, at(3, 12, 0)));
+    }
+
+    #[test]
+    fn mute_until_each_mode() {
+        let now = at(3, 14, 20);
+        assert_eq!(mute_until(MuteRequest::ForHours { hours: 1.5 }, now, None).unwrap(), at(3, 15, 50));
+        for hours in [0.0, -1.0, f64::NAN, 24.0 * 7.0 + 1.0] {
+            assert!(matches!(mute_until(MuteRequest::ForHours { hours }, now, None), Err(AppError::InvalidInput(_))));
+        }
+
+        // The next prayer's own adhan stays silent
+        assert_eq!(mute_until(MuteRequest::UntilNextPrayer, now, Some(at(3, 15, 42))).unwrap(), at(3, 15, 43));
+        assert!(matches!(mute_until(MuteRequest::UntilNextPrayer, now, None), Err(AppError::NotInitialized)));
+
+        assert_eq!(mute_until(MuteRequest::Today, now, None).unwrap(), at(4, 0, 0));
+    }
+
+    #[test]
+    fn status_reports_the_mute_and_the_window() {
+        let now = at(3, 23, 0);
+        let mut settings = AppSettings { muted_until: Some(at(3, 22, 0)), ..AppSettings::default() };
+        assert!(!is_adhan_muted(&settings, now));
+
+        settings.muted_until = Some(at(4, 0, 0));
+        let status = mute_status(&settings, now);
+        assert!(status.muted);
+        assert_eq!(status.until, Some(at(4, 0, 0)));
+        assert_eq!(status.quiet_window, None);
+
+        settings.muted_until = None;
+        settings.quiet_windows = vec![window(&[Weekday::Mon], "22:00", "06:00")];
+        assert_eq!(mute_status(&settings, now).quiet_window.as_deref(), Some("22:00-06:00"));
+        settings.quiet_windows[0].name = Some("Night".to_string());
+        let status = mute_status(&settings, now);
+        assert!(status.muted);
+        assert_eq!(status.quiet_window.as_deref(), Some("Night"));
+    }
+}
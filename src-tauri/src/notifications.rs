@@ -0,0 +1,227 @@
+use chrono::{DateTime, Datelike, Local};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_notification::NotificationExt;
+use tokio::time::{sleep, Duration};
+
+use crate::{i18n, AppState};
+use crate::models::AppSettings;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum NotificationAction {
+    StopAdhan,
+    Snooze,
+    MarkPrayed,
+}
+
+impl NotificationAction {
+    pub fn id(self) -> &'static str {
+        match self {
+            NotificationAction::StopAdhan => "stop-adhan",
+            NotificationAction::Snooze => "snooze",
+            NotificationAction::MarkPrayed => "mark-prayed",
+        }
+    }
+
+    pub fn from_id(id: &str) -> Option<Self> {
+        match id {
+            "stop-adhan" => Some(NotificationAction::StopAdhan),
+            "snooze" => Some(NotificationAction::Snooze),
+            "mark-prayed" => Some(NotificationAction::MarkPrayed),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActionButton {
+    pub action: NotificationAction,
+    pub label: String,
+}
+
+/// A rendered notification. It is also emitted to the webview as
+/// `prayer-notification` so the app can offer the same buttons in-window
+/// where the OS notification has none.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PrayerNotification {
+    pub prayer: String,
+    pub title: String,
+    pub body: String,
+    pub actions: Vec<ActionButton>,
+}
+
+fn format_time(time: DateTime<Local>) -> String {
+    time.format("%H:%M").to_string()
+}
+
+fn buttons(actions: &[NotificationAction]) -> Vec<ActionButton> {
+    actions
+        .iter()
+        .map(|&action| ActionButton {
+            action,
+            label: i18n::t(match action {
+                NotificationAction::StopAdhan => "action.stopAdhan",
+                NotificationAction::Snooze => "action.snooze",
+                NotificationAction::MarkPrayed => "action.markPrayed",
+            }),
+        })
+        .collect()
+}
+
+/// Localized prayer name, calling Friday's Dhuhr Jumuah
+fn display_name(prayer: &str, time: DateTime<Local>) -> String {
+    if prayer == "Dhuhr" && time.weekday() == chrono::Weekday::Fri {
+        i18n::prayer_name("Jumuah")
+    } else {
+        i18n::prayer_name(prayer)
+    }
+}
+
+pub fn prayer_notification(
+    settings: &AppSettings,
+    prayer: &str,
+    time: DateTime<Local>,
+    next: Option<(String, DateTime<Local>)>,
+    with_sound: bool,
+) -> PrayerNotification {
+    let mut body = i18n::t_with("notification.prayerBody", &[
+        ("prayer", display_name(prayer, time)),
+        ("time", format_time(time)),
+    ]);
+
+    let friday = time.weekday() == chrono::Weekday::Fri;
+    if let Some(offset) = settings.iqamah.as_ref().and_then(|i| i.offset_for(prayer, friday)) {
+        let iqamah = time + chrono::Duration::minutes(offset as i64);
+        body.push('\n');
+        body.push_str(&i18n::t_with("notification.iqamahLine", &[("iqamah", format_time(iqamah))]));
+    }
+
+    if let Some((next_name, next_time)) = next {
+        body.push('\n');
+        body.push_str(&i18n::t_with("notification.nextLine", &[
+            ("next", display_name(&next_name, next_time)),
+            ("next_time", format_time(next_time)),
+        ]));
+    }
+
+    let mut actions = Vec::new();
+    if with_sound {
+        actions.push(NotificationAction::StopAdhan);
+    }
+    actions.push(NotificationAction::MarkPrayed);
+
+    PrayerNotification {
+        prayer: prayer.to_string(),
+        title: i18n::t_with("notification.prayerTitle", &[]),
+        body,
+        actions: buttons(&actions),
+    }
+}
+
+pub fn reminder_notification(prayer: &str, minutes: u32) -> PrayerNotification {
+    PrayerNotification {
+        prayer: prayer.to_string(),
+        title: i18n::t_with("notification.reminderTitle", &[]),
+        body: i18n::t_with("notification.reminderBody", &[
+            ("prayer", display_name(prayer, Local::now())),
+            ("minutes", minutes.to_string()),
+        ]),
+        actions: buttons(&[NotificationAction::Snooze]),
+    }
+}
+
+pub fn show(app: &AppHandle, notification: PrayerNotification) {
+    let _ = app.emit("prayer-notification", &notification);
+
+    #[cfg(target_os = "linux")]
+    {
+        show_with_actions(app.clone(), notification);
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        // Desktop action buttons are only available through the freedesktop
+        // spec, elsewhere the in-app banner offers them instead
+        let _ = app.notification()
+            .builder()
+            .title(&notification.title)
+            .body(&notification.body)
+            .show();
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn show_with_actions(app: AppHandle, notification: PrayerNotification) {
+    std::thread::spawn(move || {
+        let mut native = notify_rust::Notification::new();
+        native
+            .appname("Muezzin")
+            .summary(&notification.title)
+            .body(&notification.body);
+        for button in &notification.actions {
+            native.action(button.action.id(), &button.label);
+        }
+
+        match native.show() {
+            Ok(handle) => handle.wait_for_action(|id| {
+                if let Some(action) = NotificationAction::from_id(id) {
+                    handle_action(&app, action, &notification.prayer);
+                }
+            }),
+            Err(e) => {
+                eprintln!("Error showing notification: {}", e);
+                let _ = app.notification()
+                    .builder()
+                    .title(&notification.title)
+                    .body(&notification.body)
+                    .show();
+            }
+        }
+    });
+}
+
+/// Routes a notification button, whether it came from the OS or the webview
+pub fn handle_action(app: &AppHandle, action: NotificationAction, prayer: &str) {
+    let state = app.state::<AppState>();
+
+    match action {
+        NotificationAction::StopAdhan => {
+            state.audio_player.write().stop();
+        }
+        NotificationAction::Snooze => {
+            let settings = match state.storage.get_settings() {
+                Ok(s) => s,
+                Err(e) => {
+                    eprintln!("Error loading settings: {}", e);
+                    return;
+                }
+            };
+            let minutes = settings.snooze_minutes.max(1);
+            let app = app.clone();
+            let prayer = prayer.to_string();
+            tauri::async_runtime::spawn(async move {
+                sleep(Duration::from_secs(minutes as u64 * 60)).await;
+                let next = app.state::<AppState>().calculator.read().get_next_prayer().ok();
+                // Only re-remind while the prayer is still ahead
+                if let Some((next_name, next_time)) = next {
+                    if next_name == prayer {
+                        let minutes_until = ((next_time - Local::now()).num_seconds().max(0) / 60) as u32;
+                        show(&app, reminder_notification(&prayer, minutes_until));
+                    }
+                }
+            });
+        }
+        NotificationAction::MarkPrayed => {
+            match crate::commands::mark_prayed(&state, prayer) {
+                Ok(entry) => {
+                    let _ = app.emit("prayer-marked", &entry);
+                    let _ = crate::tray::update_tray_menu(app);
+                }
+                Err(e) => eprintln!("Error marking prayer: {}", e),
+            }
+        }
+    }
+}
@@ -1,291 +1,193 @@
-use salah::prelude::*;
-use chrono::{DateTime, Local, Timelike, Datelike, NaiveTime};
-use chrono_tz::Tz;
+use chrono::Local;
 use std::sync::Arc;
 use parking_lot::RwLock;
-use tokio::time::{sleep, Duration};
-use tauri::{AppHandle, Manager};
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_notification::NotificationExt;
 
+use crate::checker::{self, CheckerEvent, Notifier, Scheduler, SettingsSource, SystemClock};
 use crate::storage::AppStorage;
-use crate::models::{PrayerTimes, CalculationSettings, CustomTimes, JumuahTime};
+use crate::models::{AppSettings, MultiRoomStatus};
 use crate::audio::AudioPlayer;
-use crate::error::{AppError, Result};
-
-pub struct PrayerCalculator {
-    coordinates: Option<Coordinates>,
-    config: Option<Configuration>,
-    timezone: Tz,
-    custom_times: Option<CustomTimes>,
-    jumuah_time: Option<JumuahTime>,
+use crate::error::Result;
+use crate::events::{EventBus, PrayerEvent};
+use crate::hooks::Hooks;
+use crate::media_pause::MediaPause;
+use crate::multi_room::{MultiRoom, MultiRoomHost, Schedule};
+use crate::{commands, AppState};
+use crate::i18n;
+use crate::kiosk;
+use crate::notifications;
+use crate::quran_progress;
+use crate::tray;
+
+/// Routes checker events to the tray, the webview, OS notifications, the
+/// event bus and user hooks
+struct AppHost {
+    storage: Arc<AppStorage>,
+    events: Arc<EventBus>,
+    hooks: Arc<Hooks>,
+    media: Arc<MediaPause>,
+    multi_room: Arc<MultiRoom>,
+    audio_player: Arc<RwLock<AudioPlayer>>,
+    app_handle: AppHandle,
 }
 
-impl PrayerCalculator {
-    pub fn new() -> Self {
-        Self {
-            coordinates: None,
-            config: None,
-            timezone: chrono_tz::UTC,
-            custom_times: None,
-            jumuah_time: None,
+impl AppHost {
+    fn update_tray_menu(&self) {
+        if let Err(e) = tray::update_tray_menu(&self.app_handle) {
+            eprintln!("Error updating tray menu: {}", e);
         }
     }
+}
 
-    pub fn update_settings(
-        &mut self,
-        lat: f64,
-        lon: f64,
-        calc_settings: &CalculationSettings,
-        timezone: Tz,
-        custom_times: Option<CustomTimes>,
-        jumuah_time: Option<JumuahTime>,
-    ) -> Result<()> {
-        self.coordinates = Some(Coordinates::new(lat, lon));
-        self.timezone = timezone;
-        self.custom_times = custom_times;
-        self.jumuah_time = jumuah_time;
-        
-        // Map calculation method
-        let method = match calc_settings.calc_method.as_str() {
-            "MWL" => Method::MuslimWorldLeague,
-            "Egyptian" => Method::Egyptian,
-            "Karachi" => Method::Karachi,
-            "UAQ" => Method::UmmAlQura,
-            "Dubai" => Method::Dubai,
-            "Qatar" => Method::Qatar,
-            "Kuwait" => Method::Kuwait,
-            "MC" => Method::MoonsightingCommittee,
-            "Singapore" => Method::Singapore,
-            "Turkey" => Method::Turkey,
-            "Tehran" => Method::Tehran,
-            "ISNA" => Method::NorthAmerica,
-            _ => Method::MuslimWorldLeague,
-        };
-
-        let mut config = Configuration::with(method);
-        
-        // Set madhab
-        config.madhab = match calc_settings.madhab.as_str() {
-            "Hanafi" => Madhab::Hanafi,
-            _ => Madhab::Shafi,
-        };
-
-        // Apply adjustments if any
-        if let Some(ref adj) = calc_settings.adjustments {
-            config.fajr_offset = adj.fajr;
-            config.dhuhr_offset = adj.dhuhr;
-            config.asr_offset = adj.asr;
-            config.maghrib_offset = adj.maghrib;
-            config.isha_offset = adj.isha;
+impl SettingsSource for AppHost {
+    fn settings(&self) -> Result<AppSettings> {
+        let mut settings = self.storage.get_settings()?;
+        // A connected multi-room leader starts our adhan instead
+        if self.multi_room.leader_plays_adhan() {
+            settings.adhan_check = false;
         }
-
-        self.config = Some(config);
-        Ok(())
+        Ok(settings)
     }
+}
 
-    pub fn calculate_prayer_times(&self, date: DateTime<Local>) -> Result<PrayerTimes> {
-        let coords = self.coordinates.ok_or(AppError::NotInitialized)?;
-        let config = self.config.as_ref().ok_or(AppError::NotInitialized)?;
-
-        let prayers = salah::PrayerSchedule::new()
-            .on(date.date_naive())
-            .for_location(coords)
-            .with_configuration(config.clone())
-            .calculate()
-            .map_err(|e| AppError::Prayer(e.to_string()))?;
-
-        let mut result = PrayerTimes {
-            fajr: prayers.time(Prayer::Fajr).with_timezone(&self.timezone),
-            sunrise: prayers.time(Prayer::Sunrise).with_timezone(&self.timezone),
-            dhuhr: prayers.time(Prayer::Dhuhr).with_timezone(&self.timezone),
-            asr: prayers.time(Prayer::Asr).with_timezone(&self.timezone),
-            maghrib: prayers.time(Prayer::Maghrib).with_timezone(&self.timezone),
-            isha: prayers.time(Prayer::Isha).with_timezone(&self.timezone),
-        };
-
-        // Apply custom times if enabled and it's today
-        if let Some(ref custom) = self.custom_times {
-            if custom.enabled && date.date_naive() == Local::now().date_naive() {
-                if let Some(ref time_str) = custom.fajr {
-                    if let Ok(time) = NaiveTime::parse_from_str(time_str, "%H:%M") {
-                        result.fajr = date.date_naive().and_time(time).and_local_timezone(self.timezone).unwrap();
-                    }
+impl Notifier for AppHost {
+    fn notify(&self, event: CheckerEvent) {
+        if let Ok(settings) = self.storage.get_settings() {
+            self.hooks.fire(&settings.hooks, &event);
+        }
+        kiosk::refresh(&self.app_handle);
+
+        match event {
+            CheckerEvent::DateChanged => {
+                // Emit event to frontend to refresh prayers
+                let _ = self.app_handle.emit("prayers-updated", ());
+                self.update_tray_menu();
+                self.events.publish(PrayerEvent::DateChanged { date: Local::now().date_naive() });
+            }
+            CheckerEvent::Minute { now, next_prayer, next_time, today } => {
+                if let Err(e) = tray::update_tray_countdown(&self.app_handle, &next_prayer, next_time) {
+                    eprintln!("Error updating tray countdown: {}", e);
                 }
-                if let Some(ref time_str) = custom.dhuhr {
-                    if let Ok(time) = NaiveTime::parse_from_str(time_str, "%H:%M") {
-                        result.dhuhr = date.date_naive().and_time(time).and_local_timezone(self.timezone).unwrap();
-                    }
+
+                // Daily khatm reading reminder
+                let notif_check = self.storage.get_settings().map(|s| s.notif_check).unwrap_or(false);
+                if !notif_check {
+                    return;
                 }
-                if let Some(ref time_str) = custom.asr {
-                    if let Ok(time) = NaiveTime::parse_from_str(time_str, "%H:%M") {
-                        result.asr = date.date_naive().and_time(time).and_local_timezone(self.timezone).unwrap();
+                if let (Ok(progress), Some(times)) = (self.storage.get_quran_progress(), today.as_ref()) {
+                    if let Some(status) = quran_progress::due_khatm_reminder(&progress, times, now) {
+                        let _ = self.app_handle.notification()
+                            .builder()
+                            .title(i18n::t_with("notification.quranTitle", &[]))
+                            .body(i18n::t_with("notification.quranBody", &[
+                                ("from", status.target_from_page.to_string()),
+                                ("to", status.target_to_page.to_string()),
+                            ]))
+                            .show();
                     }
                 }
-                if let Some(ref time_str) = custom.maghrib {
-                    if let Ok(time) = NaiveTime::parse_from_str(time_str, "%H:%M") {
-                        result.maghrib = date.date_naive().and_time(time).and_local_timezone(self.timezone).unwrap();
-                    }
+            }
+            CheckerEvent::Prayer { prayer, time, next, with_sound, notify } => {
+                println!("Prayer time! {}", prayer);
+                if with_sound && self.storage.get_settings().map(|s| s.pause_media).unwrap_or(false) {
+                    self.media.adhan_started(self.audio_player.clone());
                 }
-                if let Some(ref time_str) = custom.isha {
-                    if let Ok(time) = NaiveTime::parse_from_str(time_str, "%H:%M") {
-                        result.isha = date.date_naive().and_time(time).and_local_timezone(self.timezone).unwrap();
+                self.events.publish(PrayerEvent::Adhan { prayer: prayer.clone(), time, with_sound });
+
+                // Show notification
+                if notify {
+                    match self.storage.get_settings() {
+                        Ok(settings) => notifications::show(
+                            &self.app_handle,
+                            notifications::prayer_notification(&settings, &prayer, time, next, with_sound),
+                        ),
+                        Err(e) => eprintln!("Error loading settings: {}", e),
                     }
                 }
-            }
-        }
 
-        // Apply Jumuah time if it's Friday
-        if let Some(ref jumuah) = self.jumuah_time {
-            if jumuah.enabled && date.weekday() == chrono::Weekday::Fri {
-                if let Ok(time) = NaiveTime::parse_from_str(&jumuah.time, "%H:%M") {
-                    result.dhuhr = date.date_naive().and_time(time).and_local_timezone(self.timezone).unwrap();
-                }
+                // Move the highlight to the following prayer
+                self.update_tray_menu();
+            }
+            CheckerEvent::Reminder { prayer, time, minutes } => {
+                notifications::show(
+                    &self.app_handle,
+                    notifications::reminder_notification(&prayer, minutes),
+                );
+                self.events.publish(PrayerEvent::Reminder { prayer, time, minutes });
+            }
+            CheckerEvent::AdhanEnded { prayer } => {
+                self.events.publish(PrayerEvent::AdhanEnd { prayer });
+            }
+            CheckerEvent::Iqamah { prayer, time } => {
+                self.events.publish(PrayerEvent::Iqamah { prayer, time });
             }
         }
-
-        Ok(result)
     }
+}
 
-    pub fn get_next_prayer(&self) -> Result<(String, DateTime<Local>)> {
-        let now = Local::now();
-        let times = self.calculate_prayer_times(now)?;
-
-        if now < times.fajr {
-            Ok(("Fajr".to_string(), times.fajr))
-        } else if now < times.dhuhr {
-            Ok(("Dhuhr".to_string(), times.dhuhr))
-        } else if now < times.asr {
-            Ok(("Asr".to_string(), times.asr))
-        } else if now < times.maghrib {
-            Ok(("Maghrib".to_string(), times.maghrib))
-        } else if now < times.isha {
-            Ok(("Isha".to_string(), times.isha))
-        } else {
-            // Next is Fajr tomorrow
-            let tomorrow = now + chrono::Duration::days(1);
-            let tomorrow_times = self.calculate_prayer_times(tomorrow)?;
-            Ok(("Fajr".to_string(), tomorrow_times.fajr))
-        }
-    }
+pub async fn start_prayer_checker(app_handle: AppHandle) {
+    let scheduler = {
+        let state = app_handle.state::<AppState>();
+        let host = Arc::new(AppHost {
+            storage: state.storage.clone(),
+            events: state.events.clone(),
+            hooks: state.hooks.clone(),
+            media: state.media.clone(),
+            multi_room: state.multi_room.clone(),
+            audio_player: state.audio_player.clone(),
+            app_handle: app_handle.clone(),
+        });
+        Scheduler::new(Arc::new(SystemClock), state.calculator.clone(), host.clone(), host, state.audio_player.clone())
+    };
+    checker::run(scheduler).await;
+}
 
-    pub fn get_qibla_direction(&self) -> Result<f64> {
-        let coords = self.coordinates.ok_or(AppError::NotInitialized)?;
-        Ok(coords.qibla_direction())
+/// Shows times from a schedule the calculator just switched to
+fn schedule_changed(app: &AppHandle) {
+    let _ = app.emit("prayers-updated", ());
+    if let Err(e) = tray::update_tray_menu(app) {
+        eprintln!("Error updating tray menu: {}", e);
     }
 }
 
-pub async fn start_prayer_checker(
-    storage: Arc<AppStorage>,
-    calculator: Arc<RwLock<PrayerCalculator>>,
-    audio_player: Arc<RwLock<AudioPlayer>>,
-    app_handle: AppHandle,
-) {
-    let mut check_interval = tokio::time::interval(Duration::from_secs(1));
-    let mut last_check_minute = 999u32;
-    let mut last_date = Local::now().date_naive();
-
-    loop {
-        check_interval.tick().await;
-        
-        let now = Local::now();
-        let current_minute = now.hour() * 60 + now.minute();
-        let current_date = now.date_naive();
-
-        // Recalculate prayers at midnight
-        if current_date != last_date {
-            last_date = current_date;
-            // Emit event to frontend to refresh prayers
-            let _ = app_handle.emit("prayers-updated", ());
-        }
-
-        // Only check once per minute
-        if current_minute == last_check_minute {
-            continue;
-        }
-        last_check_minute = current_minute;
-
-        // Load settings
-        let settings = match storage.get_settings() {
-            Ok(s) => s,
-            Err(e) => {
-                eprintln!("Error loading settings: {}", e);
-                continue;
-            }
-        };
-
-        if !settings.adhan_check && !settings.notif_check {
-            continue;
-        }
+/// The app's side of multi-room: shares and takes over the stored schedule
+/// and plays the leader's adhan through the usual path
+impl MultiRoomHost for AppHandle {
+    fn schedule(&self) -> Result<Schedule> {
+        let state = self.state::<AppState>();
+        let (latitude, longitude, timezone) = state.storage.get_location()?;
+        Ok(Schedule {
+            latitude,
+            longitude,
+            timezone,
+            calculation: state.storage.get_settings()?.calculation,
+            custom_times: state.storage.get_custom_times()?,
+            jumuah_time: state.storage.get_jumuah_time()?,
+        })
+    }
 
-        // Get next prayer
-        let calc = calculator.read();
-        let (prayer_name, prayer_time) = match calc.get_next_prayer() {
-            Ok(p) => p,
-            Err(e) => {
-                eprintln!("Error getting next prayer: {}", e);
-                continue;
-            }
-        };
-        drop(calc);
+    fn apply_schedule(&self, schedule: &Schedule) -> Result<()> {
+        *self.state::<AppState>().calculator.write() = schedule.calculator()?;
+        schedule_changed(self);
+        Ok(())
+    }
 
-        // Check if it's prayer time (within 1 second)
-        let diff = (prayer_time.timestamp() - now.timestamp()).abs();
-        if diff <= 1 {
-            println!("Prayer time! {}", prayer_name);
-            
-            // Play adhan
-            if settings.adhan_check {
-                let mut player = audio_player.write();
-                let adhan_path = if prayer_name == "Fajr" && settings.adhan_fajr_path.is_some() {
-                    settings.adhan_fajr_path.as_ref().unwrap()
-                } else {
-                    &settings.adhan_path
-                };
-                
-                if let Err(e) = player.play_adhan(adhan_path) {
-                    eprintln!("Error playing adhan: {}", e);
-                }
-            }
+    fn restore_schedule(&self) -> Result<()> {
+        commands::reload_calculator(&self.state::<AppState>())?;
+        schedule_changed(self);
+        Ok(())
+    }
 
-            // Show notification
-            if settings.notif_check {
-                let _ = app_handle.notification()
-                    .builder()
-                    .title("Prayer Time")
-                    .body(format!("It's time for {} prayer", prayer_name))
-                    .show();
-            }
+    fn play_adhan(&self, prayer: &str) -> Result<()> {
+        let state = self.state::<AppState>();
+        if checker::adhan_wanted(&state.storage.get_settings()?, prayer, Local::now()) {
+            commands::play_configured_adhan(&state, Some(prayer))?;
         }
+        Ok(())
+    }
 
-        // Check for reminders
-        if let Some(ref reminder) = settings.reminder_times {
-            if reminder.enabled {
-                let minutes_until = ((prayer_time.timestamp() - now.timestamp()) / 60) as u32;
-                
-                let reminder_minutes = match prayer_name.as_str() {
-                    "Fajr" => reminder.fajr,
-                    "Dhuhr" => {
-                        if now.weekday() == chrono::Weekday::Fri {
-                            reminder.jumuah
-                        } else {
-                            reminder.dhuhr
-                        }
-                    },
-                    "Asr" => reminder.asr,
-                    "Maghrib" => reminder.maghrib,
-                    "Isha" => reminder.isha,
-                    _ => 0,
-                };
-
-                if reminder_minutes > 0 && minutes_until == reminder_minutes {
-                    if settings.notif_check {
-                        let _ = app_handle.notification()
-                            .builder()
-                            .title("Prayer Reminder")
-                            .body(format!("Adhan in {} minutes", reminder_minutes))
-                            .show();
-                    }
-                }
-            }
-        }
+    fn status_changed(&self, status: &MultiRoomStatus) {
+        let _ = self.emit("multi-room-status", status);
     }
 }
@@ -0,0 +1,486 @@
+//! The bundled Quran text: surahs, verse ranges, and search over the Arabic
+//! and the English translation.
+//!
+//! Verses can't be looked up by mushaf page. The bundle only has the page
+//! span of each surah, so the page a verse carries is an estimate (see
+//! `QuranText::assign_pages`), good enough to track reading progress but not
+//! to show a page. That needs the Madani verse-to-page table bundled first.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+
+use crate::audio::resolve_resource_path;
+use crate::error::{AppError, Result};
+use crate::quran_progress::juz_start_page;
+
+const QURAN_TEXT_PATH: &str = "ressources/quran/quran_en.json";
+const CHAPTERS_PATH: &str = "ressources/quran/chapters.json";
+
+pub const SURAH_COUNT: u32 = 114;
+pub const PAGE_COUNT: u32 = 604;
+
+/// First verse of each juz. In the Madani mushaf every juz starts at the top
+/// of its page, see `quran_progress::juz_start_page`.
+const JUZ_STARTS: [(u32, u32); 30] = [
+    (1, 1), (2, 142), (2, 253), (3, 93), (4, 24), (4, 148), (5, 82), (6, 111), (7, 88), (8, 41),
+    (9, 93), (11, 6), (12, 53), (15, 1), (17, 1), (18, 75), (21, 1), (23, 1), (25, 21), (27, 56),
+    (29, 46), (33, 31), (36, 28), (39, 32), (41, 47), (46, 1), (51, 31), (58, 1), (67, 1), (78, 1),
+];
+
+#[derive(Debug, Deserialize)]
+struct RawSurah {
+    id: u32,
+    verses: Vec<RawVerse>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawVerse {
+    id: u32,
+    text: String,
+    translation: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChaptersFile {
+    chapters: Vec<RawChapter>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawChapter {
+    id: u32,
+    revelation_place: String,
+    revelation_order: u32,
+    bismillah_pre: bool,
+    name_simple: String,
+    name_arabic: String,
+    verses_count: u32,
+    pages: [u32; 2],
+    translated_name: TranslatedName,
+}
+
+#[derive(Debug, Deserialize)]
+struct TranslatedName {
+    name: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChapterInfo {
+    pub id: u32,
+    pub name_simple: String,
+    pub name_arabic: String,
+    pub name_translated: String,
+    pub revelation_place: String,
+    pub revelation_order: u32,
+    pub bismillah_pre: bool,
+    pub verses_count: u32,
+    pub pages: [u32; 2],
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Verse {
+    pub chapter: u32,
+    pub verse: u32,
+    pub text: String,
+    pub translation: String,
+    /// Estimated mushaf page, only used to track reading progress. See
+    /// `QuranText::assign_pages`.
+    pub page: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Surah {
+    pub info: ChapterInfo,
+    pub verses: Vec<Verse>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SearchScope {
+    Arabic,
+    Translation,
+    All,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MatchedIn {
+    Arabic,
+    Translation,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchResult {
+    pub verse: Verse,
+    pub matched_in: MatchedIn,
+    pub score: f64,
+}
+
+/// Search-ready copy of a verse, normalized once at load time
+struct IndexedVerse {
+    arabic_words: Vec<String>,
+    arabic: String,
+    translation_words: Vec<String>,
+    translation: String,
+}
+
+pub struct QuranText {
+    chapters: Vec<ChapterInfo>,
+    /// All 6236 verses in mushaf order
+    verses: Vec<Verse>,
+    index: Vec<IndexedVerse>,
+    /// Offset of each surah's first verse in `verses`
+    chapter_offsets: Vec<usize>,
+}
+
+impl QuranText {
+    pub fn load() -> Result<Self> {
+        let surahs: Vec<RawSurah> =
+            serde_json::from_str(&fs::read_to_string(resolve_resource_path(QURAN_TEXT_PATH))?)?;
+        let chapters: ChaptersFile =
+            serde_json::from_str(&fs::read_to_string(resolve_resource_path(CHAPTERS_PATH))?)?;
+
+        Self::from_raw(surahs, chapters.chapters)
+    }
+
+    fn from_raw(mut surahs: Vec<RawSurah>, mut raw_chapters: Vec<RawChapter>) -> Result<Self> {
+        surahs.sort_by_key(|s| s.id);
+        raw_chapters.sort_by_key(|c| c.id);
+
+        if surahs.len() != SURAH_COUNT as usize || raw_chapters.len() != SURAH_COUNT as usize {
+            return Err(AppError::InvalidData("Bundled Quran data is incomplete".to_string()));
+        }
+
+        let chapters: Vec<ChapterInfo> = raw_chapters
+            .into_iter()
+            .map(|c| ChapterInfo {
+                id: c.id,
+                name_simple: c.name_simple,
+                name_arabic: c.name_arabic,
+                name_translated: c.translated_name.name,
+                revelation_place: c.revelation_place,
+                revelation_order: c.revelation_order,
+                bismillah_pre: c.bismillah_pre,
+                verses_count: c.verses_count,
+                pages: c.pages,
+            })
+            .collect();
+
+        let mut verses = Vec::new();
+        let mut chapter_offsets = Vec::with_capacity(surahs.len());
+        for (surah, info) in surahs.into_iter().zip(&chapters) {
+            if surah.verses.len() != info.verses_count as usize {
+                return Err(AppError::InvalidData(format!("Verse count mismatch in surah {}", surah.id)));
+            }
+
+            chapter_offsets.push(verses.len());
+            let mut chapter_verses: Vec<Verse> = surah.verses
+                .into_iter()
+                .map(|v| Verse {
+                    chapter: surah.id,
+                    verse: v.id,
+                    text: v.text,
+                    translation: v.translation,
+                    page: info.pages[0],
+                })
+                .collect();
+
+            // Split the surah where a juz starts, its page is known exactly
+            let juz_cuts = JUZ_STARTS
+                .iter()
+                .zip(1..)
+                .filter(|((chapter, verse), _)| *chapter == surah.id && *verse > 1)
+                .map(|((_, verse), juz)| (*verse as usize - 1, juz_start_page(juz)));
+            let mut from = (0, info.pages[0]);
+            for (until, page) in juz_cuts.chain([(chapter_verses.len(), info.pages[1] + 1)]) {
+                Self::assign_pages(&mut chapter_verses[from.0..until], [from.1, page - 1]);
+                from = (until, page);
+            }
+            verses.extend(chapter_verses);
+        }
+
+        let index = verses
+            .iter()
+            .map(|v| {
+                let arabic = normalize_arabic(&v.text);
+                let translation = normalize_latin(&v.translation);
+                IndexedVerse {
+                    arabic_words: arabic.split_whitespace().map(String::from).collect(),
+                    arabic,
+                    translation_words: translation.split_whitespace().map(String::from).collect(),
+                    translation,
+                }
+            })
+            .collect();
+
+        Ok(Self { chapters, verses, index, chapter_offsets })
+    }
+
+    /// The bundled data has no verse to page table, only the page span of
+    /// each surah, and juz starts are known. Between those, verses are spread
+    /// over the pages in proportion to their length. Exact at surah and juz
+    /// starts, elsewhere it may be a page off, which is why verses can't be
+    /// looked up by page.
+    fn assign_pages(verses: &mut [Verse], pages: [u32; 2]) {
+        let span = pages[1].saturating_sub(pages[0]) + 1;
+        let total: usize = verses.iter().map(|v| v.text.chars().count()).sum();
+
+        let mut before = 0;
+        for verse in verses.iter_mut() {
+            let offset = (before * span as usize).checked_div(total).unwrap_or(0) as u32;
+            verse.page = pages[0] + offset.min(span - 1);
+            before += verse.text.chars().count();
+        }
+    }
+
+    pub fn chapters(&self) -> &[ChapterInfo] {
+        &self.chapters
+    }
+
+    fn chapter_verses(&self, chapter: u32) -> Result<&[Verse]> {
+        if chapter == 0 || chapter > SURAH_COUNT {
+            return Err(AppError::InvalidInput(format!("Invalid surah number: {}", chapter)));
+        }
+        let start = self.chapter_offsets[chapter as usize - 1];
+        let count = self.chapters[chapter as usize - 1].verses_count as usize;
+        Ok(&self.verses[start..start + count])
+    }
+
+    pub fn surah(&self, chapter: u32) -> Result<Surah> {
+        let verses = self.chapter_verses(chapter)?.to_vec();
+        Ok(Surah {
+            info: self.chapters[chapter as usize - 1].clone(),
+            verses,
+        })
+    }
+
+    pub fn verse(&self, chapter: u32, verse: u32) -> Result<&Verse> {
+        self.chapter_verses(chapter)?
+            .get((verse as usize).wrapping_sub(1))
+            .ok_or_else(|| AppError::InvalidInput(format!("Invalid verse: {}:{}", chapter, verse)))
+    }
+
+    pub fn verse_range(&self, chapter: u32, start: u32, end: u32) -> Result<Vec<Verse>> {
+        let verses = self.chapter_verses(chapter)?;
+        if start == 0 || start > end || end as usize > verses.len() {
+            return Err(AppError::InvalidInput(format!(
+                "Invalid verse range {}-{} for surah {} ({} verses)",
+                start, end, chapter, verses.len()
+            )));
+        }
+        Ok(verses[start as usize - 1..end as usize].to_vec())
+    }
+
+    /// Ranked full-text search. Arabic queries ignore diacritics and the
+    /// different alif/hamza spellings; translation queries are case-insensitive.
+    pub fn search(&self, query: &str, scope: SearchScope, limit: usize) -> Vec<SearchResult> {
+        let arabic_query = normalize_arabic(query);
+        let latin_query = normalize_latin(query);
+        let search_arabic = scope != SearchScope::Translation && !arabic_query.is_empty();
+        let search_translation = scope != SearchScope::Arabic && !latin_query.is_empty();
+
+        let mut results: Vec<SearchResult> = self.verses
+            .iter()
+            .zip(&self.index)
+            .filter_map(|(verse, indexed)| {
+                let arabic = search_arabic
+                    .then(|| score(&arabic_query, &indexed.arabic, &indexed.arabic_words))
+                    .flatten()
+                    .map(|s| (s, MatchedIn::Arabic));
+                let translation = search_translation
+                    .then(|| score(&latin_query, &indexed.translation, &indexed.translation_words))
+                    .flatten()
+                    .map(|s| (s, MatchedIn::Translation));
+
+                let (score, matched_in) = match (arabic, translation) {
+                    (Some(a), Some(t)) => if a.0 >= t.0 { a } else { t },
+                    (a, t) => a.or(t)?,
+                };
+                Some(SearchResult { verse: verse.clone(), matched_in, score })
+            })
+            .collect();
+
+        // Highest score first, mushaf order for ties
+        results.sort_by(|a, b| {
+            b.score
+                .total_cmp(&a.score)
+                .then(a.verse.chapter.cmp(&b.verse.chapter))
+                .then(a.verse.verse.cmp(&b.verse.verse))
+        });
+        results.truncate(limit);
+        results
+    }
+}
+
+/// Scores a normalized query against a normalized text. Every query term must
+/// appear; whole-word hits, the exact phrase and short verses rank higher.
+fn score(query: &str, text: &str, words: &[String]) -> Option<f64> {
+    let terms: Vec<&str> = query.split_whitespace().collect();
+    if terms.is_empty() || words.is_empty() {
+        return None;
+    }
+
+    let mut total = 0.0;
+    for term in &terms {
+        let exact = words.iter().filter(|w| w.as_str() == *term).count();
+        let partial = words.iter().filter(|w| w.contains(term)).count() - exact;
+        if exact + partial == 0 {
+            return None;
+        }
+        total += exact as f64 * 2.0 + partial as f64;
+    }
+
+    if terms.len() > 1 && text.contains(query) {
+        total += 5.0 * terms.len() as f64;
+    }
+
+    // Favour verses where the match makes up more of the text
+    Some(total / (words.len() as f64).sqrt())
+}
+
+/// Strips tashkeel and Quranic annotation marks and folds the letter variants
+/// that readers don't type (alif wasla/madda/hamza, hamza carriers, ta marbuta).
+pub fn normalize_arabic(text: &str) -> String {
+    let mut normalized = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            // Harakat, tanween, shadda, sukun, small high/low marks
+            '\u{064B}'..='\u{065F}' | '\u{0670}' | '\u{06D6}'..='\u{06ED}' | '\u{0640}' => {}
+            'أ' | 'إ' | 'آ' | 'ٱ' => normalized.push('ا'),
+            'ؤ' => normalized.push('و'),
+            'ئ' | 'ى' => normalized.push('ي'),
+            'ة' => normalized.push('ه'),
+            'ء' => {}
+            c if c.is_whitespace() => normalized.push(' '),
+            c if is_arabic_letter(c) => normalized.push(c),
+            _ => {}
+        }
+    }
+    normalized.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn is_arabic_letter(c: char) -> bool {
+    ('\u{0621}'..='\u{064A}').contains(&c) || ('\u{0671}'..='\u{06D3}').contains(&c)
+}
+
+/// Lowercases and drops punctuation so "[All] praise" matches "all praise"
+pub fn normalize_latin(text: &str) -> String {
+    text.chars()
+        .map(|c| if c.is_alphanumeric() || c == '\'' { c } else { ' ' })
+        .flat_map(char::to_lowercase)
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::OnceLock;
+
+    /// The bundled text, loaded once for all tests
+    fn quran() -> &'static QuranText {
+        static QURAN: OnceLock<QuranText> = OnceLock::new();
+        QURAN.get_or_init(|| QuranText::load().unwrap())
+    }
+
+    fn keys(results: &[SearchResult]) -> Vec<(u32, u32)> {
+        results.iter().map(|r| (r.verse.chapter, r.verse.verse)).collect()
+    }
+
+    #[test]
+    fn arabic_normalization_strips_marks_and_folds_letters() {
+        assert_eq!(normalize_arabic("بِسۡمِ ٱللَّهِ ٱلرَّحۡمَٰنِ"), "بسم الله الرحمن");
+        assert_eq!(normalize_arabic("أَإِآ"), "ااا");
+        assert_eq!(normalize_arabic("مُؤۡمِن شَيۡءٍ"), "مومن شي");
+        assert_eq!(normalize_arabic("رَحۡمَةً هُدًى"), "رحمه هدي");
+        // Tatweel, annotation marks, Latin and extra spaces go
+        assert_eq!(normalize_arabic("  اللّـــه ۚ abc  قال "), "الله قال");
+    }
+
+    #[test]
+    fn latin_normalization_ignores_case_and_brackets() {
+        assert_eq!(normalize_latin("[All] praise is [due] to Allah, Lord"), "all praise is due to allah lord");
+        assert_eq!(normalize_latin("Allah's   Mercy!"), "allah's mercy");
+    }
+
+    #[test]
+    fn loads_every_surah_and_verse() {
+        let quran = quran();
+        assert_eq!(quran.chapters().len(), SURAH_COUNT as usize);
+        assert_eq!(quran.chapters().iter().map(|c| c.verses_count).sum::<u32>(), 6236);
+        assert_eq!(quran.surah(114).unwrap().verses.len(), 6);
+        assert!(quran.surah(0).is_err() && quran.surah(115).is_err());
+    }
+
+    #[test]
+    fn verse_and_range_lookups() {
+        let quran = quran();
+        let verse = quran.verse(2, 255).unwrap();
+        assert_eq!((verse.chapter, verse.verse), (2, 255));
+        assert!(quran.verse(1, 1).unwrap().text.starts_with("بِسۡمِ"));
+        assert!(quran.verse(2, 287).is_err());
+        assert!(quran.verse(1, 0).is_err());
+        assert!(quran.verse(0, 1).is_err());
+
+        let range = quran.verse_range(1, 2, 4).unwrap();
+        assert_eq!(range.iter().map(|v| v.verse).collect::<Vec<_>>(), [2, 3, 4]);
+        assert_eq!(quran.verse_range(1, 7, 7).unwrap().len(), 1);
+        for (start, end) in [(0, 1), (4, 2), (1, 8)] {
+            assert!(matches!(quran.verse_range(1, start, end), Err(AppError::InvalidInput(_))), "{}-{}", start, end);
+        }
+    }
+
+    #[test]
+    fn pages_are_exact_at_surah_and_juz_starts() {
+        let quran = quran();
+        for (juz, (chapter, verse)) in (1..).zip(JUZ_STARTS) {
+            assert_eq!(quran.verse(chapter, verse).unwrap().page, juz_start_page(juz), "juz {}", juz);
+        }
+        assert_eq!(quran.verse(2, 141).unwrap().page, 21);
+        for info in quran.chapters() {
+            let surah = quran.surah(info.id).unwrap();
+            assert_eq!(surah.verses[0].page, info.pages[0], "surah {}", info.id);
+            assert!(surah.verses.iter().all(|v| v.page <= info.pages[1]), "surah {}", info.id);
+        }
+        assert_eq!(quran.verse(114, 6).unwrap().page, PAGE_COUNT);
+        assert!(quran.verses.windows(2).all(|pair| pair[0].page <= pair[1].page));
+    }
+
+    #[test]
+    fn arabic_search_ignores_diacritics() {
+        let quran = quran();
+        let plain = quran.search("بسم الله الرحمن الرحيم", SearchScope::Arabic, 10);
+        assert_eq!(keys(&plain), [(1, 1), (27, 30)]);
+        assert!(plain.iter().all(|r| r.matched_in == MatchedIn::Arabic));
+
+        let voweled = quran.search("بِسْمِ اللَّهِ الرَّحْمَنِ الرَّحِيمِ", SearchScope::Arabic, 10);
+        assert_eq!(keys(&voweled), keys(&plain));
+
+        // Typed with a hamza on the alif, written without one in the text
+        let hamza = quran.search("إن الله غفور رحيم", SearchScope::Arabic, 3);
+        assert_eq!(keys(&hamza), [(2, 192), (5, 98), (8, 69)]);
+    }
+
+    #[test]
+    fn translation_search_ranks_phrases_and_short_verses_first() {
+        let quran = quran();
+        let results = quran.search("[All] praise", SearchScope::Translation, 3);
+        assert_eq!(results[0].matched_in, MatchedIn::Translation);
+        assert_eq!(keys(&results), [(1, 2), (45, 36), (18, 1)]);
+
+        // Equal scores keep mushaf order
+        let results = quran.search("MERCIFUL", SearchScope::All, 3);
+        assert_eq!(keys(&results), [(1, 3), (1, 1), (41, 2)]);
+        assert_eq!(results[1].score, results[2].score);
+        assert!(results.windows(2).all(|pair| pair[0].score >= pair[1].score));
+
+        assert_eq!(quran.search("merciful", SearchScope::All, 500).len(), 172);
+        assert!(quran.search("merciful", SearchScope::Arabic, 10).is_empty());
+        assert!(quran.search("   ", SearchScope::All, 10).is_empty());
+    }
+}
@@ -0,0 +1,460 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use parking_lot::{Mutex, RwLock};
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::time::{sleep, Duration};
+
+use crate::audio::{resolve_resource_path, AudioPlayer};
+use crate::models::{QuranPlaybackRequest, QuranPlaybackState, RepeatMode};
+use crate::quran::QuranText;
+use crate::error::{AppError, Result};
+
+const RECITATIONS_PATH: &str = "ressources/quran/recitations.json";
+const AUDIO_API: &str = "https://api.quran.com/api/v4/recitations";
+const AUDIO_HOST: &str = "https://verses.quran.com/";
+const MANIFEST_FILE: &str = "manifest.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Recitation {
+    pub id: u32,
+    pub reciter_name: String,
+    pub style: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RecitationsFile {
+    recitations: Vec<Recitation>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AudioFilesResponse {
+    audio_files: Vec<AudioFile>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct AudioFile {
+    verse_key: String,
+    url: String,
+}
+
+/// Size and SHA-256 of a cached verse, checked every time the file is reused
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CacheEntry {
+    size: u64,
+    sha256: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct VerseEvent {
+    reciter_id: u32,
+    chapter: u32,
+    verse: u32,
+    /// Position in the playback sequence, repeats included
+    index: usize,
+    total: Option<usize>,
+}
+
+pub struct QuranAudioService {
+    cache_dir: PathBuf,
+    client: reqwest::Client,
+    recitations: Vec<Recitation>,
+    /// Verses per surah from the bundled `chapters.json`
+    verse_counts: Vec<u32>,
+    manifest: Mutex<HashMap<String, CacheEntry>>,
+    /// Verse URLs per (reciter, chapter), fetched once per session
+    audio_files: Mutex<HashMap<(u32, u32), Vec<AudioFile>>>,
+    state: RwLock<QuranPlaybackState>,
+    /// Bumped whenever playback is started or stopped so stale runners exit
+    generation: AtomicU64,
+}
+
+impl QuranAudioService {
+    pub fn new(app: &AppHandle, quran: &QuranText) -> Result<Self> {
+        let cache_dir = app.path().app_data_dir()?.join("quran_audio");
+        fs::create_dir_all(&cache_dir)?;
+
+        let recitations: RecitationsFile =
+            serde_json::from_str(&fs::read_to_string(resolve_resource_path(RECITATIONS_PATH))?)?;
+
+        let manifest = fs::read_to_string(cache_dir.join(MANIFEST_FILE))
+            .ok()
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default();
+
+        Ok(Self {
+            cache_dir,
+            client: reqwest::Client::new(),
+            recitations: recitations.recitations,
+            verse_counts: quran.chapters().iter().map(|c| c.verses_count).collect(),
+            manifest: Mutex::new(manifest),
+            audio_files: Mutex::new(HashMap::new()),
+            state: RwLock::new(QuranPlaybackState::default()),
+            generation: AtomicU64::new(0),
+        })
+    }
+
+    pub fn recitations(&self) -> &[Recitation] {
+        &self.recitations
+    }
+
+    pub fn state(&self) -> QuranPlaybackState {
+        self.state.read().clone()
+    }
+
+    fn recitation(&self, reciter_id: u32) -> Result<&Recitation> {
+        self.recitations
+            .iter()
+            .find(|r| r.id == reciter_id)
+            .ok_or_else(|| AppError::NotFound(format!("Unknown reciter: {}", reciter_id)))
+    }
+
+    async fn chapter_audio_files(&self, reciter_id: u32, chapter: u32) -> Result<Vec<AudioFile>> {
+        if let Some(files) = self.audio_files.lock().get(&(reciter_id, chapter)) {
+            return Ok(files.clone());
+        }
+
+        let url = format!("{}/{}/by_chapter/{}?per_page=300", AUDIO_API, reciter_id, chapter);
+        let response = self.client
+            .get(&url)
+            .header("User-Agent", "Muezzin")
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(AppError::Http(format!("Failed to get recitation audio list: {}", response.status())));
+        }
+
+        let files = response.json::<AudioFilesResponse>().await?.audio_files;
+        self.audio_files.lock().insert((reciter_id, chapter), files.clone());
+        Ok(files)
+    }
+
+    /// Verses in a surah. Comes from the bundled text rather than the audio
+    /// list, so a downloaded surah plays offline and a partial cache can't
+    /// cut it short.
+    pub fn verse_count(&self, reciter_id: u32, chapter: u32) -> Result<u32> {
+        self.recitation(reciter_id)?;
+        (chapter as usize)
+            .checked_sub(1)
+            .and_then(|i| self.verse_counts.get(i))
+            .copied()
+            .ok_or_else(|| AppError::InvalidInput(format!("Invalid surah number: {}", chapter)))
+    }
+
+    fn cache_key(reciter_id: u32, chapter: u32, verse: u32) -> String {
+        format!("{}/{:03}{:03}.mp3", reciter_id, chapter, verse)
+    }
+
+    /// Returns the cached file if it is present and intact
+    fn cached_verse(&self, key: &str) -> Option<PathBuf> {
+        let entry = self.manifest.lock().get(key).cloned()?;
+        let path = self.cache_dir.join(key);
+
+        match fs::read(&path) {
+            Ok(bytes) if bytes.len() as u64 == entry.size && sha256_hex(&bytes) == entry.sha256 => Some(path),
+            _ => {
+                eprintln!("Cached Quran audio '{}' is missing or corrupt, downloading again", key);
+                let _ = fs::remove_file(&path);
+                self.manifest.lock().remove(key);
+                None
+            }
+        }
+    }
+
+    /// Downloads a verse into the cache unless an intact copy is already there
+    pub async fn ensure_verse(&self, reciter_id: u32, chapter: u32, verse: u32) -> Result<PathBuf> {
+        let key = Self::cache_key(reciter_id, chapter, verse);
+        if let Some(path) = self.cached_verse(&key) {
+            return Ok(path);
+        }
+
+        let verse_key = format!("{}:{}", chapter, verse);
+        let files = self.chapter_audio_files(reciter_id, chapter).await?;
+        let file = files
+            .iter()
+            .find(|f| f.verse_key == verse_key)
+            .ok_or_else(|| AppError::NotFound(format!("No audio for verse {}", verse_key)))?;
+
+        let response = self.client
+            .get(audio_url(&file.url))
+            .header("User-Agent", "Muezzin")
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(AppError::Http(format!("Failed to download verse {}: {}", verse_key, response.status())));
+        }
+
+        let expected_len = response.content_length();
+        let bytes = response.bytes().await?;
+
+        if bytes.is_empty() || expected_len.is_some_and(|len| len != bytes.len() as u64) {
+            return Err(AppError::Http(format!("Incomplete download for verse {}", verse_key)));
+        }
+
+        // Make sure it actually decodes before we trust it
+        rodio::Decoder::new(std::io::Cursor::new(bytes.clone()))
+            .map_err(|e| AppError::Rodio(format!("Downloaded audio for {} is not playable: {}", verse_key, e)))?;
+
+        let path = self.cache_dir.join(&key);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let tmp_path = path.with_extension("part");
+        fs::write(&tmp_path, &bytes)?;
+        fs::rename(&tmp_path, &path)?;
+
+        self.manifest.lock().insert(key, CacheEntry {
+            size: bytes.len() as u64,
+            sha256: sha256_hex(&bytes),
+        });
+        self.save_manifest()?;
+
+        Ok(path)
+    }
+
+    fn save_manifest(&self) -> Result<()> {
+        let json = serde_json::to_string(&*self.manifest.lock())?;
+        let path = self.cache_dir.join(MANIFEST_FILE);
+        let tmp_path = path.with_extension("tmp");
+        fs::write(&tmp_path, json)?;
+        fs::rename(&tmp_path, &path)?;
+        Ok(())
+    }
+
+    /// Downloads every verse of a surah so it can be played offline
+    pub async fn download_surah(&self, reciter_id: u32, chapter: u32, app_handle: &AppHandle) -> Result<()> {
+        let total = self.verse_count(reciter_id, chapter)?;
+        for verse in 1..=total {
+            self.ensure_verse(reciter_id, chapter, verse).await?;
+            let _ = app_handle.emit("quran-download-progress", serde_json::json!({
+                "reciterId": reciter_id,
+                "chapter": chapter,
+                "downloaded": verse,
+                "total": total,
+            }));
+        }
+        Ok(())
+    }
+
+    pub fn clear_cache(&self) -> Result<()> {
+        let mut manifest = self.manifest.lock();
+        for key in manifest.keys() {
+            let _ = fs::remove_file(self.cache_dir.join(key));
+        }
+        manifest.clear();
+        drop(manifest);
+        self.save_manifest()
+    }
+
+    pub fn stop(&self, audio_player: &RwLock<AudioPlayer>) {
+        self.generation.fetch_add(1, Ordering::SeqCst);
+        *self.state.write() = QuranPlaybackState::default();
+        audio_player.write().stop();
+    }
+}
+
+/// Queues a surah (or part of it) and plays it verse by verse, emitting
+/// `quran-verse-started` / `quran-verse-ended` at each boundary.
+pub async fn play_surah(
+    service: Arc<QuranAudioService>,
+    audio_player: Arc<RwLock<AudioPlayer>>,
+    request: QuranPlaybackRequest,
+    app_handle: AppHandle,
+) -> Result<()> {
+    let verse_count = service.verse_count(request.reciter_id, request.chapter)?;
+    let start = request.start_verse.unwrap_or(1);
+    let end = request.end_verse.unwrap_or(verse_count);
+
+    if start == 0 || start > end || end > verse_count {
+        return Err(AppError::InvalidInput(format!(
+            "Invalid verse range {}-{} for surah {} ({} verses)",
+            start, end, request.chapter, verse_count
+        )));
+    }
+
+    let sequence = build_sequence(start, end, &request.repeat);
+    let infinite = matches!(request.repeat, RepeatMode::Range { times: 0 });
+    let total = if infinite { None } else { Some(sequence.len()) };
+
+    // Take over from whatever was playing before
+    let generation = service.generation.fetch_add(1, Ordering::SeqCst) + 1;
+    *service.state.write() = QuranPlaybackState {
+        active: true,
+        reciter_id: Some(request.reciter_id),
+        chapter: Some(request.chapter),
+        verse: None,
+        index: 0,
+        total,
+        repeat: request.repeat.clone(),
+    };
+
+    tokio::spawn(async move {
+        let mut index = 0;
+        while service.generation.load(Ordering::SeqCst) == generation {
+            let Some(&verse) = sequence.get(if infinite { index % sequence.len() } else { index }) else {
+                break;
+            };
+
+            let path = match service.ensure_verse(request.reciter_id, request.chapter, verse).await {
+                Ok(path) => path,
+                Err(e) => {
+                    eprintln!("Error fetching verse {}:{}: {}", request.chapter, verse, e);
+                    let _ = app_handle.emit("quran-playback-error", e.to_string());
+                    break;
+                }
+            };
+
+            if service.generation.load(Ordering::SeqCst) != generation {
+                return;
+            }
+
+            if let Err(e) = audio_player.write().play_file(&path) {
+                eprintln!("Error playing verse {}:{}: {}", request.chapter, verse, e);
+                let _ = app_handle.emit("quran-playback-error", e.to_string());
+                break;
+            }
+
+            let event = VerseEvent {
+                reciter_id: request.reciter_id,
+                chapter: request.chapter,
+                verse,
+                index,
+                total,
+            };
+            {
+                let mut state = service.state.write();
+                state.verse = Some(verse);
+                state.index = index;
+            }
+            let _ = app_handle.emit("quran-verse-started", &event);
+
+            // Warm the cache for the next verse while this one plays
+            let next = if infinite { sequence.get((index + 1) % sequence.len()) } else { sequence.get(index + 1) };
+            if let Some(&next) = next {
+                if next != verse {
+                    let _ = service.ensure_verse(request.reciter_id, request.chapter, next).await;
+                }
+            }
+
+            while service.generation.load(Ordering::SeqCst) == generation && audio_player.read().is_playing() {
+                sleep(Duration::from_millis(200)).await;
+            }
+
+            if service.generation.load(Ordering::SeqCst) != generation {
+                return;
+            }
+            let _ = app_handle.emit("quran-verse-ended", &event);
+            index += 1;
+        }
+
+        if service.generation.load(Ordering::SeqCst) == generation {
+            *service.state.write() = QuranPlaybackState::default();
+            let _ = app_handle.emit("quran-playback-finished", ());
+        }
+    });
+
+    Ok(())
+}
+
+/// Expands a verse range into the exact order verses are played in
+pub fn build_sequence(start: u32, end: u32, repeat: &RepeatMode) -> Vec<u32> {
+    match *repeat {
+        RepeatMode::None => (start..=end).collect(),
+        RepeatMode::Verse { times } => (start..=end)
+            .flat_map(|verse| std::iter::repeat_n(verse, times.max(1) as usize))
+            .collect(),
+        // A zero count loops forever; the runner wraps around the single pass
+        RepeatMode::Range { times } => (0..times.max(1)).flat_map(|_| start..=end).collect(),
+        // Hifz style: repeat each new verse, then recite everything learnt so far
+        RepeatMode::Memorization { verse_times, review_times } => {
+            let mut sequence = Vec::new();
+            for verse in start..=end {
+                sequence.extend(std::iter::repeat_n(verse, verse_times.max(1) as usize));
+                if verse > start {
+                    for _ in 0..review_times {
+                        sequence.extend(start..=verse);
+                    }
+                }
+            }
+            sequence
+        }
+    }
+}
+
+fn audio_url(url: &str) -> String {
+    if url.starts_with("http") {
+        url.to_string()
+    } else if let Some(rest) = url.strip_prefix("//") {
+        format!("https://{}", rest)
+    } else {
+        format!("{}{}", AUDIO_HOST, url)
+    }
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_range_plays_once() {
+        assert_eq!(build_sequence(3, 6, &RepeatMode::None), [3, 4, 5, 6]);
+        assert_eq!(build_sequence(7, 7, &RepeatMode::None), [7]);
+    }
+
+    #[test]
+    fn verse_repeat_plays_each_verse_in_turn() {
+        assert_eq!(build_sequence(1, 3, &RepeatMode::Verse { times: 2 }), [1, 1, 2, 2, 3, 3]);
+        // Zero still plays each verse once
+        assert_eq!(build_sequence(1, 3, &RepeatMode::Verse { times: 0 }), [1, 2, 3]);
+    }
+
+    #[test]
+    fn range_repeat_loops_the_whole_range() {
+        assert_eq!(build_sequence(5, 7, &RepeatMode::Range { times: 3 }), [5, 6, 7, 5, 6, 7, 5, 6, 7]);
+        // Forever is one pass the runner wraps around
+        assert_eq!(build_sequence(5, 7, &RepeatMode::Range { times: 0 }), [5, 6, 7]);
+    }
+
+    #[test]
+    fn memorization_reviews_what_was_learnt() {
+        let sequence = build_sequence(1, 3, &RepeatMode::Memorization { verse_times: 2, review_times: 1 });
+        assert_eq!(sequence, [1, 1, 2, 2, 1, 2, 3, 3, 1, 2, 3]);
+
+        let sequence = build_sequence(10, 11, &RepeatMode::Memorization { verse_times: 1, review_times: 2 });
+        assert_eq!(sequence, [10, 11, 10, 11, 10, 11]);
+
+        let sequence = build_sequence(1, 3, &RepeatMode::Memorization { verse_times: 3, review_times: 0 });
+        assert_eq!(sequence, [1, 1, 1, 2, 2, 2, 3, 3, 3]);
+    }
+
+    #[test]
+    fn audio_urls_are_made_absolute() {
+        assert_eq!(audio_url("Alafasy/mp3/001001.mp3"), "https://verses.quran.com/Alafasy/mp3/001001.mp3");
+        assert_eq!(audio_url("//mirrors.quranicaudio.com/x.mp3"), "https://mirrors.quranicaudio.com/x.mp3");
+        assert_eq!(audio_url("https://example.com/x.mp3"), "https://example.com/x.mp3");
+    }
+
+    #[test]
+    fn verse_events_use_camel_case() {
+        let event = VerseEvent { reciter_id: 7, chapter: 1, verse: 2, index: 3, total: None };
+        let value = serde_json::to_value(&event).unwrap();
+        assert_eq!(value, serde_json::json!({
+            "reciterId": 7,
+            "chapter": 1,
+            "verse": 2,
+            "index": 3,
+            "total": null,
+        }));
+    }
+}
@@ -0,0 +1,592 @@
+//! Downloadable / importable translation and tafsir packs.
+//!
+//! A pack is a single JSON file:
+//!
+//! ```json
+//! {
+//!   "format": 1,
+//!   "kind": "translation",
+//!   "slug": "en-sahih-international",
+//!   "resourceId": 20,
+//!   "name": "Saheeh International",
+//!   "author": "Saheeh International",
+//!   "language": "en",
+//!   "direction": "ltr",
+//!   "verses": { "1:1": "In the name of Allah, ...", "1:2": "..." }
+//! }
+//! ```
+//!
+//! - `kind` is `translation` or `tafsir`.
+//! - `slug` names the pack; it must be lowercase ASCII letters, digits and `-`.
+//! - `resourceId` is the quran.com id, optional for hand-made packs.
+//! - `language` is an ISO 639-1 code from `languages.json`, `direction` is
+//!   `ltr` or `rtl` and defaults to the language's direction.
+//! - `verses` is keyed by `chapter:verse`. Packs may be partial (tafsir often
+//!   covers verse groups); missing verses are simply absent.
+//!
+//! Installed packs live in `<app data>/quran_packs/<slug>.json` next to an
+//! `index.json` holding their metadata, so listing packs never loads the text.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use parking_lot::Mutex;
+use tauri::{AppHandle, Manager};
+
+use crate::audio::resolve_resource_path;
+use crate::quran::{QuranText, Verse};
+use crate::error::{AppError, Result};
+
+const TRANSLATIONS_PATH: &str = "ressources/quran/translations.json";
+const LANGUAGES_PATH: &str = "ressources/quran/languages.json";
+const QURAN_API: &str = "https://api.quran.com/api/v4/quran";
+const RESOURCES_API: &str = "https://api.quran.com/api/v4/resources";
+const INDEX_FILE: &str = "index.json";
+const PACK_FORMAT: u32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PackKind {
+    Translation,
+    Tafsir,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PackInfo {
+    pub kind: PackKind,
+    pub slug: String,
+    pub resource_id: Option<u32>,
+    pub name: String,
+    pub author: Option<String>,
+    pub language: String,
+    pub direction: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Pack {
+    pub format: u32,
+    #[serde(flatten)]
+    pub info: PackInfo,
+    pub verses: HashMap<String, String>,
+}
+
+/// Raw pack as written by hand, before defaults are filled in
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PackFile {
+    format: u32,
+    kind: PackKind,
+    slug: String,
+    resource_id: Option<u32>,
+    name: String,
+    author: Option<String>,
+    language: String,
+    direction: Option<String>,
+    verses: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Language {
+    pub name: String,
+    /// `languages.json` comes from quran.com in snake_case
+    #[serde(alias = "iso_code")]
+    pub iso_code: String,
+    #[serde(alias = "native_name")]
+    pub native_name: String,
+    pub direction: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct LanguagesFile {
+    languages: Vec<Language>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct RawTranslation {
+    id: u32,
+    name: String,
+    author_name: String,
+    slug: Option<String>,
+    language_name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct TranslationsFile {
+    translations: Vec<RawTranslation>,
+}
+
+/// quran.com's tafsir list, shaped like `translations.json`
+#[derive(Debug, Deserialize)]
+struct TafsirsFile {
+    tafsirs: Vec<RawTranslation>,
+}
+
+/// A translation known from `translations.json`, installed or not
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CatalogEntry {
+    pub resource_id: u32,
+    pub slug: String,
+    pub name: String,
+    pub author: String,
+    pub language: Option<Language>,
+    pub installed: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LanguageGroup {
+    pub language: Language,
+    pub translations: Vec<CatalogEntry>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PackText {
+    pub slug: String,
+    pub kind: PackKind,
+    pub name: String,
+    pub language: String,
+    pub direction: String,
+    pub text: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ParallelVerse {
+    pub verse: Verse,
+    pub packs: Vec<PackText>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiResourceResponse {
+    #[serde(alias = "tafsirs")]
+    translations: Vec<ApiVerseText>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiVerseText {
+    verse_key: String,
+    text: String,
+}
+
+pub struct QuranPacks {
+    dir: PathBuf,
+    client: reqwest::Client,
+    languages: Vec<Language>,
+    catalog: Vec<RawTranslation>,
+    /// Verses per surah, to check pack keys against
+    verse_counts: Vec<u32>,
+    installed: Mutex<Vec<PackInfo>>,
+    /// Packs read from disk so far, loaded on first use
+    loaded: Mutex<HashMap<String, Arc<Pack>>>,
+}
+
+impl QuranPacks {
+    pub fn new(app: &AppHandle, quran: &QuranText) -> Result<Self> {
+        Self::open(app.path().app_data_dir()?.join("quran_packs"), quran)
+    }
+
+    fn open(dir: PathBuf, quran: &QuranText) -> Result<Self> {
+        fs::create_dir_all(&dir)?;
+
+        let languages: LanguagesFile =
+            serde_json::from_str(&fs::read_to_string(resolve_resource_path(LANGUAGES_PATH))?)?;
+        let catalog: TranslationsFile =
+            serde_json::from_str(&fs::read_to_string(resolve_resource_path(TRANSLATIONS_PATH))?)?;
+
+        let installed = match fs::read_to_string(dir.join(INDEX_FILE)) {
+            Ok(json) => serde_json::from_str(&json)?,
+            Err(_) => Vec::new(),
+        };
+
+        Ok(Self {
+            dir,
+            client: reqwest::Client::new(),
+            languages: languages.languages,
+            catalog: catalog.translations,
+            verse_counts: quran.chapters().iter().map(|c| c.verses_count).collect(),
+            installed: Mutex::new(installed),
+            loaded: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Whether `key` names a verse of the Quran, e.g. `2:286` but not `1:8`
+    fn is_verse(&self, key: &str) -> bool {
+        parse_verse_key(key).is_some_and(|(chapter, verse)| verse <= self.verse_counts[chapter as usize - 1])
+    }
+
+    fn language_by_name(&self, name: &str) -> Option<&Language> {
+        self.languages.iter().find(|l| l.name.eq_ignore_ascii_case(name))
+    }
+
+    fn language_by_code(&self, code: &str) -> Option<&Language> {
+        self.languages.iter().find(|l| l.iso_code.eq_ignore_ascii_case(code))
+    }
+
+    pub fn installed(&self) -> Vec<PackInfo> {
+        self.installed.lock().clone()
+    }
+
+    /// Every translation in `translations.json`, grouped by language
+    pub fn catalog(&self) -> Vec<LanguageGroup> {
+        let installed = self.installed.lock();
+        let mut groups: Vec<LanguageGroup> = Vec::new();
+
+        for raw in &self.catalog {
+            let language = self.language_by_name(&raw.language_name).cloned();
+            let slug = raw.slug.clone().unwrap_or_else(|| format!("translation-{}", raw.id));
+            let entry = CatalogEntry {
+                resource_id: raw.id,
+                installed: installed.iter().any(|p| p.resource_id == Some(raw.id) && p.kind == PackKind::Translation),
+                slug,
+                name: raw.name.clone(),
+                author: raw.author_name.clone(),
+                language: language.clone(),
+            };
+
+            let language = language.unwrap_or_else(|| Language {
+                name: raw.language_name.clone(),
+                iso_code: String::new(),
+                native_name: raw.language_name.clone(),
+                direction: "ltr".to_string(),
+            });
+
+            match groups.iter_mut().find(|g| g.language.name.eq_ignore_ascii_case(&language.name)) {
+                Some(group) => group.translations.push(entry),
+                None => groups.push(LanguageGroup { language, translations: vec![entry] }),
+            }
+        }
+
+        groups.sort_by_key(|g| g.language.name.to_lowercase());
+        groups
+    }
+
+    /// Validates a pack file and installs it, replacing any pack with the same slug
+    pub fn import(&self, path: &Path) -> Result<PackInfo> {
+        let json = fs::read_to_string(path)?;
+        let file: PackFile = serde_json::from_str(&json)
+            .map_err(|e| AppError::InvalidFile(format!("Invalid pack file: {}", e)))?;
+        self.install(file)
+    }
+
+    fn install(&self, file: PackFile) -> Result<PackInfo> {
+        if file.format != PACK_FORMAT {
+            return Err(AppError::InvalidFile(format!("Unsupported pack format: {}", file.format)));
+        }
+        if file.slug.is_empty() || !file.slug.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-') {
+            return Err(AppError::InvalidFile(format!("Invalid pack slug: '{}'", file.slug)));
+        }
+        let language = self.language_by_code(&file.language)
+            .ok_or_else(|| AppError::InvalidFile(format!("Unknown language code: {}", file.language)))?;
+        let direction = file.direction.unwrap_or_else(|| language.direction.clone());
+        if direction != "ltr" && direction != "rtl" {
+            return Err(AppError::InvalidFile(format!("Invalid text direction: {}", direction)));
+        }
+        if file.verses.is_empty() {
+            return Err(AppError::InvalidFile("Pack has no verses".to_string()));
+        }
+        if let Some(key) = file.verses.keys().find(|k| !self.is_verse(k)) {
+            return Err(AppError::InvalidFile(format!("Invalid verse key in pack: '{}'", key)));
+        }
+
+        let pack = Pack {
+            format: PACK_FORMAT,
+            info: PackInfo {
+                kind: file.kind,
+                slug: file.slug,
+                resource_id: file.resource_id,
+                name: file.name,
+                author: file.author,
+                language: language.iso_code.clone(),
+                direction,
+            },
+            verses: file.verses,
+        };
+
+        let path = self.dir.join(format!("{}.json", pack.info.slug));
+        let tmp_path = path.with_extension("tmp");
+        fs::write(&tmp_path, serde_json::to_string(&pack)?)?;
+        fs::rename(&tmp_path, &path)?;
+
+        let info = pack.info.clone();
+        {
+            let mut installed = self.installed.lock();
+            installed.retain(|p| p.slug != info.slug);
+            installed.push(info.clone());
+        }
+        self.loaded.lock().insert(info.slug.clone(), Arc::new(pack));
+        self.save_index()?;
+
+        Ok(info)
+    }
+
+    /// A tafsir's entry in quran.com's catalog, which isn't bundled
+    async fn tafsir_entry(&self, resource_id: u32) -> Result<RawTranslation> {
+        let response = self.client
+            .get(format!("{}/tafsirs", RESOURCES_API))
+            .header("User-Agent", "Muezzin")
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            return Err(AppError::Http(format!("Failed to list tafsirs: {}", response.status())));
+        }
+
+        let catalog: TafsirsFile = response.json().await?;
+        catalog.tafsirs
+            .into_iter()
+            .find(|t| t.id == resource_id)
+            .ok_or_else(|| AppError::NotFound(format!("Unknown tafsir: {}", resource_id)))
+    }
+
+    /// Downloads a translation (by `translations.json` id) or a tafsir from quran.com
+    pub async fn download(&self, kind: PackKind, resource_id: u32) -> Result<PackInfo> {
+        let (endpoint, raw) = match kind {
+            PackKind::Translation => (
+                "translations",
+                self.catalog
+                    .iter()
+                    .find(|t| t.id == resource_id)
+                    .cloned()
+                    .ok_or_else(|| AppError::NotFound(format!("Unknown translation: {}", resource_id)))?,
+            ),
+            PackKind::Tafsir => ("tafsirs", self.tafsir_entry(resource_id).await?),
+        };
+        let url = format!("{}/{}/{}?fields=verse_key", QURAN_API, endpoint, resource_id);
+        let response = self.client
+            .get(&url)
+            .header("User-Agent", "Muezzin")
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(AppError::Http(format!("Failed to download pack: {}", response.status())));
+        }
+
+        let data: ApiResourceResponse = response.json().await?;
+        let verses = data.translations
+            .into_iter()
+            .map(|v| (v.verse_key, strip_html(&v.text)))
+            .collect();
+
+        let prefix = match kind {
+            PackKind::Translation => "translation",
+            PackKind::Tafsir => "tafsir",
+        };
+        // The direction follows the language, e.g. rtl for an Arabic tafsir
+        let language = self
+            .language_by_name(&raw.language_name)
+            .map_or_else(|| "en".to_string(), |l| l.iso_code.clone());
+
+        self.install(PackFile {
+            format: PACK_FORMAT,
+            kind,
+            slug: raw.slug.unwrap_or_else(|| format!("{}-{}", prefix, raw.id)),
+            resource_id: Some(resource_id),
+            name: raw.name,
+            author: Some(raw.author_name),
+            language,
+            direction: None,
+            verses,
+        })
+    }
+
+    pub fn remove(&self, slug: &str) -> Result<()> {
+        let removed = {
+            let mut installed = self.installed.lock();
+            let before = installed.len();
+            installed.retain(|p| p.slug != slug);
+            before != installed.len()
+        };
+        if !removed {
+            return Err(AppError::NotFound(format!("Pack not installed: {}", slug)));
+        }
+
+        self.loaded.lock().remove(slug);
+        let _ = fs::remove_file(self.dir.join(format!("{}.json", slug)));
+        self.save_index()
+    }
+
+    fn save_index(&self) -> Result<()> {
+        let json = serde_json::to_string_pretty(&*self.installed.lock())?;
+        let path = self.dir.join(INDEX_FILE);
+        let tmp_path = path.with_extension("tmp");
+        fs::write(&tmp_path, json)?;
+        fs::rename(&tmp_path, &path)?;
+        Ok(())
+    }
+
+    fn pack(&self, slug: &str) -> Result<Arc<Pack>> {
+        if let Some(pack) = self.loaded.lock().get(slug) {
+            return Ok(pack.clone());
+        }
+        if !self.installed.lock().iter().any(|p| p.slug == slug) {
+            return Err(AppError::NotFound(format!("Pack not installed: {}", slug)));
+        }
+
+        let json = fs::read_to_string(self.dir.join(format!("{}.json", slug)))?;
+        let pack: Arc<Pack> = Arc::new(serde_json::from_str(&json)?);
+        self.loaded.lock().insert(slug.to_string(), pack.clone());
+        Ok(pack)
+    }
+
+    /// Verses of a range side by side with the text of each requested pack
+    pub fn parallel(&self, quran: &QuranText, chapter: u32, start: u32, end: u32, slugs: &[String]) -> Result<Vec<ParallelVerse>> {
+        let packs = slugs.iter().map(|slug| self.pack(slug)).collect::<Result<Vec<_>>>()?;
+
+        Ok(quran
+            .verse_range(chapter, start, end)?
+            .into_iter()
+            .map(|verse| {
+                let key = format!("{}:{}", verse.chapter, verse.verse);
+                let packs = packs
+                    .iter()
+                    .map(|pack| PackText {
+                        slug: pack.info.slug.clone(),
+                        kind: pack.info.kind,
+                        name: pack.info.name.clone(),
+                        language: pack.info.language.clone(),
+                        direction: pack.info.direction.clone(),
+                        text: pack.verses.get(&key).cloned(),
+                    })
+                    .collect();
+                ParallelVerse { verse, packs }
+            })
+            .collect())
+    }
+}
+
+fn parse_verse_key(key: &str) -> Option<(u32, u32)> {
+    let (chapter, verse) = key.split_once(':')?;
+    let chapter: u32 = chapter.parse().ok()?;
+    let verse: u32 = verse.parse().ok()?;
+    (1..=crate::quran::SURAH_COUNT).contains(&chapter).then_some(())?;
+    (verse > 0).then_some((chapter, verse))
+}
+
+/// quran.com texts carry footnote markup such as `<sup foot_note=123>1</sup>`
+fn strip_html(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut in_tag = false;
+    let mut in_sup = false;
+    let mut tag = String::new();
+
+    for c in text.chars() {
+        match c {
+            '<' => {
+                in_tag = true;
+                tag.clear();
+            }
+            '>' if in_tag => {
+                in_tag = false;
+                let name = tag.trim_start_matches('/').split_whitespace().next().unwrap_or("").to_lowercase();
+                if name == "sup" {
+                    in_sup = !tag.starts_with('/');
+                }
+            }
+            _ if in_tag => tag.push(c),
+            // Footnote numbers are meaningless without the footnotes
+            _ if in_sup => {}
+            _ => out.push(c),
+        }
+    }
+    out.trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+
+    fn packs(name: &str) -> QuranPacks {
+        let dir = std::env::temp_dir().join(format!("muezzin-packs-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        QuranPacks::open(dir, &QuranText::load().unwrap()).unwrap()
+    }
+
+    fn pack(language: &str, verses: Value) -> Value {
+        json!({
+            "format": 1,
+            "kind": "translation",
+            "slug": "test-pack",
+            "name": "Test",
+            "language": language,
+            "verses": verses,
+        })
+    }
+
+    fn import(packs: &QuranPacks, contents: &str) -> Result<PackInfo> {
+        let path = packs.dir.join("import.json");
+        fs::write(&path, contents).unwrap();
+        packs.import(&path)
+    }
+
+    fn invalid_file(result: Result<PackInfo>) -> String {
+        match result {
+            Err(AppError::InvalidFile(message)) => message,
+            other => panic!("expected an invalid file error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn valid_pack_is_installed_and_indexed() {
+        let packs = packs("valid");
+        let verses = json!({ "1:1": "In the name of Allah", "1:7": "Not of those who went astray" });
+        let info = import(&packs, &pack("ur", verses).to_string()).unwrap();
+        assert_eq!(info.slug, "test-pack");
+        // The direction comes from the language when the pack doesn't say
+        assert_eq!(info.direction, "rtl");
+
+        let reopened = QuranPacks::open(packs.dir.clone(), &QuranText::load().unwrap()).unwrap();
+        assert_eq!(reopened.installed().len(), 1);
+        let pack = reopened.pack("test-pack").unwrap();
+        assert_eq!(pack.verses["1:7"], "Not of those who went astray");
+
+        reopened.remove("test-pack").unwrap();
+        assert!(reopened.pack("test-pack").is_err());
+    }
+
+    #[test]
+    fn bad_json_is_rejected() {
+        let packs = packs("json");
+        assert!(invalid_file(import(&packs, "{ \"format\": 1, ")).starts_with("Invalid pack file"));
+
+        // Well-formed, but missing the verses
+        let mut missing = pack("en", json!({}));
+        missing.as_object_mut().unwrap().remove("verses");
+        assert!(invalid_file(import(&packs, &missing.to_string())).starts_with("Invalid pack file"));
+        assert!(packs.installed().is_empty());
+    }
+
+    #[test]
+    fn verses_outside_the_quran_are_rejected() {
+        let packs = packs("verses");
+        for key in ["1:8", "2:287", "115:1", "2:0", "2"] {
+            let message = invalid_file(import(&packs, &pack("en", json!({ "1:1": "text", key: "text" })).to_string()));
+            assert_eq!(message, format!("Invalid verse key in pack: '{}'", key));
+        }
+        assert!(import(&packs, &pack("en", json!({ "2:286": "text" })).to_string()).is_ok());
+        assert_eq!(invalid_file(import(&packs, &pack("en", json!({})).to_string())), "Pack has no verses");
+    }
+
+    #[test]
+    fn unknown_language_is_rejected() {
+        let packs = packs("language");
+        let message = invalid_file(import(&packs, &pack("xx", json!({ "1:1": "text" })).to_string()));
+        assert_eq!(message, "Unknown language code: xx");
+        assert!(packs.installed().is_empty());
+    }
+
+    #[test]
+    fn strip_html_drops_tags_and_footnote_numbers() {
+        assert_eq!(strip_html("In the name of Allah<sup foot_note=123>1</sup>, the Merciful"), "In the name of Allah, the Merciful");
+        assert_eq!(strip_html("<p>The <i>Lord</i> of the worlds</p>"), "The Lord of the worlds");
+        assert_eq!(strip_html(" <SUP>2</SUP>Guide us "), "Guide us");
+        assert_eq!(strip_html("No markup"), "No markup");
+    }
+}
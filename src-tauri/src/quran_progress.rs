@@ -0,0 +1,415 @@
+use chrono::{DateTime, Local, NaiveDate};
+use std::fs;
+use std::path::Path;
+
+use crate::models::{
+    KhatmPlan, KhatmReminder, KhatmStatus, KhatmUnit, PrayerTimes, QuranBookmark, QuranProgress,
+    ReadingPosition, VerseNote,
+};
+use crate::quran::{QuranText, PAGE_COUNT};
+use crate::error::{AppError, Result};
+
+pub const JUZ_COUNT: u32 = 30;
+
+/// First page of a juz in the standard 604-page Madani mushaf
+pub fn juz_start_page(juz: u32) -> u32 {
+    if juz <= 1 { 1 } else { (juz - 1) * 20 + 2 }
+}
+
+/// Last page of a juz in the standard 604-page Madani mushaf
+pub fn juz_end_page(juz: u32) -> u32 {
+    if juz >= JUZ_COUNT { PAGE_COUNT } else { juz_start_page(juz + 1) - 1 }
+}
+
+pub fn toggle_bookmark(progress: &mut QuranProgress, chapter: u32, verse: u32, label: Option<String>) -> bool {
+    if let Some(pos) = progress.bookmarks.iter().position(|b| b.chapter == chapter && b.verse == verse) {
+        progress.bookmarks.remove(pos);
+        return false;
+    }
+
+    progress.bookmarks.push(QuranBookmark {
+        chapter,
+        verse,
+        label,
+        created_at: Local::now(),
+    });
+    progress.bookmarks.sort_by_key(|b| (b.chapter, b.verse));
+    true
+}
+
+/// Sets the note for a verse, an empty text removes it
+pub fn set_note(progress: &mut QuranProgress, chapter: u32, verse: u32, text: &str) {
+    progress.notes.retain(|n| !(n.chapter == chapter && n.verse == verse));
+
+    let text = text.trim();
+    if !text.is_empty() {
+        progress.notes.push(VerseNote {
+            chapter,
+            verse,
+            text: text.to_string(),
+            updated_at: Local::now(),
+        });
+        progress.notes.sort_by_key(|n| (n.chapter, n.verse));
+    }
+}
+
+/// Records the last read verse and moves an active khatm forward when the
+/// reader has gone past what was already recorded.
+pub fn set_last_read(progress: &mut QuranProgress, quran: &QuranText, chapter: u32, verse: u32) -> Result<()> {
+    let page = quran.verse(chapter, verse)?.page;
+
+    progress.last_read = Some(ReadingPosition {
+        chapter,
+        verse,
+        page,
+        updated_at: Local::now(),
+    });
+
+    if let Some(ref mut plan) = progress.khatm {
+        if plan.completed_at.is_none() && page > plan.pages_read {
+            record_pages(plan, page);
+        }
+    }
+    Ok(())
+}
+
+pub fn record_pages(plan: &mut KhatmPlan, pages_read: u32) {
+    plan.pages_read = pages_read.min(PAGE_COUNT);
+    plan.completed_at = if plan.pages_read >= PAGE_COUNT {
+        plan.completed_at.or_else(|| Some(Local::now()))
+    } else {
+        None
+    };
+}
+
+pub fn new_plan(start_date: NaiveDate, days: u32, unit: KhatmUnit, reminder: Option<KhatmReminder>) -> Result<KhatmPlan> {
+    if days == 0 || days > 3650 {
+        return Err(AppError::InvalidInput(format!("Invalid khatm duration: {} days", days)));
+    }
+    if unit == KhatmUnit::Juz && days > JUZ_COUNT {
+        return Err(AppError::InvalidInput(format!("A juz plan can't be spread over more than {} days", JUZ_COUNT)));
+    }
+    if let Some(ref reminder) = reminder {
+        if !["Fajr", "Dhuhr", "Asr", "Maghrib", "Isha"].contains(&reminder.after_prayer.as_str()) {
+            return Err(AppError::InvalidInput(format!("Unknown prayer: {}", reminder.after_prayer)));
+        }
+    }
+
+    Ok(KhatmPlan {
+        start_date,
+        days,
+        unit,
+        reminder,
+        pages_read: 0,
+        completed_at: None,
+    })
+}
+
+/// Page reached at the end of a given plan day (1-based), following the
+/// plan's unit so juz plans always end a day on a juz boundary.
+fn target_page(plan: &KhatmPlan, day: u32) -> u32 {
+    let day = day.min(plan.days);
+    match plan.unit {
+        KhatmUnit::Pages => (PAGE_COUNT * day).div_ceil(plan.days),
+        KhatmUnit::Juz => juz_end_page((JUZ_COUNT * day).div_ceil(plan.days)),
+    }
+}
+
+pub fn khatm_status(plan: &KhatmPlan, today: NaiveDate) -> KhatmStatus {
+    let elapsed = (today - plan.start_date).num_days().max(0) as u32;
+    let day = (elapsed + 1).min(plan.days);
+
+    let target_from_page = target_page(plan, day - 1) + 1;
+    let target_to_page = target_page(plan, day);
+    let target_juz = (plan.unit == KhatmUnit::Juz).then(|| {
+        ((JUZ_COUNT * (day - 1)).div_ceil(plan.days) + 1, (JUZ_COUNT * day).div_ceil(plan.days))
+    });
+
+    KhatmStatus {
+        day,
+        days: plan.days,
+        unit: plan.unit,
+        target_from_page: target_from_page.min(target_to_page),
+        target_to_page,
+        target_juz,
+        pages_read: plan.pages_read,
+        behind_by: target_page(plan, day - 1).saturating_sub(plan.pages_read),
+        today_done: plan.pages_read >= target_to_page,
+        completed: plan.completed_at.is_some(),
+    }
+}
+
+/// Returns today's status when `now` is the configured minute after the
+/// chosen prayer and today's portion isn't finished yet.
+pub fn due_khatm_reminder(progress: &QuranProgress, times: &PrayerTimes, now: DateTime<Local>) -> Option<KhatmStatus> {
+    let plan = progress.khatm.as_ref()?;
+    let reminder = plan.reminder.as_ref()?;
+    if plan.completed_at.is_some() {
+        return None;
+    }
+
+    let prayer_time = match reminder.after_prayer.as_str() {
+        "Fajr" => times.fajr,
+        "Dhuhr" => times.dhuhr,
+        "Asr" => times.asr,
+        "Maghrib" => times.maghrib,
+        "Isha" => times.isha,
+        _ => return None,
+    };
+
+    if (now - prayer_time).num_minutes() != reminder.delay_minutes as i64 {
+        return None;
+    }
+
+    let status = khatm_status(plan, now.date_naive());
+    (!status.today_done).then_some(status)
+}
+
+pub fn export(progress: &QuranProgress, path: &Path) -> Result<()> {
+    fs::write(path, serde_json::to_string_pretty(progress)?)?;
+    Ok(())
+}
+
+/// Reads an exported file. With `merge`, bookmarks and notes are added to the
+/// existing ones (imported notes win) and the newer last read position is kept.
+pub fn import(current: &QuranProgress, path: &Path, merge: bool) -> Result<QuranProgress> {
+    let imported: QuranProgress = serde_json::from_str(&fs::read_to_string(path)?)
+        .map_err(|e| AppError::InvalidFile(format!("Invalid Quran progress file: {}", e)))?;
+
+    if !merge {
+        return Ok(imported);
+    }
+
+    let mut merged = current.clone();
+    for bookmark in imported.bookmarks {
+        if !merged.bookmarks.iter().any(|b| b.chapter == bookmark.chapter && b.verse == bookmark.verse) {
+            merged.bookmarks.push(bookmark);
+        }
+    }
+    merged.bookmarks.sort_by_key(|b| (b.chapter, b.verse));
+
+    for note in imported.notes {
+        merged.notes.retain(|n| !(n.chapter == note.chapter && n.verse == note.verse));
+        merged.notes.push(note);
+    }
+    merged.notes.sort_by_key(|n| (n.chapter, n.verse));
+
+    merged.last_read = match (merged.last_read.take(), imported.last_read) {
+        (Some(a), Some(b)) => Some(if b.updated_at > a.updated_at { b } else { a }),
+        (a, b) => a.or(b),
+    };
+    if merged.khatm.is_none() {
+        merged.khatm = imported.khatm;
+    }
+
+    Ok(merged)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use std::path::PathBuf;
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 3, day).unwrap()
+    }
+
+    fn at(day: u32, hour: u32, minute: u32) -> DateTime<Local> {
+        Local.with_ymd_and_hms(2025, 3, day, hour, minute, 0).unwrap()
+    }
+
+    fn file(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("muezzin-quran-progress-{}-{}.json", std::process::id(), name))
+    }
+
+    fn times(day: u32) -> PrayerTimes {
+        PrayerTimes {
+            fajr: at(day, 5, 30),
+            sunrise: at(day, 7, 0),
+            dhuhr: at(day, 12, 45),
+            asr: at(day, 16, 0),
+            maghrib: at(day, 18, 40),
+            isha: at(day, 20, 5),
+        }
+    }
+
+    fn position(chapter: u32, verse: u32, page: u32, updated_at: DateTime<Local>) -> ReadingPosition {
+        ReadingPosition { chapter, verse, page, updated_at }
+    }
+
+    #[test]
+    fn juz_boundaries() {
+        assert_eq!((juz_start_page(1), juz_end_page(1)), (1, 21));
+        assert_eq!((juz_start_page(2), juz_end_page(2)), (22, 41));
+        assert_eq!((juz_start_page(30), juz_end_page(30)), (582, PAGE_COUNT));
+    }
+
+    #[test]
+    fn new_plan_rejects_bad_input() {
+        assert!(new_plan(date(3), 30, KhatmUnit::Pages, None).is_ok());
+        assert!(matches!(new_plan(date(3), 0, KhatmUnit::Pages, None), Err(AppError::InvalidInput(_))));
+        assert!(matches!(new_plan(date(3), 3651, KhatmUnit::Pages, None), Err(AppError::InvalidInput(_))));
+        assert!(matches!(new_plan(date(3), 31, KhatmUnit::Juz, None), Err(AppError::InvalidInput(_))));
+
+        let reminder = KhatmReminder { after_prayer: "Sunrise".to_string(), delay_minutes: 10 };
+        assert!(matches!(new_plan(date(3), 30, KhatmUnit::Pages, Some(reminder)), Err(AppError::InvalidInput(_))));
+    }
+
+    #[test]
+    fn page_plan_daily_targets() {
+        let mut plan = new_plan(date(3), 30, KhatmUnit::Pages, None).unwrap();
+
+        // Days before the start count as the first day
+        let status = khatm_status(&plan, date(1));
+        assert_eq!((status.day, status.target_from_page, status.target_to_page), (1, 1, 21));
+        assert_eq!(status.target_juz, None);
+
+        let status = khatm_status(&plan, date(4));
+        assert_eq!((status.day, status.target_from_page, status.target_to_page), (2, 22, 41));
+        assert_eq!(status.behind_by, 21);
+        assert!(!status.today_done);
+
+        record_pages(&mut plan, 41);
+        let status = khatm_status(&plan, date(4));
+        assert_eq!(status.behind_by, 0);
+        assert!(status.today_done);
+
+        // Past the last day the target stays on the last day
+        let status = khatm_status(&plan, NaiveDate::from_ymd_opt(2025, 5, 1).unwrap());
+        assert_eq!((status.day, status.target_to_page), (30, PAGE_COUNT));
+    }
+
+    #[test]
+    fn juz_plan_days_end_on_juz_boundaries() {
+        let plan = new_plan(date(3), 7, KhatmUnit::Juz, None).unwrap();
+
+        let status = khatm_status(&plan, date(3));
+        assert_eq!(status.target_juz, Some((1, 5)));
+        assert_eq!((status.target_from_page, status.target_to_page), (1, juz_end_page(5)));
+
+        let status = khatm_status(&plan, date(4));
+        assert_eq!(status.target_juz, Some((6, 9)));
+        assert_eq!((status.target_from_page, status.target_to_page), (juz_start_page(6), juz_end_page(9)));
+
+        let status = khatm_status(&plan, date(9));
+        assert_eq!(status.target_juz, Some((27, 30)));
+        assert_eq!(status.target_to_page, PAGE_COUNT);
+    }
+
+    #[test]
+    fn record_pages_caps_and_completes() {
+        let mut plan = new_plan(date(3), 30, KhatmUnit::Pages, None).unwrap();
+        record_pages(&mut plan, 700);
+        assert_eq!(plan.pages_read, PAGE_COUNT);
+        let completed_at = plan.completed_at.expect("completed");
+
+        // Recording the end again keeps the first completion time
+        record_pages(&mut plan, PAGE_COUNT);
+        assert_eq!(plan.completed_at, Some(completed_at));
+
+        record_pages(&mut plan, 500);
+        assert_eq!(plan.completed_at, None);
+    }
+
+    #[test]
+    fn last_read_moves_the_khatm_forward_only() {
+        let quran = QuranText::load().unwrap();
+        let mut progress = QuranProgress {
+            khatm: Some(new_plan(date(3), 30, KhatmUnit::Pages, None).unwrap()),
+            ..Default::default()
+        };
+
+        set_last_read(&mut progress, &quran, 2, 142).unwrap();
+        assert_eq!(progress.last_read.as_ref().map(|p| p.page), Some(22));
+        assert_eq!(progress.khatm.as_ref().unwrap().pages_read, 22);
+
+        set_last_read(&mut progress, &quran, 1, 1).unwrap();
+        assert_eq!(progress.last_read.as_ref().map(|p| (p.chapter, p.verse, p.page)), Some((1, 1, 1)));
+        assert_eq!(progress.khatm.as_ref().unwrap().pages_read, 22);
+
+        assert!(matches!(set_last_read(&mut progress, &quran, 1, 8), Err(AppError::InvalidInput(_))));
+    }
+
+    #[test]
+    fn khatm_reminder_fires_once_after_the_prayer() {
+        let reminder = KhatmReminder { after_prayer: "Isha".to_string(), delay_minutes: 15 };
+        let mut progress = QuranProgress {
+            khatm: Some(new_plan(date(3), 30, KhatmUnit::Pages, Some(reminder)).unwrap()),
+            ..Default::default()
+        };
+        let times = times(4);
+
+        assert!(due_khatm_reminder(&progress, &times, at(4, 20, 19)).is_none());
+        let status = due_khatm_reminder(&progress, &times, at(4, 20, 20)).expect("reminder due");
+        assert_eq!((status.day, status.target_to_page), (2, 41));
+        assert!(due_khatm_reminder(&progress, &times, at(4, 20, 21)).is_none());
+
+        // Nothing to remind once today's portion is read
+        record_pages(progress.khatm.as_mut().unwrap(), 41);
+        assert!(due_khatm_reminder(&progress, &times, at(4, 20, 20)).is_none());
+
+        record_pages(progress.khatm.as_mut().unwrap(), PAGE_COUNT);
+        assert!(due_khatm_reminder(&progress, &times, at(4, 20, 20)).is_none());
+
+        progress.khatm.as_mut().unwrap().reminder = None;
+        record_pages(progress.khatm.as_mut().unwrap(), 0);
+        assert!(due_khatm_reminder(&progress, &times, at(4, 20, 20)).is_none());
+    }
+
+    #[test]
+    fn export_then_import_round_trips() {
+        let mut progress = QuranProgress::default();
+        toggle_bookmark(&mut progress, 2, 255, Some("Ayat al-Kursi".to_string()));
+        set_note(&mut progress, 1, 1, "  opening  ");
+        progress.last_read = Some(position(18, 10, 294, at(3, 21, 0)));
+        progress.khatm = Some(new_plan(date(3), 30, KhatmUnit::Juz, None).unwrap());
+
+        let path = file("round-trip");
+        export(&progress, &path).unwrap();
+        let imported = import(&QuranProgress::default(), &path, false).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(serde_json::to_value(&imported).unwrap(), serde_json::to_value(&progress).unwrap());
+        assert_eq!(imported.notes[0].text, "opening");
+    }
+
+    #[test]
+    fn merged_import_keeps_both_sides() {
+        let mut current = QuranProgress::default();
+        toggle_bookmark(&mut current, 2, 255, None);
+        set_note(&mut current, 1, 1, "mine");
+        set_note(&mut current, 1, 2, "kept");
+        current.last_read = Some(position(2, 10, 3, at(3, 21, 0)));
+        current.khatm = Some(new_plan(date(3), 30, KhatmUnit::Pages, None).unwrap());
+
+        let mut incoming = QuranProgress::default();
+        toggle_bookmark(&mut incoming, 2, 255, Some("duplicate".to_string()));
+        toggle_bookmark(&mut incoming, 1, 1, None);
+        set_note(&mut incoming, 1, 1, "theirs");
+        incoming.last_read = Some(position(3, 1, 50, at(4, 7, 0)));
+        incoming.khatm = Some(new_plan(date(4), 10, KhatmUnit::Juz, None).unwrap());
+
+        let path = file("merge");
+        export(&incoming, &path).unwrap();
+        let merged = import(&current, &path, true).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let bookmarks: Vec<_> = merged.bookmarks.iter().map(|b| (b.chapter, b.verse, b.label.clone())).collect();
+        assert_eq!(bookmarks, vec![(1, 1, None), (2, 255, None)]);
+        let notes: Vec<_> = merged.notes.iter().map(|n| (n.chapter, n.verse, n.text.as_str())).collect();
+        assert_eq!(notes, vec![(1, 1, "theirs"), (1, 2, "kept")]);
+        assert_eq!(merged.last_read.map(|p| p.page), Some(50));
+        // The running plan isn't replaced
+        assert_eq!(merged.khatm.map(|p| p.days), Some(30));
+    }
+
+    #[test]
+    fn import_rejects_invalid_files() {
+        let path = file("invalid");
+        fs::write(&path, "{\"bookmarks\": 3}").unwrap();
+        let result = import(&QuranProgress::default(), &path, false);
+        fs::remove_file(&path).unwrap();
+        assert!(matches!(result, Err(AppError::InvalidFile(_))));
+    }
+}
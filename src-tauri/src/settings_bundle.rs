@@ -0,0 +1,271 @@
+//! Settings bundles, for setting up several machines the same way.
+//!
+//! A bundle is a single JSON file:
+//!
+//! ```json
+//! {
+//!   "format": 1,
+//!   "schemaVersion": 1,
+//!   "exportedAt": "2025-03-01T12:00:00+01:00",
+//!   "checksum": "sha256 of the payload, hex",
+//!   "payload": { "settings": { ... }, "location": { ... }, "locationProfiles": [ ... ],
+//!                "activeProfile": "Mosque", "customTimes": { ... }, "jumuahTime": { ... } }
+//! }
+//! ```
+//!
+//! Optional payload fields that aren't set are left out rather than written
+//! as `null`.
+//!
+//! The checksum covers the compact serialization of `payload` with its keys
+//! sorted, which is how `serde_json::Value` writes objects. It catches files
+//! damaged in transit or edited by hand, it is not a signature.
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::Path;
+
+use crate::migrations::SCHEMA_VERSION;
+use crate::models::{AppSettings, BundleLocation, BundlePayload, BundlePreview, SettingChange};
+use crate::storage::AppStorage;
+use crate::error::{AppError, Result};
+
+const FORMAT: u32 = 1;
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SettingsBundle {
+    format: u32,
+    schema_version: u32,
+    exported_at: DateTime<Local>,
+    checksum: String,
+    payload: Value,
+}
+
+fn checksum(payload: &Value) -> Result<String> {
+    let bytes = serde_json::to_vec(payload)?;
+    Ok(Sha256::digest(bytes).iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+/// Drops what belongs to this machine. A temporary mute belongs to this
+/// moment too, and the API token, broker password and display PIN must not
+/// leak with a shared bundle, nor show up in a preview.
+fn clear_local(settings: &mut AppSettings) {
+    settings.muted_until = None;
+    settings.http_api.token.clear();
+    settings.mqtt.password.clear();
+    settings.kiosk.pin_hash.clear();
+}
+
+/// Puts this machine's mute and secrets back into imported settings
+pub fn keep_local(settings: &mut AppSettings, local: &AppSettings) {
+    settings.muted_until = local.muted_until;
+    settings.http_api.token = local.http_api.token.clone();
+    settings.mqtt.password = local.mqtt.password.clone();
+    settings.kiosk.pin_hash = local.kiosk.pin_hash.clone();
+}
+
+/// The persisted state as it would be exported
+pub fn current_payload(storage: &AppStorage) -> Result<BundlePayload> {
+    let mut settings = storage.get_settings()?;
+    clear_local(&mut settings);
+
+    let (latitude, longitude, timezone) = storage.get_location()?;
+    let location = (!storage.is_first_time()).then_some(BundleLocation { latitude, longitude, timezone });
+
+    Ok(BundlePayload {
+        settings,
+        location,
+        location_profiles: storage.get_location_profiles()?,
+        active_profile: storage.get_active_profile(),
+        custom_times: storage.get_custom_times()?,
+        jumuah_time: storage.get_jumuah_time()?,
+    })
+}
+
+pub fn export(storage: &AppStorage, path: &Path) -> Result<()> {
+    write(path, current_payload(storage)?)
+}
+
+fn write(path: &Path, mut payload: BundlePayload) -> Result<()> {
+    clear_local(&mut payload.settings);
+    let payload = serde_json::to_value(payload)?;
+    let bundle = SettingsBundle {
+        format: FORMAT,
+        schema_version: SCHEMA_VERSION,
+        exported_at: Local::now(),
+        checksum: checksum(&payload)?,
+        payload,
+    };
+    fs::write(path, serde_json::to_string_pretty(&bundle)?)?;
+    Ok(())
+}
+
+/// Reads a bundle, checking its format, checksum and every value in it
+pub fn read(path: &Path) -> Result<(DateTime<Local>, BundlePayload)> {
+    let bundle: SettingsBundle = serde_json::from_str(&fs::read_to_string(path)?)
+        .map_err(|e| AppError::InvalidFile(format!("Invalid settings bundle: {}", e)))?;
+
+    if bundle.format != FORMAT {
+        return Err(AppError::InvalidFile(format!("Unsupported settings bundle format: {}", bundle.format)));
+    }
+    if bundle.schema_version > SCHEMA_VERSION {
+        return Err(AppError::InvalidFile(format!(
+            "Settings bundle was exported by a newer version (schema {})",
+            bundle.schema_version
+        )));
+    }
+    if checksum(&bundle.payload)? != bundle.checksum.to_lowercase() {
+        return Err(AppError::InvalidFile("Settings bundle checksum doesn't match, the file was modified or damaged".to_string()));
+    }
+
+    let payload: BundlePayload = serde_json::from_value(bundle.payload)
+        .map_err(|e| AppError::InvalidFile(format!("Invalid settings bundle: {}", e)))?;
+    if let Some(ref active) = payload.active_profile {
+        if !payload.location_profiles.iter().any(|p| &p.name == active) {
+            return Err(AppError::NotFound(format!("Unknown location profile: {}", active)));
+        }
+    }
+    Ok((bundle.exported_at, payload))
+}
+
+fn diff(path: &str, current: Option<&Value>, incoming: Option<&Value>, changes: &mut Vec<SettingChange>) {
+    match (current, incoming) {
+        (Some(Value::Object(a)), Some(Value::Object(b))) => {
+            let mut keys: Vec<&String> = a.keys().chain(b.keys()).collect();
+            keys.sort();
+            keys.dedup();
+            for key in keys {
+                let child = if path.is_empty() { key.clone() } else { format!("{}.{}", path, key) };
+                diff(&child, a.get(key), b.get(key), changes);
+            }
+        }
+        (a, b) if a == b => {}
+        (a, b) => changes.push(SettingChange {
+            path: path.to_string(),
+            current: a.cloned(),
+            incoming: b.cloned(),
+        }),
+    }
+}
+
+/// What applying `incoming` would change, compared to the persisted state
+pub fn changes(storage: &AppStorage, incoming: &BundlePayload) -> Result<Vec<SettingChange>> {
+    changes_between(&current_payload(storage)?, incoming)
+}
+
+/// Mute and secrets are left out on both sides, importing never changes them
+fn changes_between(current: &BundlePayload, incoming: &BundlePayload) -> Result<Vec<SettingChange>> {
+    let mut current = current.clone();
+    let mut incoming = incoming.clone();
+    clear_local(&mut current.settings);
+    clear_local(&mut incoming.settings);
+    let current = serde_json::to_value(current)?;
+    let incoming = serde_json::to_value(incoming)?;
+
+    let mut changes = Vec::new();
+    diff("", Some(&current), Some(&incoming), &mut changes);
+    Ok(changes)
+}
+
+pub fn preview(storage: &AppStorage, path: &Path) -> Result<BundlePreview> {
+    let (exported_at, incoming) = read(path)?;
+    Ok(BundlePreview { exported_at, changes: changes(storage, &incoming)? })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{CalculationSettings, HttpApiSettings, KioskSettings, MqttSettings};
+    use chrono::TimeZone;
+    use serde_json::json;
+    use std::path::PathBuf;
+
+    fn file(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("muezzin-bundle-{}-{}.json", std::process::id(), name))
+    }
+
+    fn payload() -> BundlePayload {
+        let defaults = AppSettings::default();
+        let settings = AppSettings {
+            calculation: CalculationSettings { madhab: "Hanafi".to_string(), ..defaults.calculation.clone() },
+            muted_until: Local.with_ymd_and_hms(2025, 3, 3, 18, 0, 0).earliest(),
+            http_api: HttpApiSettings { token: "0123456789abcdef".to_string(), ..defaults.http_api.clone() },
+            mqtt: MqttSettings { password: "broker secret".to_string(), ..defaults.mqtt.clone() },
+            kiosk: KioskSettings { pin_hash: "pbkdf2-sha256$100000$00$00".to_string(), ..defaults.kiosk.clone() },
+            ..defaults
+        };
+        BundlePayload {
+            settings,
+            location: Some(BundleLocation { latitude: 21.4225, longitude: 39.8262, timezone: "Asia/Riyadh".to_string() }),
+            location_profiles: Vec::new(),
+            active_profile: None,
+            custom_times: None,
+            jumuah_time: None,
+        }
+    }
+
+    #[test]
+    fn written_bundle_reads_back_without_secrets() {
+        let path = file("round-trip");
+        write(&path, payload()).unwrap();
+        let (_, read_back) = read(&path).unwrap();
+        let _ = fs::remove_file(&path);
+
+        let settings = &read_back.settings;
+        assert_eq!(settings.calculation.madhab, "Hanafi");
+        assert_eq!(settings.muted_until, None);
+        assert!(settings.http_api.token.is_empty());
+        assert!(settings.mqtt.password.is_empty());
+        assert!(settings.kiosk.pin_hash.is_empty());
+        assert_eq!(read_back.location.unwrap().timezone, "Asia/Riyadh");
+    }
+
+    #[test]
+    fn tampered_bundle_is_rejected() {
+        let path = file("tampered");
+        write(&path, payload()).unwrap();
+        let mut bundle: Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        bundle["payload"]["settings"]["calculation"]["madhab"] = json!("Shafi");
+        fs::write(&path, bundle.to_string()).unwrap();
+
+        let error = read(&path).unwrap_err();
+        let _ = fs::remove_file(&path);
+        assert!(matches!(error, AppError::InvalidFile(ref message) if message.contains("checksum")), "{}", error);
+    }
+
+    #[test]
+    fn diff_lists_changed_paths_but_no_secrets() {
+        let current = payload();
+        let mut incoming = payload();
+        incoming.settings.calculation.madhab = "Shafi".to_string();
+        incoming.settings.dark_mode = !current.settings.dark_mode;
+        incoming.location = None;
+        incoming.settings.muted_until = None;
+        incoming.settings.http_api.token = "fedcba9876543210".to_string();
+        incoming.settings.mqtt.password = "another secret".to_string();
+        incoming.settings.kiosk.pin_hash.clear();
+
+        let changes = changes_between(&current, &incoming).unwrap();
+        let paths: Vec<&str> = changes.iter().map(|c| c.path.as_str()).collect();
+        assert_eq!(paths, ["location", "settings.calculation.madhab", "settings.dark_mode"]);
+        assert_eq!(changes[1].current, Some(json!("Hanafi")));
+        assert_eq!(changes[1].incoming, Some(json!("Shafi")));
+        assert_eq!(changes[0].incoming, None);
+    }
+
+    #[test]
+    fn import_keeps_the_local_mute_and_secrets() {
+        let local = payload().settings;
+        let mut incoming = AppSettings { dark_mode: false, ..AppSettings::default() };
+        keep_local(&mut incoming, &local);
+
+        assert!(!incoming.dark_mode);
+        assert_eq!(incoming.muted_until, local.muted_until);
+        assert_eq!(incoming.http_api.token, local.http_api.token);
+        assert_eq!(incoming.mqtt.password, local.mqtt.password);
+        assert_eq!(incoming.kiosk.pin_hash, local.kiosk.pin_hash);
+    }
+}
@@ -0,0 +1,80 @@
+//! Reads the app's settings store straight from disk, for the binaries that
+//! run without Tauri. Only the keys needed to calculate times are read.
+
+use chrono_tz::Tz;
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use muezzin_core::PrayerCalculator;
+use crate::error::{AppError, Result};
+use crate::models::{AppSettings, CustomTimes, JumuahTime};
+
+const APP_IDENTIFIER: &str = "io.github.cormacz.muezzin";
+const STORE_FILE: &str = "settings.json";
+
+#[derive(Default)]
+pub struct StoredSettings {
+    pub settings: AppSettings,
+    pub location: Option<(f64, f64, String)>,
+    pub custom_times: Option<CustomTimes>,
+    pub jumuah_time: Option<JumuahTime>,
+}
+
+/// Where the app keeps its store, e.g. `~/.local/share/io.github.cormacz.muezzin/settings.json`
+pub fn default_path() -> Option<PathBuf> {
+    let dirs = directories::BaseDirs::new()?;
+    Some(dirs.data_dir().join(APP_IDENTIFIER).join(STORE_FILE))
+}
+
+fn read_key<T: DeserializeOwned>(values: &Map<String, Value>, key: &str) -> Result<Option<T>> {
+    values
+        .get(key)
+        .map(|v| serde_json::from_value(v.clone()))
+        .transpose()
+        .map_err(|e| AppError::CorruptValue { key: key.to_string(), reason: e.to_string() })
+}
+
+impl StoredSettings {
+    pub fn load(path: &Path) -> Result<Self> {
+        let json = fs::read_to_string(path)?;
+        let values: Map<String, Value> = serde_json::from_str(&json)
+            .map_err(|e| AppError::InvalidFile(format!("Invalid settings file {}: {}", path.display(), e)))?;
+
+        let location = match (
+            read_key::<f64>(&values, "latitude")?,
+            read_key::<f64>(&values, "longitude")?,
+            read_key::<String>(&values, "timezone")?,
+        ) {
+            (Some(lat), Some(lon), Some(tz)) => Some((lat, lon, tz)),
+            _ => None,
+        };
+
+        Ok(Self {
+            settings: read_key(&values, "settings")?.unwrap_or_default(),
+            location,
+            custom_times: read_key(&values, "customTimes")?,
+            jumuah_time: read_key(&values, "jumuahTime")?,
+        })
+    }
+
+    /// Calculator for the stored location, failing when none has been set
+    pub fn calculator(&self) -> Result<PrayerCalculator> {
+        let (lat, lon, tz) = self.location.clone().ok_or_else(|| {
+            AppError::NotFound("No location has been saved yet".to_string())
+        })?;
+        let tz = tz.parse::<Tz>().map_err(|e| AppError::Timezone(format!("Invalid timezone: {}", e)))?;
+
+        let mut calculator = PrayerCalculator::new();
+        calculator.update_settings(
+            lat,
+            lon,
+            &self.settings.calculation,
+            tz,
+            self.custom_times.clone(),
+            self.jumuah_time.clone(),
+        )?;
+        Ok(calculator)
+    }
+}
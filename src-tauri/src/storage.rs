@@ -1,31 +1,114 @@
-use tauri::AppHandle;
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value};
+use tauri::{AppHandle, Manager};
 use tauri_plugin_store::{Store, StoreExt};
 use std::sync::Arc;
 use parking_lot::Mutex;
 
-use crate::models::{AppSettings, CustomTimes, JumuahTime};
-use crate::error::Result;
+use crate::migrations;
+use crate::models::{
+    AppSettings, BundlePayload, CorruptValue, CustomTimes, JumuahTime, LocationProfile, QuranProgress, StorageReport,
+};
+use crate::error::{AppError, Result};
+
+const STORE_FILE: &str = "settings.json";
+
+fn check<T: DeserializeOwned>(value: &Value) -> serde_json::Result<()> {
+    serde_json::from_value::<T>(value.clone()).map(drop)
+}
+
+/// Whether a stored value still reads as its type
+type Check = fn(&Value) -> serde_json::Result<()>;
+
+/// Typed keys of the store, checked at launch and by `get_storage_report`
+const TYPED_KEYS: [(&str, Check); 8] = [
+    ("settings", check::<AppSettings>),
+    ("latitude", check::<f64>),
+    ("longitude", check::<f64>),
+    ("timezone", check::<String>),
+    ("customTimes", check::<CustomTimes>),
+    ("jumuahTime", check::<JumuahTime>),
+    ("locationProfiles", check::<Vec<LocationProfile>>),
+    ("quranProgress", check::<QuranProgress>),
+];
+
+/// Reads a key, reporting a value that no longer deserializes instead of
+/// replacing it with a default
+fn read<T: DeserializeOwned>(store: &Store, key: &str) -> Result<Option<T>> {
+    match store.get(key) {
+        None => Ok(None),
+        Some(value) => serde_json::from_value(value)
+            .map(Some)
+            .map_err(|e| AppError::CorruptValue { key: key.to_string(), reason: e.to_string() }),
+    }
+}
 
 pub struct AppStorage {
     store: Arc<Mutex<Store>>,
+    report: Mutex<StorageReport>,
 }
 
 impl AppStorage {
     pub fn new(app: &AppHandle) -> Result<Self> {
-        let store = app.store("settings.json")?;
+        let store = app.store(STORE_FILE)?;
+        let file = app.path().app_data_dir()?.join(STORE_FILE);
+
+        let mut values: Map<String, Value> = store.entries().into_iter().collect();
+        let mut report = StorageReport::default();
+        if migrations::migrate(&mut values, &file, &mut report)? {
+            for key in store.keys() {
+                if !values.contains_key(&key) {
+                    store.delete(&key)?;
+                }
+            }
+            for (key, value) in values {
+                store.set(key, value)?;
+            }
+            store.save()?;
+        }
 
-        Ok(Self {
+        let storage = Self {
             store: Arc::new(Mutex::new(store)),
-        })
+            report: Mutex::new(report),
+        };
+        for corrupt in storage.corrupt_values() {
+            eprintln!("Stored value '{}' is corrupt: {}", corrupt.key, corrupt.error);
+        }
+        Ok(storage)
+    }
+
+    pub fn corrupt_values(&self) -> Vec<CorruptValue> {
+        let store = self.store.lock();
+        TYPED_KEYS
+            .iter()
+            .filter_map(|(key, check)| {
+                let error = check(&store.get(key)?).err()?;
+                Some(CorruptValue { key: key.to_string(), error: error.to_string() })
+            })
+            .collect()
+    }
+
+    pub fn report(&self) -> StorageReport {
+        let mut report = self.report.lock().clone();
+        report.corrupt = self.corrupt_values();
+        report
+    }
+
+    /// Drops a stored value so its default applies again. Only done on
+    /// request, so a corrupt value can be looked at before it is lost.
+    pub fn reset_value(&self, key: &str) -> Result<()> {
+        if !TYPED_KEYS.iter().any(|(k, _)| *k == key) {
+            return Err(AppError::InvalidInput(format!("Unknown setting: {}", key)));
+        }
+        let mut store = self.store.lock();
+        store.delete(key)?;
+        store.save()?;
+        Ok(())
     }
 
     pub fn get_settings(&self) -> Result<AppSettings> {
         let store = self.store.lock();
-        let settings: AppSettings = store
-            .get("settings")
-            .and_then(|v| serde_json::from_value(v.clone()).ok())
-            .unwrap_or_default();
-        Ok(settings)
+        Ok(read(&store, "settings")?.unwrap_or_default())
     }
 
     pub fn save_settings(&self, settings: &AppSettings) -> Result<()> {
@@ -37,15 +120,9 @@ impl AppStorage {
 
     pub fn get_location(&self) -> Result<(f64, f64, String)> {
         let store = self.store.lock();
-        let lat = store.get("latitude")
-            .and_then(|v| v.as_f64())
-            .unwrap_or(0.0);
-        let lon = store.get("longitude")
-            .and_then(|v| v.as_f64())
-            .unwrap_or(0.0);
-        let tz = store.get("timezone")
-            .and_then(|v| v.as_str().map(String::from))
-            .unwrap_or_else(|| "UTC".to_string());
+        let lat = read(&store, "latitude")?.unwrap_or(0.0);
+        let lon = read(&store, "longitude")?.unwrap_or(0.0);
+        let tz = read(&store, "timezone")?.unwrap_or_else(|| "UTC".to_string());
         Ok((lat, lon, tz))
     }
 
@@ -58,12 +135,36 @@ impl AppStorage {
         Ok(())
     }
 
+    pub fn get_location_profiles(&self) -> Result<Vec<LocationProfile>> {
+        let store = self.store.lock();
+        Ok(read(&store, "locationProfiles")?.unwrap_or_default())
+    }
+
+    pub fn save_location_profiles(&self, profiles: &[LocationProfile]) -> Result<()> {
+        let mut store = self.store.lock();
+        store.set("locationProfiles", serde_json::to_value(profiles)?)?;
+        store.save()?;
+        Ok(())
+    }
+
+    pub fn get_active_profile(&self) -> Option<String> {
+        let store = self.store.lock();
+        store.get("activeProfile").and_then(|v| v.as_str().map(String::from))
+    }
+
+    pub fn set_active_profile(&self, name: Option<&str>) -> Result<()> {
+        let mut store = self.store.lock();
+        match name {
+            Some(name) => store.set("activeProfile", serde_json::json!(name))?,
+            None => { store.delete("activeProfile")?; }
+        }
+        store.save()?;
+        Ok(())
+    }
+
     pub fn get_custom_times(&self) -> Result<Option<CustomTimes>> {
         let store = self.store.lock();
-        let custom_times = store
-            .get("customTimes")
-            .and_then(|v| serde_json::from_value(v.clone()).ok());
-        Ok(custom_times)
+        read(&store, "customTimes")
     }
 
     pub fn save_custom_times(&self, custom_times: &CustomTimes) -> Result<()> {
@@ -75,10 +176,7 @@ impl AppStorage {
 
     pub fn get_jumuah_time(&self) -> Result<Option<JumuahTime>> {
         let store = self.store.lock();
-        let jumuah_time = store
-            .get("jumuahTime")
-            .and_then(|v| serde_json::from_value(v.clone()).ok());
-        Ok(jumuah_time)
+        read(&store, "jumuahTime")
     }
 
     pub fn save_jumuah_time(&self, jumuah_time: &JumuahTime) -> Result<()> {
@@ -88,6 +186,61 @@ impl AppStorage {
         Ok(())
     }
 
+    pub fn get_quran_progress(&self) -> Result<QuranProgress> {
+        let store = self.store.lock();
+        Ok(read(&store, "quranProgress")?.unwrap_or_default())
+    }
+
+    pub fn save_quran_progress(&self, progress: &QuranProgress) -> Result<()> {
+        let mut store = self.store.lock();
+        store.set("quranProgress", serde_json::to_value(progress)?)?;
+        store.save()?;
+        Ok(())
+    }
+
+    /// Writes a whole settings bundle with a single save. If anything fails,
+    /// the keys already changed are put back so nothing is half applied.
+    pub fn apply_bundle(&self, payload: &BundlePayload) -> Result<()> {
+        let mut entries: Vec<(&str, Option<Value>)> = vec![
+            ("settings", Some(serde_json::to_value(&payload.settings)?)),
+            ("locationProfiles", Some(serde_json::to_value(&payload.location_profiles)?)),
+            ("activeProfile", payload.active_profile.as_ref().map(|name| Value::from(name.as_str()))),
+            ("customTimes", payload.custom_times.as_ref().map(serde_json::to_value).transpose()?),
+            ("jumuahTime", payload.jumuah_time.as_ref().map(serde_json::to_value).transpose()?),
+        ];
+        if let Some(ref location) = payload.location {
+            entries.push(("latitude", Some(Value::from(location.latitude))));
+            entries.push(("longitude", Some(Value::from(location.longitude))));
+            entries.push(("timezone", Some(Value::from(location.timezone.as_str()))));
+            entries.push(("first", Some(Value::from(true))));
+        }
+
+        let mut store = self.store.lock();
+        let previous: Vec<(&str, Option<Value>)> = entries
+            .iter()
+            .map(|(key, _)| (*key, store.get(key)))
+            .collect();
+
+        let write = |store: &mut Store, entries: &[(&str, Option<Value>)]| -> Result<()> {
+            for (key, value) in entries {
+                match value {
+                    Some(value) => store.set(*key, value.clone())?,
+                    None => { store.delete(key)?; }
+                }
+            }
+            store.save()?;
+            Ok(())
+        };
+
+        if let Err(e) = write(&mut store, &entries) {
+            if let Err(restore) = write(&mut store, &previous) {
+                eprintln!("Error restoring settings after a failed import: {}", restore);
+            }
+            return Err(e);
+        }
+        Ok(())
+    }
+
     pub fn is_first_time(&self) -> bool {
         let store = self.store.lock();
         !store.has("first")
@@ -0,0 +1,530 @@
+//! Prayer tracker and qada (make-up prayer) log.
+//!
+//! Entries live in `<app data>/tracker.db`, a SQLite database with one row per
+//! prayer per day. Qada is counted rather than tied to a date: the outstanding
+//! count for a prayer is the user's backlog from before tracking started, plus
+//! every missed entry, minus the make-up prayers recorded.
+
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate};
+use parking_lot::Mutex;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::fs;
+use std::path::Path;
+use tauri::{AppHandle, Manager};
+
+use crate::models::{
+    PrayerCounts, PrayerLogEntry, PrayerStats, PrayerStatus, PrayerStreak, PrayerTimes, QadaBalance,
+    StatsPeriod, StatusCounts,
+};
+use crate::error::{AppError, Result};
+
+pub const PRAYERS: [&str; 5] = ["Fajr", "Dhuhr", "Asr", "Maghrib", "Isha"];
+
+const DB_FILE: &str = "tracker.db";
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS prayer_log (
+        date TEXT NOT NULL,
+        prayer TEXT NOT NULL,
+        status TEXT NOT NULL,
+        recorded_at TEXT NOT NULL,
+        PRIMARY KEY (date, prayer)
+    );
+    CREATE TABLE IF NOT EXISTS qada_backlog (
+        prayer TEXT PRIMARY KEY,
+        count INTEGER NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS qada_made_up (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        prayer TEXT NOT NULL,
+        made_up_at TEXT NOT NULL
+    );
+";
+
+fn check_prayer(prayer: &str) -> Result<()> {
+    if PRAYERS.contains(&prayer) {
+        Ok(())
+    } else {
+        Err(AppError::InvalidInput(format!("Unknown prayer: {}", prayer)))
+    }
+}
+
+fn parse_date(value: &str) -> Result<NaiveDate> {
+    Ok(NaiveDate::parse_from_str(value, DATE_FORMAT)?)
+}
+
+pub struct PrayerTracker {
+    conn: Mutex<Connection>,
+}
+
+impl PrayerTracker {
+    pub fn new(app: &AppHandle) -> Result<Self> {
+        let dir = app.path().app_data_dir()?;
+        fs::create_dir_all(&dir)?;
+        Self::open(&dir.join(DB_FILE))
+    }
+
+    pub fn open(path: &Path) -> Result<Self> {
+        let conn = Connection::open(path)?;
+        conn.execute_batch(SCHEMA)?;
+        Ok(Self { conn: Mutex::new(conn) })
+    }
+
+    /// Records (or replaces) the status of one prayer on one day
+    pub fn record(&self, date: NaiveDate, prayer: &str, status: PrayerStatus) -> Result<PrayerLogEntry> {
+        check_prayer(prayer)?;
+        let entry = PrayerLogEntry {
+            date,
+            prayer: prayer.to_string(),
+            status,
+            recorded_at: Local::now(),
+        };
+
+        self.conn.lock().execute(
+            "INSERT OR REPLACE INTO prayer_log (date, prayer, status, recorded_at) VALUES (?1, ?2, ?3, ?4)",
+            params![
+                date.format(DATE_FORMAT).to_string(),
+                prayer,
+                status.as_str(),
+                entry.recorded_at.to_rfc3339(),
+            ],
+        )?;
+        Ok(entry)
+    }
+
+    pub fn clear(&self, date: NaiveDate, prayer: &str) -> Result<()> {
+        self.conn.lock().execute(
+            "DELETE FROM prayer_log WHERE date = ?1 AND prayer = ?2",
+            params![date.format(DATE_FORMAT).to_string(), prayer],
+        )?;
+        Ok(())
+    }
+
+    /// Entries between two dates, inclusive, oldest first
+    pub fn entries(&self, from: NaiveDate, to: NaiveDate) -> Result<Vec<PrayerLogEntry>> {
+        let conn = self.conn.lock();
+        let mut stmt = conn.prepare(
+            "SELECT date, prayer, status, recorded_at FROM prayer_log
+             WHERE date >= ?1 AND date <= ?2 ORDER BY date, recorded_at",
+        )?;
+        let rows = stmt.query_map(
+            params![from.format(DATE_FORMAT).to_string(), to.format(DATE_FORMAT).to_string()],
+            |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?, row.get::<_, String>(3)?)),
+        )?;
+
+        let mut entries = Vec::new();
+        for row in rows {
+            let (date, prayer, status, recorded_at) = row?;
+            let status = PrayerStatus::parse(&status)
+                .ok_or_else(|| AppError::InvalidData(format!("Unknown prayer status in tracker: {}", status)))?;
+            entries.push(PrayerLogEntry {
+                date: parse_date(&date)?,
+                prayer,
+                status,
+                recorded_at: DateTime::parse_from_rfc3339(&recorded_at)?.with_timezone(&Local),
+            });
+        }
+        Ok(entries)
+    }
+
+    pub fn first_date(&self) -> Result<Option<NaiveDate>> {
+        let first: Option<String> = self
+            .conn
+            .lock()
+            .query_row("SELECT MIN(date) FROM prayer_log", [], |row| row.get(0))?;
+        first.map(|d| parse_date(&d)).transpose()
+    }
+
+    fn all_entries(&self) -> Result<Vec<PrayerLogEntry>> {
+        match self.first_date()? {
+            Some(first) => self.entries(first, Local::now().date_naive() + Duration::days(1)),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// A day counts towards a streak once all five prayers are logged and none
+    /// was missed. Today only breaks the current streak once it is complete.
+    pub fn streak(&self, today: NaiveDate) -> Result<PrayerStreak> {
+        let entries = self.all_entries()?;
+        let Some(first) = entries.first().map(|e| e.date) else {
+            return Ok(PrayerStreak::default());
+        };
+
+        let prayed_count = |date: NaiveDate| {
+            entries.iter().filter(|e| e.date == date && e.status != PrayerStatus::Missed).count()
+        };
+
+        let mut streak = PrayerStreak::default();
+        let mut run = 0;
+        let mut date = first;
+        while date <= today {
+            if prayed_count(date) == PRAYERS.len() {
+                run += 1;
+                streak.longest = streak.longest.max(run);
+            } else if date < today {
+                run = 0;
+            }
+            date += Duration::days(1);
+        }
+        streak.current = run;
+        Ok(streak)
+    }
+
+    /// Counts per prayer for the week (Monday to Sunday) or calendar month
+    /// containing `date`
+    pub fn stats(&self, period: StatsPeriod, date: NaiveDate, today: NaiveDate) -> Result<PrayerStats> {
+        let (from, to) = match period {
+            StatsPeriod::Week => {
+                let from = date - Duration::days(date.weekday().num_days_from_monday() as i64);
+                (from, from + Duration::days(6))
+            }
+            StatsPeriod::Month => {
+                let from = date.with_day(1).unwrap_or(date);
+                let next_month = if from.month() == 12 {
+                    NaiveDate::from_ymd_opt(from.year() + 1, 1, 1)
+                } else {
+                    NaiveDate::from_ymd_opt(from.year(), from.month() + 1, 1)
+                };
+                (from, next_month.map(|d| d - Duration::days(1)).unwrap_or(from))
+            }
+        };
+
+        let entries = self.entries(from, to)?;
+        // Days before tracking started or still ahead aren't "unlogged"
+        let counted_days = match self.first_date()? {
+            Some(first) => {
+                let start = from.max(first);
+                let end = to.min(today);
+                if end >= start { (end - start).num_days() as u32 + 1 } else { 0 }
+            }
+            None => 0,
+        };
+
+        let mut total = StatusCounts::default();
+        let prayers = PRAYERS
+            .iter()
+            .map(|&prayer| {
+                let mut counts = StatusCounts::default();
+                let mut logged_in_range = 0;
+                for entry in entries.iter().filter(|e| e.prayer == prayer) {
+                    match entry.status {
+                        PrayerStatus::OnTime => counts.on_time += 1,
+                        PrayerStatus::Late => counts.late += 1,
+                        PrayerStatus::Congregation => counts.congregation += 1,
+                        PrayerStatus::Missed => counts.missed += 1,
+                    }
+                    if entry.date <= today {
+                        logged_in_range += 1;
+                    }
+                }
+                counts.unlogged = counted_days.saturating_sub(logged_in_range);
+
+                total.on_time += counts.on_time;
+                total.late += counts.late;
+                total.congregation += counts.congregation;
+                total.missed += counts.missed;
+                total.unlogged += counts.unlogged;
+
+                PrayerCounts { prayer: prayer.to_string(), counts }
+            })
+            .collect();
+
+        Ok(PrayerStats { from, to, prayers, total })
+    }
+
+    pub fn qada_balance(&self) -> Result<Vec<QadaBalance>> {
+        let conn = self.conn.lock();
+        PRAYERS
+            .iter()
+            .map(|&prayer| {
+                let backlog: u32 = conn
+                    .query_row("SELECT count FROM qada_backlog WHERE prayer = ?1", [prayer], |row| row.get(0))
+                    .optional()?
+                    .unwrap_or(0);
+                let missed: u32 = conn.query_row(
+                    "SELECT COUNT(*) FROM prayer_log WHERE prayer = ?1 AND status = ?2",
+                    params![prayer, PrayerStatus::Missed.as_str()],
+                    |row| row.get(0),
+                )?;
+                let made_up: u32 = conn.query_row(
+                    "SELECT COUNT(*) FROM qada_made_up WHERE prayer = ?1",
+                    [prayer],
+                    |row| row.get(0),
+                )?;
+
+                Ok(QadaBalance {
+                    prayer: prayer.to_string(),
+                    backlog,
+                    missed,
+                    made_up,
+                    outstanding: (backlog + missed).saturating_sub(made_up),
+                })
+            })
+            .collect()
+    }
+
+    /// Sets how many of a prayer were owed before tracking started
+    pub fn set_qada_backlog(&self, prayer: &str, count: u32) -> Result<()> {
+        check_prayer(prayer)?;
+        self.conn.lock().execute(
+            "INSERT OR REPLACE INTO qada_backlog (prayer, count) VALUES (?1, ?2)",
+            params![prayer, count],
+        )?;
+        Ok(())
+    }
+
+    pub fn record_qada(&self, prayer: &str, count: u32) -> Result<()> {
+        check_prayer(prayer)?;
+        let mut conn = self.conn.lock();
+        let tx = conn.transaction()?;
+        let now = Local::now().to_rfc3339();
+        for _ in 0..count {
+            tx.execute(
+                "INSERT INTO qada_made_up (prayer, made_up_at) VALUES (?1, ?2)",
+                params![prayer, now],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Undoes the most recent make-up prayers, e.g. after a mistaken tap
+    pub fn undo_qada(&self, prayer: &str, count: u32) -> Result<()> {
+        self.conn.lock().execute(
+            "DELETE FROM qada_made_up WHERE id IN
+             (SELECT id FROM qada_made_up WHERE prayer = ?1 ORDER BY id DESC LIMIT ?2)",
+            params![prayer, count],
+        )?;
+        Ok(())
+    }
+
+    pub fn export_csv(&self, path: &Path) -> Result<()> {
+        let mut csv = String::from("date,prayer,status,recorded_at\n");
+        for entry in self.all_entries()? {
+            csv.push_str(&format!(
+                "{},{},{},{}\n",
+                entry.date.format(DATE_FORMAT),
+                entry.prayer,
+                entry.status.as_str(),
+                entry.recorded_at.to_rfc3339(),
+            ));
+        }
+        fs::write(path, csv)?;
+        Ok(())
+    }
+}
+
+/// Day and status for a prayer marked as prayed from a shortcut (notification
+/// button or tray). It is on time while its window is still open; Isha marked
+/// after midnight belongs to the previous day.
+pub fn status_for_mark(prayer: &str, times: &PrayerTimes, now: DateTime<Local>) -> (NaiveDate, PrayerStatus) {
+    let window_end = match prayer {
+        "Fajr" => Some(times.sunrise),
+        "Dhuhr" => Some(times.asr),
+        "Asr" => Some(times.maghrib),
+        "Maghrib" => Some(times.isha),
+        _ => None,
+    };
+
+    match window_end {
+        Some(end) if now >= end => (now.date_naive(), PrayerStatus::Late),
+        Some(_) => (now.date_naive(), PrayerStatus::OnTime),
+        None if now < times.fajr => (now.date_naive() - Duration::days(1), PrayerStatus::OnTime),
+        None => (now.date_naive(), PrayerStatus::OnTime),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn tracker() -> PrayerTracker {
+        PrayerTracker::open(Path::new(":memory:")).unwrap()
+    }
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 3, day).unwrap()
+    }
+
+    fn at(day: u32, hour: u32, minute: u32) -> DateTime<Local> {
+        Local.with_ymd_and_hms(2025, 3, day, hour, minute, 0).unwrap()
+    }
+
+    fn full_day(tracker: &PrayerTracker, day: u32) {
+        for prayer in PRAYERS {
+            tracker.record(date(day), prayer, PrayerStatus::OnTime).unwrap();
+        }
+    }
+
+    fn counts(counts: &StatusCounts) -> (u32, u32, u32, u32, u32) {
+        (counts.on_time, counts.late, counts.congregation, counts.missed, counts.unlogged)
+    }
+
+    #[test]
+    fn record_replaces_and_rejects_unknown_prayers() {
+        let tracker = tracker();
+        tracker.record(date(3), "Fajr", PrayerStatus::Late).unwrap();
+        tracker.record(date(3), "Fajr", PrayerStatus::OnTime).unwrap();
+
+        let entries = tracker.entries(date(1), date(31)).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].status, PrayerStatus::OnTime);
+
+        assert!(matches!(tracker.record(date(3), "Witr", PrayerStatus::OnTime), Err(AppError::InvalidInput(_))));
+
+        tracker.clear(date(3), "Fajr").unwrap();
+        assert!(tracker.entries(date(1), date(31)).unwrap().is_empty());
+        assert_eq!(tracker.first_date().unwrap(), None);
+    }
+
+    #[test]
+    fn streaks_break_on_missed_or_incomplete_days() {
+        let tracker = tracker();
+        assert_eq!(tracker.streak(date(9)).unwrap().current, 0);
+
+        for day in 1..=4 {
+            full_day(&tracker, day);
+        }
+        full_day(&tracker, 5);
+        tracker.record(date(5), "Asr", PrayerStatus::Missed).unwrap();
+        for day in 6..=8 {
+            full_day(&tracker, day);
+        }
+        tracker.record(date(9), "Fajr", PrayerStatus::Late).unwrap();
+        tracker.record(date(9), "Dhuhr", PrayerStatus::Congregation).unwrap();
+
+        // Today isn't finished yet, so it doesn't break the run
+        let streak = tracker.streak(date(9)).unwrap();
+        assert_eq!((streak.current, streak.longest), (3, 4));
+
+        let streak = tracker.streak(date(10)).unwrap();
+        assert_eq!((streak.current, streak.longest), (0, 4));
+
+        // Late and congregation prayers count as prayed
+        for prayer in ["Asr", "Maghrib", "Isha"] {
+            tracker.record(date(9), prayer, PrayerStatus::Late).unwrap();
+        }
+        let streak = tracker.streak(date(9)).unwrap();
+        assert_eq!((streak.current, streak.longest), (4, 4));
+    }
+
+    #[test]
+    fn weekly_stats_count_unlogged_days_since_tracking_started() {
+        let tracker = tracker();
+        assert_eq!(counts(&tracker.stats(StatsPeriod::Week, date(5), date(5)).unwrap().total), (0, 0, 0, 0, 0));
+
+        // Sunday before the week, then Monday and Tuesday of it
+        tracker.record(date(2), "Fajr", PrayerStatus::OnTime).unwrap();
+        full_day(&tracker, 3);
+        tracker.record(date(3), "Isha", PrayerStatus::Late).unwrap();
+        tracker.record(date(4), "Fajr", PrayerStatus::Missed).unwrap();
+        tracker.record(date(4), "Dhuhr", PrayerStatus::Congregation).unwrap();
+
+        let stats = tracker.stats(StatsPeriod::Week, date(5), date(5)).unwrap();
+        assert_eq!((stats.from, stats.to), (date(3), date(9)));
+
+        let prayers: Vec<_> = stats.prayers.iter().map(|p| (p.prayer.as_str(), counts(&p.counts))).collect();
+        assert_eq!(
+            prayers,
+            vec![
+                ("Fajr", (1, 0, 0, 1, 1)),
+                ("Dhuhr", (1, 0, 1, 0, 1)),
+                ("Asr", (1, 0, 0, 0, 2)),
+                ("Maghrib", (1, 0, 0, 0, 2)),
+                ("Isha", (0, 1, 0, 0, 2)),
+            ]
+        );
+        assert_eq!(counts(&stats.total), (4, 1, 1, 1, 8));
+    }
+
+    #[test]
+    fn monthly_stats_cover_the_calendar_month() {
+        let tracker = tracker();
+        tracker.record(date(2), "Fajr", PrayerStatus::OnTime).unwrap();
+        full_day(&tracker, 3);
+
+        let stats = tracker.stats(StatsPeriod::Month, date(15), date(31)).unwrap();
+        assert_eq!((stats.from, stats.to), (date(1), date(31)));
+        // Thirty days from the 2nd, five prayers each, six logged
+        assert_eq!(counts(&stats.total), (6, 0, 0, 0, 144));
+
+        let december = NaiveDate::from_ymd_opt(2024, 12, 15).unwrap();
+        let stats = tracker.stats(StatsPeriod::Month, december, date(31)).unwrap();
+        assert_eq!(
+            (stats.from, stats.to),
+            (NaiveDate::from_ymd_opt(2024, 12, 1).unwrap(), NaiveDate::from_ymd_opt(2024, 12, 31).unwrap())
+        );
+        assert_eq!(counts(&stats.total), (0, 0, 0, 0, 0));
+    }
+
+    #[test]
+    fn qada_balance_adds_backlog_and_missed_minus_made_up() {
+        let tracker = tracker();
+        tracker.set_qada_backlog("Fajr", 10).unwrap();
+        tracker.record(date(4), "Fajr", PrayerStatus::Missed).unwrap();
+        tracker.record_qada("Fajr", 3).unwrap();
+        tracker.undo_qada("Fajr", 1).unwrap();
+        tracker.record_qada("Isha", 2).unwrap();
+
+        let balance: Vec<_> = tracker
+            .qada_balance()
+            .unwrap()
+            .into_iter()
+            .map(|b| (b.prayer, b.backlog, b.missed, b.made_up, b.outstanding))
+            .collect();
+        assert_eq!(
+            balance,
+            vec![
+                ("Fajr".to_string(), 10, 1, 2, 9),
+                ("Dhuhr".to_string(), 0, 0, 0, 0),
+                ("Asr".to_string(), 0, 0, 0, 0),
+                ("Maghrib".to_string(), 0, 0, 0, 0),
+                ("Isha".to_string(), 0, 0, 2, 0),
+            ]
+        );
+
+        assert!(matches!(tracker.set_qada_backlog("Witr", 1), Err(AppError::InvalidInput(_))));
+        assert!(matches!(tracker.record_qada("Witr", 1), Err(AppError::InvalidInput(_))));
+    }
+
+    #[test]
+    fn csv_export_lists_every_entry() {
+        let tracker = tracker();
+        tracker.record(date(3), "Fajr", PrayerStatus::OnTime).unwrap();
+        tracker.record(date(3), "Dhuhr", PrayerStatus::Congregation).unwrap();
+        tracker.record(date(2), "Isha", PrayerStatus::Missed).unwrap();
+
+        let path = std::env::temp_dir().join(format!("muezzin-tracker-{}.csv", std::process::id()));
+        tracker.export_csv(&path).unwrap();
+        let csv = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let lines: Vec<_> = csv.lines().collect();
+        assert_eq!(lines[0], "date,prayer,status,recorded_at");
+        let rows: Vec<_> = lines[1..].iter().map(|l| l.rsplit_once(',').unwrap().0).collect();
+        assert_eq!(rows, vec!["2025-03-02,Isha,missed", "2025-03-03,Fajr,onTime", "2025-03-03,Dhuhr,congregation"]);
+        for line in &lines[1..] {
+            assert!(DateTime::parse_from_rfc3339(line.rsplit_once(',').unwrap().1).is_ok());
+        }
+    }
+
+    #[test]
+    fn marked_prayers_are_on_time_while_their_window_is_open() {
+        let times = PrayerTimes {
+            fajr: at(4, 5, 30),
+            sunrise: at(4, 7, 0),
+            dhuhr: at(4, 12, 45),
+            asr: at(4, 16, 0),
+            maghrib: at(4, 18, 40),
+            isha: at(4, 20, 5),
+        };
+        assert_eq!(status_for_mark("Fajr", &times, at(4, 6, 59)), (date(4), PrayerStatus::OnTime));
+        assert_eq!(status_for_mark("Fajr", &times, at(4, 7, 0)), (date(4), PrayerStatus::Late));
+        assert_eq!(status_for_mark("Asr", &times, at(4, 19, 0)), (date(4), PrayerStatus::Late));
+        assert_eq!(status_for_mark("Isha", &times, at(4, 23, 0)), (date(4), PrayerStatus::OnTime));
+        // Isha after midnight belongs to the night before
+        assert_eq!(status_for_mark("Isha", &times, at(4, 1, 0)), (date(3), PrayerStatus::OnTime));
+    }
+}
@@ -1,12 +1,88 @@
-use tauri::{AppHandle, Manager, tray::{TrayIconBuilder, TrayIconEvent}, menu::{MenuBuilder, MenuItemBuilder}};
+use chrono::{DateTime, Local};
+use tauri::{AppHandle, Emitter, Manager, Wry, tray::{TrayIconBuilder, TrayIconEvent}, menu::{CheckMenuItemBuilder, Menu, MenuBuilder, MenuItemBuilder, SubmenuBuilder}};
+use crate::AppState;
 use crate::error::Result;
+use crate::i18n;
+use crate::mute;
+use crate::models::{MuteRequest, PrayerStatus};
+use crate::tracker::{self, PRAYERS};
 
-pub fn setup_tray(app: &AppHandle) -> Result<()> {
-    let quit_item = MenuItemBuilder::with_id("quit", "Quit").build(app)?;
-    let open_item = MenuItemBuilder::with_id("open", "Open").build(app)?;
-    let settings_item = MenuItemBuilder::with_id("settings", "Settings").build(app)?;
-    
-    let menu = MenuBuilder::new(app)
+const TRAY_ID: &str = "main";
+const PROFILE_PREFIX: &str = "profile:";
+const PRAYED_PREFIX: &str = "prayed:";
+
+fn build_menu(app: &AppHandle) -> Result<Menu<Wry>> {
+    let state = app.state::<AppState>();
+    let settings = state.storage.get_settings()?;
+    let mut menu = MenuBuilder::new(app);
+
+    // Today's times, the upcoming one marked
+    let calculator = state.calculator.read();
+    let times = calculator.calculate_prayer_times(Local::now()).ok();
+    let next = calculator.get_next_prayer().ok();
+    drop(calculator);
+
+    if let Some(times) = times {
+        let entries = [
+            ("Fajr", times.fajr),
+            ("Sunrise", times.sunrise),
+            ("Dhuhr", times.dhuhr),
+            ("Asr", times.asr),
+            ("Maghrib", times.maghrib),
+            ("Isha", times.isha),
+        ];
+        for (name, time) in entries {
+            let is_next = next.as_ref().is_some_and(|(n, t)| n == name && *t == time);
+            let marker = if is_next { "▸ " } else { "   " };
+            let label = format!("{}{}  {}", marker, i18n::prayer_name(name), i18n::localize_digits(&time.format("%H:%M").to_string(), &i18n::language()));
+            let item = MenuItemBuilder::with_id(format!("time:{}", name), label)
+                .enabled(false)
+                .build(app)?;
+            menu = menu.item(&item);
+        }
+        menu = menu.separator();
+    }
+
+    let muted = settings.muted_until.is_some_and(|until| until > Local::now());
+    let stop_item = MenuItemBuilder::with_id("stop-adhan", i18n::t("tray.stopAdhan")).build(app)?;
+    let play_item = MenuItemBuilder::with_id("play-adhan", i18n::t("tray.playAdhan")).build(app)?;
+    let mute_item = CheckMenuItemBuilder::with_id("mute-today", i18n::t("tray.muteToday"))
+        .checked(muted)
+        .build(app)?;
+    menu = menu.item(&stop_item).item(&play_item).item(&mute_item);
+
+    // Today's prayers, ticked once logged as prayed
+    let today = Local::now().date_naive();
+    let logged = state.tracker.entries(today, today)?;
+    let mut prayed_menu = SubmenuBuilder::new(app, i18n::t("action.markPrayed"));
+    for prayer in PRAYERS {
+        let prayed = logged.iter().any(|e| e.prayer == prayer && e.status != PrayerStatus::Missed);
+        let item = CheckMenuItemBuilder::with_id(format!("{}{}", PRAYED_PREFIX, prayer), i18n::prayer_name(prayer))
+            .checked(prayed)
+            .build(app)?;
+        prayed_menu = prayed_menu.item(&item);
+    }
+    menu = menu.item(&prayed_menu.build()?);
+
+    let profiles = state.storage.get_location_profiles()?;
+    if !profiles.is_empty() {
+        let active = state.storage.get_active_profile();
+        let mut submenu = SubmenuBuilder::new(app, i18n::t("tray.location"));
+        for profile in &profiles {
+            let item = CheckMenuItemBuilder::with_id(format!("{}{}", PROFILE_PREFIX, profile.name), &profile.name)
+                .checked(active.as_deref() == Some(profile.name.as_str()))
+                .build(app)?;
+            submenu = submenu.item(&item);
+        }
+        menu = menu.item(&submenu.build()?);
+    }
+
+    let quit_item = MenuItemBuilder::with_id("quit", i18n::t("tray.quit")).build(app)?;
+    let open_item = MenuItemBuilder::with_id("open", i18n::t("tray.open")).build(app)?;
+    let settings_item = MenuItemBuilder::with_id("settings", i18n::t("tray.settings")).build(app)?;
+
+    let menu = menu
+        .separator()
         .item(&open_item)
         .separator()
         .item(&settings_item)
@@ -14,7 +90,13 @@ pub fn setup_tray(app: &AppHandle) -> Result<()> {
         .item(&quit_item)
         .build()?;
 
-    let _tray = TrayIconBuilder::new()
+    Ok(menu)
+}
+
+pub fn setup_tray(app: &AppHandle) -> Result<()> {
+    let menu = build_menu(app)?;
+
+    let _tray = TrayIconBuilder::with_id(TRAY_ID)
         .menu(&menu)
         .icon(app.default_window_icon().unwrap().clone())
         .on_menu_event(move |app, event| {
@@ -29,15 +111,48 @@ pub fn setup_tray(app: &AppHandle) -> Result<()> {
                     }
                 }
                 "settings" => {
-                    if let Some(window) = app.get_webview_window("main") {
+                    // Settings stay out of reach behind a locked display
+                    let locked = crate::commands::ensure_unlocked(&app.state::<AppState>()).is_err();
+                    if let Some(window) = app.get_webview_window("main").filter(|_| !locked) {
                         let _ = window.show();
                         let _ = window.set_focus();
                         // Emit event to navigate to settings
                         let _ = window.emit("navigate-to-settings", ());
                     }
                 }
+                "stop-adhan" => {
+                    app.state::<AppState>().audio_player.write().stop();
+                }
+                "play-adhan" => {
+                    if let Err(e) = crate::commands::play_configured_adhan(&app.state::<AppState>(), None) {
+                        eprintln!("Error playing adhan: {}", e);
+                    }
+                }
+                "mute-today" => {
+                    if let Err(e) = toggle_mute_today(app) {
+                        eprintln!("Error toggling mute: {}", e);
+                    }
+                }
+                id if id.starts_with(PRAYED_PREFIX) => {
+                    if let Err(e) = toggle_prayed(app, &id[PRAYED_PREFIX.len()..]) {
+                        eprintln!("Error marking prayer: {}", e);
+                    }
+                }
+                id if id.starts_with(PROFILE_PREFIX) => {
+                    let state = app.state::<AppState>();
+                    match crate::commands::apply_location_profile(&state, &id[PROFILE_PREFIX.len()..]) {
+                        Ok(()) => {
+                            let _ = app.emit("prayers-updated", ());
+                        }
+                        Err(e) => eprintln!("Error switching location: {}", e),
+                    }
+                }
                 _ => {}
             }
+            // Keep check marks and times in sync with what was just changed
+            if let Err(e) = update_tray_menu(app) {
+                eprintln!("Error updating tray menu: {}", e);
+            }
         })
         .on_tray_icon_event(|_tray, event| {
             if let TrayIconEvent::Click { button, .. } = event {
@@ -52,8 +167,70 @@ pub fn setup_tray(app: &AppHandle) -> Result<()> {
     Ok(())
 }
 
-pub fn update_tray_menu(app: &AppHandle, prayer_times: &crate::models::PrayerTimes) -> Result<()> {
-    // TODO: Update tray menu with current prayer times
-    // This can be called when prayer times are updated
+/// Mutes the adhan until midnight, or unmutes if already muted
+fn toggle_mute_today(app: &AppHandle) -> Result<()> {
+    let state = app.state::<AppState>();
+    crate::commands::ensure_unlocked(&state)?;
+    let mut settings = state.storage.get_settings()?;
+    let now = Local::now();
+
+    settings.muted_until = if settings.muted_until.is_some_and(|until| until > now) {
+        None
+    } else {
+        Some(mute::mute_until(MuteRequest::Today, now, None)?)
+    };
+
+    state.storage.save_settings(&settings)?;
+    let _ = app.emit("settings-updated", &settings);
+    Ok(())
+}
+
+/// Marks one of today's prayers as prayed, or clears it when already ticked
+fn toggle_prayed(app: &AppHandle, prayer: &str) -> Result<()> {
+    let state = app.state::<AppState>();
+    let today = Local::now().date_naive();
+    let prayed = state
+        .tracker
+        .entries(today, today)?
+        .iter()
+        .any(|e| e.prayer == prayer && e.status != PrayerStatus::Missed);
+
+    if prayed {
+        state.tracker.clear(today, prayer)?;
+        let _ = app.emit("prayer-unmarked", serde_json::json!({ "date": today, "prayer": prayer }));
+    } else {
+        // The menu lists today's prayers, so only the status follows the clock
+        let now = Local::now();
+        let times = state.calculator.read().calculate_prayer_times(now)?;
+        let (_, status) = tracker::status_for_mark(prayer, &times, now);
+        let entry = state.tracker.record(today, prayer, status)?;
+        let _ = app.emit("prayer-marked", &entry);
+    }
+    Ok(())
+}
+
+/// Rebuilds the menu from today's times, the settings and the current language.
+/// Called at each prayer, at midnight and whenever those inputs change.
+pub fn update_tray_menu(app: &AppHandle) -> Result<()> {
+    if let Some(tray) = app.tray_by_id(TRAY_ID) {
+        tray.set_menu(Some(build_menu(app)?))?;
+    }
+    Ok(())
+}
+
+/// Shows "Asr in 1h 12m" as the tooltip, and as the title next to the icon
+/// on macOS and on Linux desktops that show one
+pub fn update_tray_countdown(app: &AppHandle, prayer: &str, time: DateTime<Local>) -> Result<()> {
+    if let Some(tray) = app.tray_by_id(TRAY_ID) {
+        // Round up so "in 0m" never shows before the adhan
+        let minutes = ((time - Local::now()).num_seconds() + 59) / 60;
+        let text = i18n::t_with("tray.countdown", &[
+            ("prayer", i18n::prayer_name(prayer)),
+            ("duration", i18n::format_duration(minutes)),
+        ]);
+        tray.set_tooltip(Some(&text))?;
+        #[cfg(any(target_os = "macos", target_os = "linux"))]
+        tray.set_title(Some(&text))?;
+    }
     Ok(())
 }
@@ -0,0 +1,391 @@
+//! Checks run before settings are written, so a bad value is rejected with the
+//! field it came from instead of being ignored later by the calculator.
+//!
+//! Field paths follow the serialized names, e.g. `reminder_times.fajr` or
+//! `quiet_windows[1].start`, so the settings page can highlight the input.
+
+use chrono::NaiveTime;
+use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+
+use crate::i18n;
+use crate::models::{AppSettings, CustomTimes, Hook, HookAction, JumuahTime, MultiRoomRole};
+use crate::error::{AppError, Result};
+
+pub const CALC_METHODS: &[&str] = &[
+    "MWL", "Egyptian", "Karachi", "UAQ", "Dubai", "Qatar", "Kuwait", "MC", "Singapore", "Turkey", "Tehran", "ISNA",
+];
+pub const MADHABS: &[&str] = &["Shafi", "Hanafi"];
+
+/// Reminders and Iqamah offsets are minutes around a prayer
+const MAX_OFFSET_MINUTES: u32 = 120;
+const MAX_ADJUSTMENT_MINUTES: i32 = 60;
+const MAX_SNOOZE_MINUTES: u32 = 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FieldErrorCode {
+    InvalidTime,
+    OutOfRange,
+    UnknownValue,
+    Empty,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FieldError {
+    pub field: String,
+    pub code: FieldErrorCode,
+    pub message: String,
+}
+
+#[derive(Default)]
+struct Checker {
+    errors: Vec<FieldError>,
+}
+
+impl Checker {
+    fn push(&mut self, field: &str, code: FieldErrorCode, message: String) {
+        self.errors.push(FieldError { field: field.to_string(), code, message });
+    }
+
+    fn time(&mut self, field: &str, value: &str) {
+        if NaiveTime::parse_from_str(value, "%H:%M").is_err() {
+            self.push(field, FieldErrorCode::InvalidTime, format!("'{}' is not a HH:MM time", value));
+        }
+    }
+
+    fn minutes(&mut self, field: &str, value: u32, min: u32, max: u32) {
+        if !(min..=max).contains(&value) {
+            self.push(field, FieldErrorCode::OutOfRange, format!("{} must be between {} and {} minutes", value, min, max));
+        }
+    }
+
+    fn range(&mut self, field: &str, value: u32, min: u32, max: u32) {
+        if !(min..=max).contains(&value) {
+            self.push(field, FieldErrorCode::OutOfRange, format!("{} must be between {} and {}", value, min, max));
+        }
+    }
+
+    fn one_of(&mut self, field: &str, value: &str, allowed: &[&str]) {
+        if !allowed.contains(&value) {
+            self.push(field, FieldErrorCode::UnknownValue, format!("Unknown value '{}'", value));
+        }
+    }
+
+    fn not_empty(&mut self, field: &str, value: &str) {
+        if value.trim().is_empty() {
+            self.push(field, FieldErrorCode::Empty, "Must not be empty".to_string());
+        }
+    }
+
+    fn finish(self) -> Result<()> {
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(AppError::Validation(self.errors))
+        }
+    }
+}
+
+pub fn validate_settings(settings: &AppSettings) -> Result<()> {
+    let mut check = Checker::default();
+
+    check.not_empty("adhan_path", &settings.adhan_path);
+    if let Some(ref path) = settings.adhan_fajr_path {
+        check.not_empty("adhan_fajr_path", path);
+    }
+    check.one_of("language", &settings.language, i18n::LANGUAGES);
+    check.minutes("snooze_minutes", settings.snooze_minutes, 1, MAX_SNOOZE_MINUTES);
+
+    let calc = &settings.calculation;
+    check.one_of("calculation.calc_method", &calc.calc_method, CALC_METHODS);
+    check.one_of("calculation.madhab", &calc.madhab, MADHABS);
+    if let Some(ref adj) = calc.adjustments {
+        for (prayer, offset) in [
+            ("fajr", adj.fajr),
+            ("dhuhr", adj.dhuhr),
+            ("asr", adj.asr),
+            ("maghrib", adj.maghrib),
+            ("isha", adj.isha),
+        ] {
+            if offset.abs() > MAX_ADJUSTMENT_MINUTES {
+                check.push(
+                    &format!("calculation.adjustments.{}", prayer),
+                    FieldErrorCode::OutOfRange,
+                    format!("{} must be between -{} and {} minutes", offset, MAX_ADJUSTMENT_MINUTES, MAX_ADJUSTMENT_MINUTES),
+                );
+            }
+        }
+    }
+
+    if let Some(ref reminders) = settings.reminder_times {
+        for (prayer, minutes) in [
+            ("fajr", reminders.fajr),
+            ("dhuhr", reminders.dhuhr),
+            ("asr", reminders.asr),
+            ("maghrib", reminders.maghrib),
+            ("isha", reminders.isha),
+            ("jumuah", reminders.jumuah),
+        ] {
+            check.minutes(&format!("reminder_times.{}", prayer), minutes, 0, MAX_OFFSET_MINUTES);
+        }
+    }
+
+    if let Some(ref iqamah) = settings.iqamah {
+        for (prayer, minutes) in [
+            ("fajr", iqamah.fajr),
+            ("dhuhr", iqamah.dhuhr),
+            ("asr", iqamah.asr),
+            ("maghrib", iqamah.maghrib),
+            ("isha", iqamah.isha),
+            ("jumuah", iqamah.jumuah),
+        ] {
+            check.minutes(&format!("iqamah.{}", prayer), minutes, 0, MAX_OFFSET_MINUTES);
+        }
+    }
+
+    if let Some(ref bg) = settings.bg_image {
+        if bg.enabled {
+            check.not_empty("bg_image.path", &bg.path);
+        }
+    }
+
+    for (i, window) in settings.quiet_windows.iter().enumerate() {
+        check.time(&format!("quiet_windows[{}].start", i), &window.start);
+        check.time(&format!("quiet_windows[{}].end", i), &window.end);
+        if window.start == window.end {
+            check.push(
+                &format!("quiet_windows[{}].end", i),
+                FieldErrorCode::OutOfRange,
+                format!("Quiet window {}-{} is empty", window.start, window.end),
+            );
+        }
+    }
+
+    let api = &settings.http_api;
+    if api.bind_address.parse::<IpAddr>().is_err() {
+        check.push(
+            "http_api.bind_address",
+            FieldErrorCode::UnknownValue,
+            format!("'{}' is not an IP address", api.bind_address),
+        );
+    }
+    // Ports below 1024 need root on most systems
+    if api.port < 1024 {
+        check.push("http_api.port", FieldErrorCode::OutOfRange, format!("{} must be between 1024 and 65535", api.port));
+    }
+
+    let mqtt = &settings.mqtt;
+    if mqtt.enabled {
+        check.not_empty("mqtt.host", &mqtt.host);
+        check.not_empty("mqtt.client_id", &mqtt.client_id);
+    }
+    if mqtt.port == 0 {
+        check.push("mqtt.port", FieldErrorCode::OutOfRange, "0 must be between 1 and 65535".to_string());
+    }
+    for (field, topic) in [("mqtt.base_topic", &mqtt.base_topic), ("mqtt.discovery_prefix", &mqtt.discovery_prefix)] {
+        check.not_empty(field, topic);
+        // Wildcards are only valid in subscriptions
+        if topic.contains(['+', '#']) {
+            check.push(field, FieldErrorCode::UnknownValue, format!("'{}' can't contain + or #", topic));
+        }
+    }
+
+    let room = &settings.multi_room;
+    if room.role != MultiRoomRole::Off {
+        check.not_empty("multi_room.room_name", &room.room_name);
+    }
+    if room.port < 1024 {
+        check.push("multi_room.port", FieldErrorCode::OutOfRange, format!("{} must be between 1024 and 65535", room.port));
+    }
+    if !room.leader_address.is_empty()
+        && room.leader_address.rsplit_once(':').and_then(|(_, port)| port.parse::<u16>().ok()).is_none()
+    {
+        check.push(
+            "multi_room.leader_address",
+            FieldErrorCode::UnknownValue,
+            format!("'{}' is not a host:port address", room.leader_address),
+        );
+    }
+
+    let kiosk = &settings.kiosk;
+    check.range("kiosk.announcement_secs", kiosk.announcement_secs, 3, 600);
+    for (i, announcement) in kiosk.announcements.iter().enumerate() {
+        check.not_empty(&format!("kiosk.announcements[{}]", i), announcement);
+    }
+
+    for (i, hook) in settings.hooks.iter().enumerate() {
+        check_hook(&mut check, &format!("hooks[{}]", i), hook);
+    }
+
+    check.finish()
+}
+
+/// Longest a hook may run per attempt, and how often it may be retried
+const MAX_HOOK_TIMEOUT_SECS: u32 = 300;
+const MAX_HOOK_RETRIES: u32 = 5;
+
+fn check_hook(check: &mut Checker, field: &str, hook: &Hook) {
+    check.not_empty(&format!("{}.name", field), &hook.name);
+    if hook.events.is_empty() {
+        check.push(&format!("{}.events", field), FieldErrorCode::Empty, "Must not be empty".to_string());
+    }
+    match hook.action {
+        HookAction::Http { ref url, .. } => {
+            if !url.starts_with("http://") && !url.starts_with("https://") {
+                check.push(&format!("{}.action.url", field), FieldErrorCode::UnknownValue, format!("'{}' is not an http(s) URL", url));
+            }
+        }
+        HookAction::Command { ref program, .. } => check.not_empty(&format!("{}.action.program", field), program),
+    }
+    check.range(&format!("{}.timeout_secs", field), hook.timeout_secs, 1, MAX_HOOK_TIMEOUT_SECS);
+    check.range(&format!("{}.retries", field), hook.retries, 0, MAX_HOOK_RETRIES);
+}
+
+pub fn validate_hook(hook: &Hook) -> Result<()> {
+    let mut check = Checker::default();
+    check_hook(&mut check, "hook", hook);
+    check.finish()
+}
+
+pub fn validate_custom_times(custom_times: &CustomTimes) -> Result<()> {
+    let mut check = Checker::default();
+    for (field, time) in [
+        ("fajr", &custom_times.fajr),
+        ("dhuhr", &custom_times.dhuhr),
+        ("asr", &custom_times.asr),
+        ("maghrib", &custom_times.maghrib),
+        ("isha", &custom_times.isha),
+    ] {
+        // An empty input means the calculated time is kept
+        if let Some(time) = time.as_deref().filter(|t| !t.is_empty()) {
+            check.time(field, time);
+        }
+    }
+    check.finish()
+}
+
+pub fn validate_jumuah_time(jumuah_time: &JumuahTime) -> Result<()> {
+    let mut check = Checker::default();
+    check.time("time", &jumuah_time.time);
+    check.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Adjustments, IqamahTimes, QuietWindow, ReminderTimes};
+    use serde_json::{json, Value};
+
+    /// `(field, code)` of each error, as the settings page receives them
+    fn errors(result: Result<()>) -> Vec<(String, String)> {
+        let error = result.expect_err("validation should fail");
+        assert!(matches!(error, AppError::Validation(_)));
+        let value = serde_json::to_value(error).unwrap();
+        value["details"]["errors"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|e| (e["field"].as_str().unwrap().to_string(), e["code"].as_str().unwrap().to_string()))
+            .collect()
+    }
+
+    fn expected(fields: &[(&str, &str)]) -> Vec<(String, String)> {
+        fields.iter().map(|&(field, code)| (field.to_string(), code.to_string())).collect()
+    }
+
+    fn quiet_window(start: &str, end: &str) -> QuietWindow {
+        QuietWindow { enabled: true, name: None, days: Vec::new(), start: start.to_string(), end: end.to_string() }
+    }
+
+    #[test]
+    fn default_settings_are_valid() {
+        assert!(validate_settings(&AppSettings::default()).is_ok());
+    }
+
+    #[test]
+    fn bad_times_name_their_field() {
+        let settings = AppSettings {
+            quiet_windows: vec![quiet_window("22:00", "06:00"), quiet_window("25:00", "7am"), quiet_window("12:00", "12:00")],
+            ..AppSettings::default()
+        };
+        assert_eq!(
+            errors(validate_settings(&settings)),
+            expected(&[
+                ("quiet_windows[1].start", "invalidTime"),
+                ("quiet_windows[1].end", "invalidTime"),
+                ("quiet_windows[2].end", "outOfRange"),
+            ])
+        );
+
+        let custom_times = CustomTimes {
+            enabled: true,
+            fajr: Some("5:30".to_string()),
+            dhuhr: Some(String::new()),
+            asr: Some("16:60".to_string()),
+            maghrib: None,
+            isha: Some("20:15:00".to_string()),
+        };
+        assert_eq!(
+            errors(validate_custom_times(&custom_times)),
+            expected(&[("asr", "invalidTime"), ("isha", "invalidTime")])
+        );
+
+        assert!(validate_jumuah_time(&JumuahTime { enabled: true, time: "13:15".to_string() }).is_ok());
+        assert_eq!(
+            errors(validate_jumuah_time(&JumuahTime { enabled: true, time: "1:15 pm".to_string() })),
+            expected(&[("time", "invalidTime")])
+        );
+    }
+
+    #[test]
+    fn offsets_out_of_range() {
+        let mut settings = AppSettings {
+            reminder_times: Some(ReminderTimes { enabled: true, fajr: 121, dhuhr: 120, asr: 0, maghrib: 10, isha: 10, jumuah: 500 }),
+            iqamah: Some(IqamahTimes { enabled: true, fajr: 20, dhuhr: 15, asr: 15, maghrib: 180, isha: 15, jumuah: 30 }),
+            snooze_minutes: 0,
+            ..AppSettings::default()
+        };
+        settings.calculation.adjustments = Some(Adjustments { fajr: -60, dhuhr: 0, asr: 61, maghrib: 0, isha: -90 });
+
+        assert_eq!(
+            errors(validate_settings(&settings)),
+            expected(&[
+                ("snooze_minutes", "outOfRange"),
+                ("calculation.adjustments.asr", "outOfRange"),
+                ("calculation.adjustments.isha", "outOfRange"),
+                ("reminder_times.fajr", "outOfRange"),
+                ("reminder_times.jumuah", "outOfRange"),
+                ("iqamah.maghrib", "outOfRange"),
+            ])
+        );
+    }
+
+    #[test]
+    fn unknown_method_and_madhab() {
+        let mut settings = AppSettings::default();
+        settings.calculation.calc_method = "UOIF".to_string();
+        settings.calculation.madhab = "Maliki".to_string();
+        settings.language = "xx".to_string();
+
+        let error = validate_settings(&settings).unwrap_err();
+        let value = serde_json::to_value(error).unwrap();
+        let details: Vec<Value> = value["details"]["errors"].as_array().unwrap().clone();
+        assert_eq!(
+            details,
+            vec![
+                json!({ "field": "language", "code": "unknownValue", "message": "Unknown value 'xx'" }),
+                json!({ "field": "calculation.calc_method", "code": "unknownValue", "message": "Unknown value 'UOIF'" }),
+                json!({ "field": "calculation.madhab", "code": "unknownValue", "message": "Unknown value 'Maliki'" }),
+            ]
+        );
+
+        for method in CALC_METHODS {
+            settings.calculation.calc_method = method.to_string();
+            settings.calculation.madhab = "Hanafi".to_string();
+            settings.language = "en".to_string();
+            assert!(validate_settings(&settings).is_ok(), "{}", method);
+        }
+    }
+}
@@ -0,0 +1,84 @@
+//! Runs a leader and a follower `muezzin-daemon` side by side and checks the
+//! follower connects, then notices when the leader is killed.
+
+use std::fs;
+use std::net::TcpListener;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command};
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+/// Kills the daemon when a test fails halfway
+struct Daemon(Child);
+
+impl Drop for Daemon {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+fn free_port() -> u16 {
+    TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port()
+}
+
+fn spawn(dir: &Path, name: &str, multi_room: serde_json::Value) -> (Daemon, PathBuf) {
+    let settings = dir.join(format!("{}.json", name));
+    let store = serde_json::json!({
+        "latitude": 51.5074,
+        "longitude": -0.1278,
+        "timezone": "Europe/London",
+        "settings": { "multi_room": multi_room },
+    });
+    fs::write(&settings, store.to_string()).unwrap();
+
+    let log = dir.join(format!("{}.log", name));
+    let child = Command::new(env!("CARGO_BIN_EXE_muezzin-daemon"))
+        .arg("--settings")
+        .arg(&settings)
+        .arg("--log-file")
+        .arg(&log)
+        .arg("--no-audio")
+        .spawn()
+        .unwrap();
+    (Daemon(child), log)
+}
+
+fn wait_for_log(log: &Path, text: &str) {
+    let started = Instant::now();
+    while started.elapsed() < Duration::from_secs(30) {
+        if fs::read_to_string(log).unwrap_or_default().contains(text) {
+            return;
+        }
+        sleep(Duration::from_millis(100));
+    }
+    panic!("no '{}' in {}:\n{}", text, log.display(), fs::read_to_string(log).unwrap_or_default());
+}
+
+#[test]
+fn follower_tracks_a_leader_daemon() {
+    let dir = std::env::temp_dir().join(format!("muezzin-multi-room-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let port = free_port();
+
+    let (mut leader, leader_log) = spawn(
+        &dir,
+        "leader",
+        serde_json::json!({ "role": "leader", "room_name": "Main hall", "port": port }),
+    );
+    wait_for_log(&leader_log, "Leading as Main hall");
+
+    let (_follower, follower_log) = spawn(
+        &dir,
+        "follower",
+        serde_json::json!({ "role": "follower", "leader_address": format!("127.0.0.1:{}", port) }),
+    );
+    wait_for_log(&follower_log, "Following Main hall");
+    wait_for_log(&leader_log, "(1 following)");
+
+    leader.0.kill().unwrap();
+    let _ = leader.0.wait();
+    wait_for_log(&follower_log, "Leader Main hall unreachable");
+
+    let _ = fs::remove_dir_all(&dir);
+}
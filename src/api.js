@@ -13,6 +13,22 @@ export async function updateLocation(lat, lon, timezone) {
     return await invoke('update_location', { lat, lon, timezone });
 }
 
+export async function getLocationProfiles() {
+    return await invoke('get_location_profiles');
+}
+
+export async function saveLocationProfile(profile) {
+    return await invoke('save_location_profile', { profile });
+}
+
+export async function deleteLocationProfile(name) {
+    return await invoke('delete_location_profile', { name });
+}
+
+export async function switchLocationProfile(name) {
+    return await invoke('switch_location_profile', { name });
+}
+
 export async function getSettings() {
     return await invoke('get_settings');
 }
@@ -29,6 +45,22 @@ export async function stopAdhan() {
     return await invoke('stop_adhan');
 }
 
+export async function pauseAdhan() {
+    return await invoke('pause_adhan');
+}
+
+export async function resumeAdhan() {
+    return await invoke('resume_adhan');
+}
+
+export async function seekAudio(position) {
+    return await invoke('seek_audio', { position });
+}
+
+export async function getAudioProgress() {
+    return await invoke('get_audio_progress');
+}
+
 export async function setVolume(volume) {
     return await invoke('set_volume', { volume });
 }
@@ -37,14 +69,138 @@ export async function isAudioPlaying() {
     return await invoke('is_audio_playing');
 }
 
+export async function muteAdhan(request) {
+    return await invoke('mute_adhan', { request });
+}
+
+export async function unmuteAdhan() {
+    return await invoke('unmute_adhan');
+}
+
+export async function getMuteStatus() {
+    return await invoke('get_mute_status');
+}
+
+export async function getHttpApiStatus() {
+    return await invoke('get_http_api_status');
+}
+
+export async function regenerateHttpApiToken() {
+    return await invoke('regenerate_http_api_token');
+}
+
+export async function getHookLog() {
+    return await invoke('get_hook_log');
+}
+
+export async function testHook(hook) {
+    return await invoke('test_hook', { hook });
+}
+
+export async function getMqttStatus() {
+    return await invoke('get_mqtt_status');
+}
+
+export async function getMultiRoomStatus() {
+    return await invoke('get_multi_room_status');
+}
+
+export async function getKioskDisplay() {
+    return await invoke('get_kiosk_display');
+}
+
+export async function enterKiosk() {
+    return await invoke('enter_kiosk');
+}
+
+export async function exitKiosk(pin = '') {
+    return await invoke('exit_kiosk', { pin });
+}
+
+export async function setKioskPin(pin) {
+    return await invoke('set_kiosk_pin', { pin });
+}
+
 export async function getQiblaDirection() {
     return await invoke('get_qibla_direction');
 }
 
+export async function notificationAction(action, prayer) {
+    return await invoke('notification_action', { action, prayer });
+}
+
+export async function recordPrayer(date, prayer, status) {
+    return await invoke('record_prayer', { date, prayer, status });
+}
+
+export async function clearPrayerRecord(date, prayer) {
+    return await invoke('clear_prayer_record', { date, prayer });
+}
+
+export async function getPrayerLog(from, to) {
+    return await invoke('get_prayer_log', { from, to });
+}
+
+export async function getPrayerStreak() {
+    return await invoke('get_prayer_streak');
+}
+
+export async function getPrayerStats(period, date = null) {
+    return await invoke('get_prayer_stats', { period, date });
+}
+
+export async function getQadaBalance() {
+    return await invoke('get_qada_balance');
+}
+
+export async function setQadaBacklog(prayer, count) {
+    return await invoke('set_qada_backlog', { prayer, count });
+}
+
+export async function recordQada(prayer, count = 1) {
+    return await invoke('record_qada', { prayer, count });
+}
+
+export async function undoQada(prayer, count = 1) {
+    return await invoke('undo_qada', { prayer, count });
+}
+
+export async function exportPrayerLog(path) {
+    return await invoke('export_prayer_log', { path });
+}
+
 export async function getNextPrayer() {
     return await invoke('get_next_prayer');
 }
 
+export async function exportSettingsBundle(path) {
+    return await invoke('export_settings_bundle', { path });
+}
+
+export async function previewSettingsBundle(path) {
+    return await invoke('preview_settings_bundle', { path });
+}
+
+export async function importSettingsBundle(path) {
+    return await invoke('import_settings_bundle', { path });
+}
+
+export async function findElectronConfig() {
+    return await invoke('find_electron_config');
+}
+
+export async function importElectronSettings(path = null, dryRun = false) {
+    return await invoke('import_electron_settings', { path, dryRun });
+}
+
+export async function getStorageReport() {
+    return await invoke('get_storage_report');
+}
+
+export async function resetStorageValue(key) {
+    return await invoke('reset_storage_value', { key });
+}
+
 export async function checkForUpdates() {
     return await invoke('check_for_updates');
 }
@@ -61,6 +217,110 @@ export async function updateJumuahTime(jumuahTime) {
     return await invoke('update_jumuah_time', { jumuahTime });
 }
 
+export async function getQuranChapters() {
+    return await invoke('get_quran_chapters');
+}
+
+export async function getSurah(chapter) {
+    return await invoke('get_surah', { chapter });
+}
+
+export async function getVerses(chapter, start, end) {
+    return await invoke('get_verses', { chapter, start, end });
+}
+
+export async function searchQuran(query, scope, limit) {
+    return await invoke('search_quran', { query, scope, limit });
+}
+
+export async function getTranslationCatalog() {
+    return await invoke('get_translation_catalog');
+}
+
+export async function getInstalledPacks() {
+    return await invoke('get_installed_packs');
+}
+
+export async function downloadQuranPack(kind, resourceId) {
+    return await invoke('download_quran_pack', { kind, resourceId });
+}
+
+export async function importQuranPack(path) {
+    return await invoke('import_quran_pack', { path });
+}
+
+export async function removeQuranPack(slug) {
+    return await invoke('remove_quran_pack', { slug });
+}
+
+export async function getParallelVerses(chapter, start, end, packs) {
+    return await invoke('get_parallel_verses', { chapter, start, end, packs });
+}
+
+export async function getQuranProgress() {
+    return await invoke('get_quran_progress');
+}
+
+export async function toggleBookmark(chapter, verse, label) {
+    return await invoke('toggle_bookmark', { chapter, verse, label });
+}
+
+export async function setVerseNote(chapter, verse, text) {
+    return await invoke('set_verse_note', { chapter, verse, text });
+}
+
+export async function setLastRead(chapter, verse) {
+    return await invoke('set_last_read', { chapter, verse });
+}
+
+export async function createKhatmPlan(days, unit, reminder) {
+    return await invoke('create_khatm_plan', { days, unit, reminder });
+}
+
+export async function recordKhatmPages(pagesRead) {
+    return await invoke('record_khatm_pages', { pagesRead });
+}
+
+export async function cancelKhatmPlan() {
+    return await invoke('cancel_khatm_plan');
+}
+
+export async function getKhatmStatus() {
+    return await invoke('get_khatm_status');
+}
+
+export async function exportQuranProgress(path) {
+    return await invoke('export_quran_progress', { path });
+}
+
+export async function importQuranProgress(path, merge) {
+    return await invoke('import_quran_progress', { path, merge });
+}
+
+export async function getRecitations() {
+    return await invoke('get_recitations');
+}
+
+export async function playQuran(request) {
+    return await invoke('play_quran', { request });
+}
+
+export async function stopQuran() {
+    return await invoke('stop_quran');
+}
+
+export async function getQuranPlaybackState() {
+    return await invoke('get_quran_playback_state');
+}
+
+export async function downloadSurahAudio(reciterId, chapter) {
+    return await invoke('download_surah_audio', { reciterId, chapter });
+}
+
+export async function clearQuranAudioCache() {
+    return await invoke('clear_quran_audio_cache');
+}
+
 // Event listeners
 export function onPrayerTime(callback) {
     return listen('prayer-time', callback);
@@ -73,3 +333,39 @@ export function onReminderTime(callback) {
 export function onPrayersUpdated(callback) {
     return listen('prayers-updated', callback);
 }
+
+export function onPrayerNotification(callback) {
+    return listen('prayer-notification', callback);
+}
+
+export function onPrayerMarked(callback) {
+    return listen('prayer-marked', callback);
+}
+
+export function onPrayerUnmarked(callback) {
+    return listen('prayer-unmarked', callback);
+}
+
+export function onKioskMode(callback) {
+    return listen('kiosk-mode', callback);
+}
+
+export function onKioskDisplay(callback) {
+    return listen('kiosk-display', callback);
+}
+
+export function onAudioProgress(callback) {
+    return listen('audio-progress', callback);
+}
+
+export function onQuranVerseStarted(callback) {
+    return listen('quran-verse-started', callback);
+}
+
+export function onQuranVerseEnded(callback) {
+    return listen('quran-verse-ended', callback);
+}
+
+export function onQuranDownloadProgress(callback) {
+    return listen('quran-download-progress', callback);
+}
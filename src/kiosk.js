@@ -0,0 +1,151 @@
+// Mosque display: clock, adhan and Iqamah table, Iqamah countdown,
+// announcements and the Jumuah banner, full-screen on a TV
+import * as PrayerAPI from './api.js';
+
+let display = null;
+let clockInterval = null;
+let announcementInterval = null;
+let announcementIndex = 0;
+let unlisteners = [];
+
+function formatTime(value) {
+    return new Date(value).toLocaleTimeString([], { hour: '2-digit', minute: '2-digit' });
+}
+
+function formatCountdown(milliseconds) {
+    const total = Math.max(0, Math.floor(milliseconds / 1000));
+    const hours = Math.floor(total / 3600);
+    const minutes = Math.floor((total % 3600) / 60);
+    const seconds = String(total % 60).padStart(2, '0');
+    return hours > 0 ? `${hours}:${String(minutes).padStart(2, '0')}:${seconds}` : `${minutes}:${seconds}`;
+}
+
+function escapeHtml(text) {
+    const div = document.createElement('div');
+    div.textContent = text;
+    return div.innerHTML;
+}
+
+function render() {
+    const app = document.getElementById('app');
+    const nextName = display.next?.name;
+
+    const rows = display.prayers.map(prayer => `
+        <tr class="${prayer.name === nextName ? 'kiosk-next' : ''}">
+            <td>${prayer.name}</td>
+            <td>${formatTime(prayer.adhan)}</td>
+            <td>${prayer.iqamah ? formatTime(prayer.iqamah) : ''}</td>
+        </tr>
+    `).join('');
+
+    const jumuah = display.jumuah ? `
+        <div class="kiosk-jumuah">
+            <i class="fas fa-mosque me-2"></i>
+            Jumuah: khutbah ${formatTime(display.jumuah.khutbah)}${display.jumuah.iqamah ? `, Iqamah ${formatTime(display.jumuah.iqamah)}` : ''}
+            ${display.jumuah.banner ? `<div class="kiosk-jumuah-banner">${escapeHtml(display.jumuah.banner)}</div>` : ''}
+        </div>
+    ` : '';
+
+    app.innerHTML = `
+        <div class="kiosk">
+            <div class="kiosk-header">
+                <div id="kiosk-clock" class="kiosk-clock" title="Double-click to leave the display"></div>
+                <div class="kiosk-date">
+                    ${new Date(display.date + 'T12:00:00').toLocaleDateString([], { weekday: 'long', year: 'numeric', month: 'long', day: 'numeric' })}
+                    <div class="kiosk-hijri">${display.hijri}</div>
+                </div>
+            </div>
+            ${jumuah}
+            <table class="kiosk-table">
+                <thead><tr><th></th><th>Adhan</th><th>Iqamah</th></tr></thead>
+                <tbody>${rows}</tbody>
+            </table>
+            <div id="kiosk-countdown" class="kiosk-countdown"></div>
+            <div id="kiosk-announcement" class="kiosk-announcement"></div>
+        </div>
+    `;
+
+    document.getElementById('kiosk-clock').addEventListener('dblclick', leave);
+    tick();
+    showAnnouncement();
+}
+
+function tick() {
+    const clock = document.getElementById('kiosk-clock');
+    const countdown = document.getElementById('kiosk-countdown');
+    if (!clock || !countdown) return;
+
+    const now = new Date();
+    clock.textContent = now.toLocaleTimeString([], { hour: '2-digit', minute: '2-digit', second: '2-digit' });
+
+    // Iqamah first, it is what the people in the hall are waiting for
+    const awaiting = display.awaitingIqamah;
+    if (awaiting && new Date(awaiting.iqamah) > now) {
+        countdown.textContent = `${awaiting.name} Iqamah in ${formatCountdown(new Date(awaiting.iqamah) - now)}`;
+    } else if (display.next && new Date(display.next.adhan) > now) {
+        countdown.textContent = `${display.next.name} in ${formatCountdown(new Date(display.next.adhan) - now)}`;
+    } else {
+        // Passed a time the backend hasn't told us about yet
+        refresh();
+    }
+}
+
+function showAnnouncement() {
+    const element = document.getElementById('kiosk-announcement');
+    if (!element) return;
+    const announcements = display.announcements;
+    if (announcements.length === 0) {
+        element.textContent = '';
+        return;
+    }
+    announcementIndex %= announcements.length;
+    element.textContent = announcements[announcementIndex];
+}
+
+async function refresh() {
+    try {
+        display = await PrayerAPI.getKioskDisplay();
+        render();
+    } catch (error) {
+        console.error('Error loading the kiosk display:', error);
+    }
+}
+
+async function leave() {
+    const pin = display.locked ? window.prompt('PIN') : '';
+    if (pin === null) return;
+    try {
+        await PrayerAPI.exitKiosk(pin);
+    } catch (error) {
+        window.alert(error?.message ?? error);
+    }
+}
+
+export async function start(initial = null) {
+    stop();
+    display = initial ?? await PrayerAPI.getKioskDisplay();
+    render();
+
+    clockInterval = setInterval(tick, 1000);
+    announcementInterval = setInterval(() => {
+        announcementIndex++;
+        showAnnouncement();
+    }, display.announcementSecs * 1000);
+
+    unlisteners = await Promise.all([
+        PrayerAPI.onKioskDisplay(event => {
+            display = event.payload;
+            render();
+        }),
+        PrayerAPI.onPrayersUpdated(refresh),
+    ]);
+}
+
+export function stop() {
+    clearInterval(clockInterval);
+    clearInterval(announcementInterval);
+    clockInterval = null;
+    announcementInterval = null;
+    unlisteners.forEach(unlisten => unlisten());
+    unlisteners = [];
+}
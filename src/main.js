@@ -2,12 +2,14 @@ import { invoke } from '@tauri-apps/api/core';
 import { listen } from '@tauri-apps/api/event';
 import * as PrayerAPI from './api.js';
 import * as UI from './ui.js';
+import * as Kiosk from './kiosk.js';
 
 // Global state
 let prayerTimes = null;
 let settings = null;
 let nextPrayer = null;
 let countdownInterval = null;
+let kioskActive = false;
 
 // Initialize app
 async function init() {
@@ -22,6 +24,17 @@ async function init() {
         settings = await PrayerAPI.getSettings();
         console.log('Settings loaded:', settings);
 
+        // Setup event listeners
+        setupEventListeners();
+
+        const display = await PrayerAPI.getKioskDisplay();
+        if (display.active) {
+            kioskActive = true;
+            await Kiosk.start(display);
+            console.log('Muezzin initialized in kiosk mode');
+            return;
+        }
+
         // Load prayer times
         await loadPrayerTimes();
 
@@ -31,13 +44,10 @@ async function init() {
         // Start countdown
         startCountdown();
 
-        // Setup event listeners
-        setupEventListeners();
-
         console.log('Muezzin initialized successfully');
     } catch (error) {
         console.error('Initialization error:', error);
-        UI.showError('Failed to initialize: ' + error);
+        UI.showError('Failed to initialize: ' + (error?.message ?? error));
     }
 }
 
@@ -82,6 +92,8 @@ function setupEventListeners() {
     // Listen for prayer time updates
     listen('prayers-updated', async () => {
         console.log('Prayer times updated');
+        // The display refreshes itself
+        if (kioskActive) return;
         await loadPrayerTimes();
         UI.renderPrayerTimes(prayerTimes, nextPrayer);
     });
@@ -89,8 +101,23 @@ function setupEventListeners() {
     // Listen for navigation events
     listen('navigate-to-settings', () => {
         console.log('Navigate to settings');
+        if (kioskActive) return;
         UI.showSettingsPage();
     });
+
+    // Switch between the main window and the mosque display
+    PrayerAPI.onKioskMode(async (event) => {
+        kioskActive = event.payload;
+        if (kioskActive) {
+            clearInterval(countdownInterval);
+            await Kiosk.start();
+        } else {
+            Kiosk.stop();
+            await loadPrayerTimes();
+            UI.renderPrayerTimes(prayerTimes, nextPrayer);
+            startCountdown();
+        }
+    });
 }
 
 // Wait for DOM to be ready
@@ -107,6 +134,7 @@ window.muezzin = {
         UI.renderPrayerTimes(prayerTimes, nextPrayer);
     },
     settings: () => settings,
+    kiosk: () => PrayerAPI.enterKiosk(),
     prayerTimes: () => prayerTimes,
     nextPrayer: () => nextPrayer
 };
@@ -79,9 +79,12 @@ export function renderPrayerTimes(prayerTimes, nextPrayer) {
                     <button class="btn btn-primary me-2" onclick="window.muezzin.reload()">
                         <i class="fas fa-sync me-2"></i>Refresh
                     </button>
-                    <button class="btn btn-secondary" onclick="window.muezzin.settings()">
+                    <button class="btn btn-secondary me-2" onclick="window.muezzin.settings()">
                         <i class="fas fa-cog me-2"></i>Settings
                     </button>
+                    <button class="btn btn-secondary" onclick="window.muezzin.kiosk()">
+                        <i class="fas fa-tv me-2"></i>Mosque display
+                    </button>
                 </div>
             </div>
         </div>